@@ -0,0 +1,218 @@
+use std::io::{self, stdin, stdout, BufRead, Write};
+
+use blackjack::cards::Card;
+use blackjack::game::{AnyTable, TableAction};
+use blackjack::history::HistoryLog;
+use blackjack::round::{BlackjackTable, GameResult};
+
+use serde::{Deserialize, Serialize};
+
+/// A line of JSON written for the program playing the game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMessage {
+    /// One of the legal actions has to be chosen
+    Decision {
+        hand: Vec<Card>,
+        total: u8,
+        soft: bool,
+        /// The dealer's face up card, once the round has been dealt
+        upcard: Option<Card>,
+        legal_actions: Vec<TableAction>,
+        /// The Hi-Lo count of every card seen since the shoe was shuffled
+        running_count: i32,
+        true_count: f32,
+        cards_remaining: usize,
+        bankroll: f64,
+    },
+    RoundOver {
+        result: GameResult,
+        payout: f64,
+        hand: Vec<Card>,
+        total: u8,
+        dealer: Vec<Card>,
+        dealer_total: u8,
+        bankroll: f64,
+    },
+    /// The last line could not be used, and the decision is asked again
+    Error { message: String },
+}
+
+/// A line read from the program playing the game
+#[derive(Deserialize)]
+struct BotInput {
+    action: TableAction,
+}
+
+/// Play rounds with the decisions made by another program, until it sends
+/// `quit` or closes stdin, writing the history of each round to the log if one is supplied
+pub fn run_bot_protocol(cfg: crate::Config, history: Option<HistoryLog>) {
+    let table = AnyTable::from(BlackjackTable::default().with_bankroll(cfg.bankroll).reshuffle());
+
+    let table = match history {
+        Some(log) => table.with_history(log),
+        None => table,
+    };
+
+    if let Err(e) = play(table, stdin().lock(), stdout().lock()) {
+        eprintln!("The game stopped: {e}");
+    }
+}
+
+/// Returns the action on a line, either a bare action such as
+/// `hit` or an object such as `{"action": "hit"}`
+fn parse_action(line: &str) -> Result<TableAction, String> {
+    let line = line.trim();
+
+    let parsed = match line.starts_with('{') {
+        true => serde_json::from_str::<BotInput>(line).map(|input| input.action),
+        false => serde_json::from_value(serde_json::Value::String(line.to_lowercase())),
+    };
+
+    parsed.map_err(|_| format!("`{line}` is not an action"))
+}
+
+fn write_message<W: Write>(output: &mut W, message: &BotMessage) -> io::Result<()> {
+    serde_json::to_writer(&mut *output, message)?;
+    writeln!(output)?;
+
+    output.flush()
+}
+
+/// Take an action that needs no decision, stopping if the round cannot continue
+fn advance(table: AnyTable, action: TableAction) -> io::Result<AnyTable> {
+    table.apply(action).map_err(|e| io::Error::other(format!("The round could not continue: {e}")))
+}
+
+/// Play at the table with the actions read from `input`, writing each
+/// decision and result to `output`, and return the table once the input ends
+///
+/// The dealer's turn is played and each round is cleared away without
+/// asking, and the shoe is reshuffled once it needs to be
+fn play<R: BufRead, W: Write>(mut table: AnyTable, input: R, mut output: W) -> io::Result<AnyTable> {
+    let mut lines = input.lines();
+
+    loop {
+        table = match table {
+            AnyTable::NotStarted(t) if t.needs_reshuffle() => AnyTable::from(t.reshuffle()),
+            AnyTable::DealerTurn(_) => advance(table, TableAction::DealerPlay)?,
+            AnyTable::GameOver(ref t) => {
+                write_message(&mut output, &BotMessage::RoundOver {
+                    result: *t.result(),
+                    payout: t.payout(),
+                    hand: t.player_hand().cards().clone(),
+                    total: t.player_hand().total_value(),
+                    dealer: t.dealer_hand().cards().clone(),
+                    dealer_total: t.dealer_hand().total_value(),
+                    bankroll: t.session().bankroll,
+                })?;
+
+                advance(table, TableAction::NextRound)?
+            },
+            table => {
+                let count = table.running_count();
+
+                write_message(&mut output, &BotMessage::Decision {
+                    hand: table.player_hand().cards().clone(),
+                    total: table.player_hand().total_value(),
+                    soft: table.player_hand().is_soft(),
+                    upcard: table.showing_card().copied(),
+                    legal_actions: table.legal_actions(),
+                    running_count: count.running(),
+                    true_count: count.true_count(table.cards_remaining()),
+                    cards_remaining: table.cards_remaining(),
+                    bankroll: table.session().bankroll,
+                })?;
+
+                let Some(line) = lines.next().transpose()? else { return Ok(table) };
+
+                if line.trim().eq_ignore_ascii_case("quit") {
+                    return Ok(table);
+                }
+
+                match parse_action(&line) {
+                    Ok(action) => match table.apply(action) {
+                        Ok(table) => table,
+                        Err(e) => {
+                            let Some(table) = e.table else {
+                                return Err(io::Error::other(format!("The round could not continue: {}", e.error)));
+                            };
+
+                            write_message(&mut output, &BotMessage::Error { message: e.error.to_string() })?;
+                            *table
+                        },
+                    },
+                    Err(message) => {
+                        write_message(&mut output, &BotMessage::Error { message })?;
+                        table
+                    },
+                }
+            },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use blackjack::game::{AnyTable, TableAction};
+    use blackjack::round::BlackjackTable;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::{parse_action, play, BotMessage};
+
+    fn messages(output: &[u8]) -> Vec<BotMessage> {
+        String::from_utf8_lossy(output)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn actions_can_be_words_or_objects() {
+        assert_eq!(parse_action("hit"), Ok(TableAction::Hit));
+        assert_eq!(parse_action(" Stand \n"), Ok(TableAction::Stand));
+        assert_eq!(parse_action(r#"{"action": "deal"}"#), Ok(TableAction::Deal));
+        assert!(parse_action("split").is_err());
+    }
+
+    #[test]
+    fn decisions_are_written_and_read_as_lines() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let table = AnyTable::from(BlackjackTable::default().reshuffle_with(&mut rng));
+
+        // A natural ends the round at the deal, so a stand can be refused
+        let input = "split\n".to_string() + &"deal\nstand\n".repeat(20);
+        let mut output = Vec::new();
+
+        let table = play(table, Cursor::new(input), &mut output).unwrap();
+        let messages = messages(&output);
+
+        assert!(matches!(&messages[1], BotMessage::Error { .. }));
+
+        let mut rounds = 0;
+
+        for message in &messages {
+            match message {
+                BotMessage::Decision { hand, upcard, legal_actions, .. } if hand.is_empty() => {
+                    assert_eq!(*upcard, None);
+                    assert_eq!(legal_actions, &[TableAction::Reshuffle, TableAction::Deal]);
+                },
+                BotMessage::Decision { hand, total, upcard, legal_actions, .. } => {
+                    assert_eq!(hand.len(), 2);
+                    assert!(*total < 21);
+                    assert!(upcard.is_some());
+                    assert_eq!(legal_actions, &[TableAction::Hit, TableAction::Stand]);
+                },
+                BotMessage::RoundOver { .. } => rounds += 1,
+                BotMessage::Error { .. } => {},
+            }
+        }
+
+        assert!(rounds > 0);
+        assert_eq!(table.session().stats.rounds, rounds);
+        assert!(matches!(messages.last(), Some(BotMessage::Decision { .. })));
+    }
+}
@@ -1,31 +1,173 @@
-use std::io::{stdin, stdout, Write};
-
-use crate::game_rules::round::{
+use std::io::{self, stdin, stdout, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use blackjack::events::TableEvent;
+use blackjack::history::HistoryLog;
+use crate::files;
+use blackjack::round::{
     BlackjackTable,
+    GameOver,
     GameStartResult,
-    BlackjackRoundError,
+    NotStarted,
     PlayerTurnResult,
     DealerTurnResult,
     GameResult
 };
 
-pub fn run_game(cfg: crate::Config) {
+use clap::Args;
+
+#[derive(Args, Clone, Copy)]
+pub struct TelnetConfig {
+
+    /// Port to listen on. Only connections from this machine are accepted
+    #[arg(short, long, default_value_t = 2323)]
+    port: u16,
+}
+
+/// Play rounds from a single shoe until the player quits,
+/// writing the history of each round to the log if one is supplied
+pub fn run_game(cfg: crate::Config, history: Option<HistoryLog>) {
+
+    let table = BlackjackTable::default().with_bankroll(cfg.bankroll).reshuffle();
+
+    let table = match history {
+        Some(log) => table.with_history(log),
+        None => table,
+    };
+
+    let mut stats_error_shown = false;
+
+    let record_stats = |table: &BlackjackTable<GameOver>| {
+        if let Err(e) = files::record_round(table) {
+            if !stats_error_shown {
+                eprintln!("The lifetime statistics could not be saved: {e}");
+                stats_error_shown = true;
+            }
+        }
+    };
+
+    if let Err(e) = play(cfg, table, stdin().lock(), stdout(), record_stats) {
+        eprintln!("The game stopped: {e}");
+    }
+}
+
+/// Let players on this machine play the game with `nc localhost <port>`,
+/// each connection with a session of its own, until the process is stopped
+///
+/// Rounds played over a connection are not added to the lifetime statistics
+pub fn run_telnet(cfg: crate::Config, telnet_cfg: TelnetConfig) {
+    let listener = match TcpListener::bind(("127.0.0.1", telnet_cfg.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Unable to listen on port {}: {e}", telnet_cfg.port);
+            return;
+        },
+    };
+
+    println!("Play with `nc localhost {}`", telnet_cfg.port);
+
+    serve(listener, cfg);
+}
+
+/// Play a game over every connection to the listener, each on its own thread
+fn serve(listener: TcpListener, cfg: crate::Config) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Unable to accept a connection: {e}");
+                continue;
+            },
+        };
+
+        thread::spawn(move || {
+            let addr = stream.peer_addr().ok();
+
+            if let Err(e) = play_over(cfg, stream) {
+                eprintln!("The game with {} stopped: {e}", describe(addr));
+            }
+        });
+    }
+}
+
+fn describe(addr: Option<SocketAddr>) -> String {
+    addr.map_or_else(|| "a player".to_string(), |addr| addr.to_string())
+}
+
+fn play_over(cfg: crate::Config, stream: TcpStream) -> io::Result<()> {
+    let input = BufReader::new(stream.try_clone()?);
+    let table = BlackjackTable::default().with_bankroll(cfg.bankroll).reshuffle();
+
+    play(cfg, table, input, stream, |_| {})
+}
+
+/// Write a line for each time the shoe was shuffled since this was last called
+fn announce_shuffles<W: Write>(shuffles: &Receiver<()>, output: &mut W) -> io::Result<()> {
+    for () in shuffles.try_iter() {
+        writeln!(output, "Reshuffling the shoe...")?;
+    }
+
+    Ok(())
+}
+
+/// Play rounds at the table with the choices read from `input` and the game
+/// written to `output`, until the player quits or the input ends
+///
+/// `on_round` is called with every finished round
+fn play<R, W, F>(cfg: crate::Config, mut table: BlackjackTable<NotStarted>, mut input: R, mut output: W, mut on_round: F) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+    F: FnMut(&BlackjackTable<GameOver>),
+{
 
     let mut user_input_buf = String::new();
 
+    // Shuffles are announced as they happen, including when the
+    // discards have to be shuffled to finish a round
+    let (shuffled, shuffles) = mpsc::channel();
+
+    table.subscribe(move |event: &TableEvent| {
+        if let TableEvent::ShoeShuffled { .. } = event {
+            let _ = shuffled.send(());
+        }
+    });
+
+    let mut history_error_shown = false;
+
     'game_loop: loop {
 
-        print!("Enter [s] to start a new blackjack game or [q] to quit: ");
+        if let Some(e) = table.history().and_then(|h| h.error()) {
+            if !history_error_shown {
+                eprintln!("The hand history could not be written: {e}");
+                history_error_shown = true;
+            }
+        }
+
+        if table.session().stats.rounds > 0 {
+            writeln!(output, "Your bankroll is {:.1} units", table.session().bankroll)?;
+        }
+
+        if table.needs_reshuffle() {
+            table = table.reshuffle();
+        }
+
+        announce_shuffles(&shuffles, &mut output)?;
+
+        write!(output, "Enter [s] to start a new blackjack game or [q] to quit: ")?;
 
         'start_loop: loop {
-            stdout().flush().unwrap();
+            output.flush()?;
 
             user_input_buf.clear();
 
-            let choice = match stdin().read_line(&mut user_input_buf) {
+            let choice = match input.read_line(&mut user_input_buf) {
+                Ok(0) => break 'game_loop,
                 Ok(_) => user_input_buf.trim(),
                 Err(_) => {
-                    eprintln!("Encountered an error reading from stdin, stopping game...");
+                    eprintln!("Encountered an error reading the input, stopping game...");
 
                     break 'game_loop;
                 },
@@ -37,21 +179,24 @@ pub fn run_game(cfg: crate::Config) {
                 }
 
                 "q" | "Q" => {
-                    println!("Goodbye!");
+                    writeln!(output, "Goodbye!")?;
 
                     break 'game_loop;
                 }
 
                 s => {
-                    println!("Input '{}' was not recognized, please enter either 's' or 'q'", s)
+                    writeln!(output, "Input '{}' was not recognized, please enter either 's' or 'q'", s)?
                 }
             }
         }
 
-        println!("Starting a new blackjack game. Enter q to quit at any time.");
-        println!("Dealing...");
+        writeln!(output, "Starting a new blackjack game. Enter q to quit at any time.")?;
+        writeln!(output, "Dealing...")?;
+
+        let dealt = table.deal();
+        announce_shuffles(&shuffles, &mut output)?;
 
-        let mut players_turn = match BlackjackTable::default().shuffle().deal() {
+        let mut players_turn = match dealt {
             Ok(GameStartResult::Normal(s)) => s,
             Ok(GameStartResult::Natural(s)) => {
                 let result_str = match s.result() {
@@ -61,13 +206,16 @@ pub fn run_game(cfg: crate::Config) {
                     _ => "An unexpected game result occured"
                 };
 
-                println!("{}", result_str);
+                writeln!(output, "{}", result_str)?;
+
+                on_round(&s);
+                table = s.next_round();
 
                 continue;
             }
 
-            Err(BlackjackRoundError::DeckEmpty) => {
-                println!("There weren't enough cards in the deck to deal.");
+            Err(e) => {
+                writeln!(output, "The round could not be dealt: {e}")?;
                 break;
             },
         };
@@ -75,44 +223,45 @@ pub fn run_game(cfg: crate::Config) {
         match players_turn.showing_card() {
             Some(c) => {
                 if cfg.ascii {
-                    println!("The dealer is showing a {c:+#}")
+                    writeln!(output, "The dealer is showing a {c:+#}")?
                 } else {
-                    println!("The dealer is showing {c}")
+                    writeln!(output, "The dealer is showing {c}")?
                 }
             },
             None => unreachable!(),
         }
 
         if cfg.ascii {
-            println!("Your hand is:");
+            writeln!(output, "Your hand is:")?;
 
             for c in players_turn.player_hand().cards() {
-                println!("{c:+}")
+                writeln!(output, "{c:+}")?
             }
         } else {
-            print!("Your hand is: ");
+            write!(output, "Your hand is: ")?;
 
             for c in players_turn.player_hand().cards() {
-                print!("{c} ")
+                write!(output, "{c} ")?
             }
 
-            println!()
+            writeln!(output)?
         }
 
         let mut dealers_turn = 'player_turn_loop: loop {
 
 
-            println!("Your hand's value is {}", players_turn.player_hand().total_value());
+            writeln!(output, "Your hand's value is {}", players_turn.player_hand().total_value())?;
 
-            print!("Would you like to [h]it or [s]tand? > ");
+            write!(output, "Would you like to [h]it or [s]tand? > ")?;
 
-            stdout().flush().unwrap();
+            output.flush()?;
             user_input_buf.clear();
 
-            let choice = match stdin().read_line(&mut user_input_buf) {
+            let choice = match input.read_line(&mut user_input_buf) {
+                Ok(0) => break 'game_loop,
                 Ok(_) => user_input_buf.trim(),
                 Err(_) => {
-                    println!("Failed to read user input, exiting");
+                    writeln!(output, "Failed to read user input, exiting")?;
 
                     break 'game_loop;
                 },
@@ -120,19 +269,29 @@ pub fn run_game(cfg: crate::Config) {
 
             match choice {
                 "h" | "H" => {
-                    players_turn = match players_turn.hit() {
+                    let hit = players_turn.hit();
+                    announce_shuffles(&shuffles, &mut output)?;
+
+                    players_turn = match hit {
                         Ok(PlayerTurnResult::Hit(s)) => {
-                            println!("You drew a {}", s.player_hand().cards().last().unwrap());
+                            writeln!(output, "You drew a {}", s.player_hand().cards().last().unwrap())?;
 
                             s
                         },
                         Ok(PlayerTurnResult::Bust(s)) => {
 
-                            println!("You drew a {} and went bust!", s.player_hand().cards().last().unwrap());
+                            writeln!(output, "You drew a {} and went bust!", s.player_hand().cards().last().unwrap())?;
+
+                            on_round(&s);
+                            table = s.next_round();
 
                             continue 'game_loop;
                         }
-                        Err(_) => todo!(),
+                        Err(e) => {
+                            writeln!(output, "The round could not continue: {e}")?;
+
+                            break 'game_loop;
+                        },
                     };
                 }
 
@@ -141,65 +300,156 @@ pub fn run_game(cfg: crate::Config) {
                 }
 
                 "q" | "Q" => {
-                    println!("Goodbye!");
+                    writeln!(output, "Goodbye!")?;
 
                     break 'game_loop;
                 }
 
                 s => {
-                    println!("Unexpected response {s}")
+                    writeln!(output, "Unexpected response {s}")?
                 }
             };
 
         };
 
         if cfg.ascii {
-            println!("The dealer's hand is:");
+            writeln!(output, "The dealer's hand is:")?;
 
             for c in dealers_turn.dealer_hand().cards() {
-                println!("{c}");
+                writeln!(output, "{c}")?;
             }
         } else {
-            print!("The dealer's hand is: ");
+            write!(output, "The dealer's hand is: ")?;
 
             for c in dealers_turn.dealer_hand().cards() {
-                print!("{c} ")
+                write!(output, "{c} ")?
             }
 
-            println!()
+            writeln!(output)?
         }
 
         let round_result = loop {
 
-            match dealers_turn.hit() {
+            let cards_before_hit = dealers_turn.dealer_hand().cards().len();
+
+            let hit = dealers_turn.hit();
+            announce_shuffles(&shuffles, &mut output)?;
+
+            match hit {
                 Ok(DealerTurnResult::Hit(s)) => {
-                    println!("Dealer drew a {}", s.dealer_hand().cards().last().unwrap());
+                    writeln!(output, "Dealer drew a {}", s.dealer_hand().cards().last().unwrap())?;
 
                     dealers_turn = s;
                 },
                 Ok(DealerTurnResult::Stand(s)) => {
-                    println!("Dealer drew a {}", s.dealer_hand().cards().last().unwrap());
+                    if s.dealer_hand().cards().len() > cards_before_hit {
+                        writeln!(output, "Dealer drew a {}", s.dealer_hand().cards().last().unwrap())?;
+                    }
 
                     break s;
                 },
-                Err(_) => todo!(),
+                Err(e) => {
+                    writeln!(output, "The round could not continue: {e}")?;
+
+                    break 'game_loop;
+                },
             }
 
         };
 
-        println!(
+        writeln!(
+            output,
             "The dealer's score is {} and your score is {}",
             round_result.dealer_hand().total_value(),
             round_result.player_hand().total_value()
-        );
+        )?;
 
         match round_result.result() {
-            GameResult::DealerWin => println!("The dealer wins"),
-            GameResult::PlayerWin => println!("You win!"),
-            GameResult::DealerBust => println!("The dealer went bust! You win!"),
-            GameResult::PlayerBust => println!("You went bust! The dealer wins"),
-            GameResult::StandOff => println!("You and the dealer are in a stand off!"),
+            GameResult::DealerWin => writeln!(output, "The dealer wins")?,
+            GameResult::PlayerWin => writeln!(output, "You win!")?,
+            GameResult::DealerBust => writeln!(output, "The dealer went bust! You win!")?,
+            GameResult::PlayerBust => writeln!(output, "You went bust! The dealer wins")?,
+            GameResult::StandOff => writeln!(output, "You and the dealer are in a stand off!")?,
         }
 
+        on_round(&round_result);
+        table = round_result.next_round();
+    }
+
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use blackjack::round::GameResult;
+
+    use crate::Config;
+    use crate::testing::stacked;
+
+    use super::{play, serve};
+
+    const CFG: Config = Config {
+        ascii: false,
+        cli: true,
+        show_ev: false,
+        hints: false,
+        bot_protocol: false,
+        bankroll: 100.0,
+    };
+
+    #[test]
+    fn rounds_are_played_from_the_input() {
+        // The player hits 12 to 20 and the dealer stands on 18
+        let table = stacked(&["10H", "10S", "2D", "8C", "8H"]);
+
+        let mut output = Vec::new();
+        let mut rounds = Vec::new();
+
+        play(CFG, table, Cursor::new("x\ns\nh\ns\nq\n"), &mut output, |t| rounds.push(*t.result())).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("Input 'x' was not recognized"));
+        assert!(output.contains("Your hand's value is 12"));
+        assert!(output.contains("Your hand's value is 20"));
+        assert!(output.contains("The dealer's score is 18 and your score is 20"));
+        assert!(output.contains("Your bankroll is 101.0 units"));
+        assert!(output.ends_with("Goodbye!\n"));
+
+        assert_eq!(rounds, [GameResult::PlayerWin]);
+    }
+
+    #[test]
+    fn the_game_ends_with_the_input() {
+        let table = stacked(&["10H", "10S", "2D", "8C"]);
+        let mut output = Vec::new();
+
+        play(CFG, table, Cursor::new("s\n"), &mut output, |_| {}).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with("Would you like to [h]it or [s]tand? > "), "{output}");
+    }
+
+    #[test]
+    fn each_connection_plays_its_own_game() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || serve(listener, CFG));
+
+        let connections: Vec<TcpStream> = (0..2).map(|_| TcpStream::connect(addr).unwrap()).collect();
+
+        for mut stream in connections {
+            writeln!(stream, "q").unwrap();
+
+            let mut output = String::new();
+            stream.read_to_string(&mut output).unwrap();
+
+            assert_eq!(output, "Enter [s] to start a new blackjack game or [q] to quit: Goodbye!\n");
+        }
     }
 }
@@ -0,0 +1,31 @@
+use std::io;
+use std::path::PathBuf;
+
+use blackjack::lifetime::LifetimeStats;
+use blackjack::round::{BlackjackTable, GameOver};
+
+/// Returns the directory the game keeps its files in,
+/// within the user's data directory
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("blackjack"))
+}
+
+/// Returns the file the TUI saves its session to when it is quit,
+/// in the user's data directory
+pub fn default_save_path() -> Option<PathBuf> {
+    data_dir().map(|d| d.join("session.json"))
+}
+
+/// Returns the file the lifetime statistics are kept in, in the user's data directory
+pub fn stats_path() -> Option<PathBuf> {
+    data_dir().map(|d| d.join("stats.json"))
+}
+
+/// Add a finished round to the lifetime statistics,
+/// doing nothing if the user has no data directory
+pub fn record_round(table: &BlackjackTable<GameOver>) -> io::Result<()> {
+    match stats_path() {
+        Some(path) => LifetimeStats::update(path, table),
+        None => Ok(()),
+    }
+}
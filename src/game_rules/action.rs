@@ -0,0 +1,87 @@
+use std::fmt::Display;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::strategy::Action;
+
+/// Everything that can be done at a table, by the player or the dealer
+///
+/// The player's moves are the [Action]s the table can play,
+/// and are converted to and from them with [TryFrom]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TableAction {
+    /// Shuffle the discards back into the shoe before the next deal
+    Reshuffle,
+    /// Deal the player and the dealer two cards each
+    Deal,
+    /// The player draws a card
+    Hit,
+    /// The player ends their turn
+    Stand,
+    /// The dealer draws their next card, or stands once they have to
+    DealerPlay,
+    /// Clear the hands from a finished round
+    NextRound,
+}
+
+impl Display for TableAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            Self::Reshuffle => "reshuffle",
+            Self::Deal => "deal",
+            Self::Hit => "hit",
+            Self::Stand => "stand",
+            Self::DealerPlay => "dealer play",
+            Self::NextRound => "next round",
+        };
+
+        write!(f, "{action}")
+    }
+}
+
+impl TryFrom<Action> for TableAction {
+    /// The action, which cannot be played at a table
+    type Error = Action;
+
+    fn try_from(action: Action) -> Result<Self, Self::Error> {
+        match action {
+            Action::Hit => Ok(Self::Hit),
+            Action::Stand => Ok(Self::Stand),
+            Action::Double | Action::Split | Action::Surrender => Err(action),
+        }
+    }
+}
+
+impl TryFrom<TableAction> for Action {
+    /// The action, which is not one of the player's moves
+    type Error = TableAction;
+
+    fn try_from(action: TableAction) -> Result<Self, Self::Error> {
+        match action {
+            TableAction::Hit => Ok(Self::Hit),
+            TableAction::Stand => Ok(Self::Stand),
+            TableAction::Reshuffle | TableAction::Deal | TableAction::DealerPlay | TableAction::NextRound => Err(action),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::strategy::Action;
+
+    use super::TableAction;
+
+    #[test]
+    fn player_moves_convert_to_and_from_actions() {
+        for (action, table_action) in [(Action::Hit, TableAction::Hit), (Action::Stand, TableAction::Stand)] {
+            assert_eq!(TableAction::try_from(action), Ok(table_action));
+            assert_eq!(Action::try_from(table_action), Ok(action));
+        }
+
+        assert_eq!(TableAction::try_from(Action::Double), Err(Action::Double));
+        assert_eq!(Action::try_from(TableAction::DealerPlay), Err(TableAction::DealerPlay));
+    }
+}
@@ -1,7 +1,10 @@
 
 use std::fmt::Display;
+use std::str::FromStr;
 
 use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 pub trait AsPrettyString {
     fn as_pretty_string(&self) -> String;
@@ -53,11 +56,34 @@ pub enum CardFace {
     Ace
 }
 
-#[derive(Debug)]
-pub struct CardValueError(u8);
+/// An error from building or parsing a [Card], [CardFace] or [CardSuit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardError {
+    /// A face value outside of 1 (Ace) to 13 (King)
+    Value(u8),
+    /// A string that does not name a face
+    Face(String),
+    /// A string that does not name a suit
+    Suit(String),
+    /// A string that does not have both a face and a suit
+    Card(String),
+}
+
+impl Display for CardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardError::Value(v) => write!(f, "{v} is not a valid card value, expected 1 (Ace) to 13 (King)"),
+            CardError::Face(s) => write!(f, "'{s}' is not a card face, expected A, 2 to 10, T, J, Q, K or a name such as Ace"),
+            CardError::Suit(s) => write!(f, "'{s}' is not a suit, expected H, D, S, C, a symbol such as ♠ or a name such as Spades"),
+            CardError::Card(s) => write!(f, "'{s}' is not a card, expected a face and a suit such as AS, A♠ or Ace of Spades"),
+        }
+    }
+}
+
+impl std::error::Error for CardError {}
 
 impl TryFrom<u8> for CardFace {
-    type Error = CardValueError;
+    type Error = CardError;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
@@ -66,7 +92,7 @@ impl TryFrom<u8> for CardFace {
             11 => Ok(Self::Jack),
             12 => Ok(Self::Queen),
             13 => Ok(Self::King),
-            e => Err(CardValueError(e))
+            e => Err(CardError::Value(e))
         }
     }
 }
@@ -120,7 +146,8 @@ impl Display for CardFace {
 /// let card = Card::new(CardFace::try_from(4).unwrap(), CardSuit::Clubs);
 /// assert_eq!(card.face(), &CardFace::Number(4));
 /// assert_eq!(card.suit(), &CardSuit::Clubs);
-/// assert_eq!(card.to_string(), "4 of Clubs".to_string());
+/// assert_eq!(card.to_string(), "4♣");
+/// assert_eq!("4C".parse(), Ok(card));
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Card {
@@ -134,7 +161,7 @@ impl Card {
     }
 
     /// Generate a card with a random face and suit
-    pub fn random() -> Result<Self, CardValueError> {
+    pub fn random() -> Result<Self, CardError> {
         let mut rng = rand::thread_rng();
 
         let suit = match rng.gen_range(1..=4) {
@@ -142,7 +169,7 @@ impl Card {
             2 => CardSuit::Diamonds,
             3 => CardSuit::Spades,
             4 => CardSuit::Clubs,
-            e => return Err(CardValueError(e))
+            e => return Err(CardError::Value(e))
         };
 
         let face = CardFace::try_from(rng.gen_range(1..=12))?;
@@ -178,9 +205,200 @@ impl Display for Card {
     }
 }
 
+impl FromStr for CardSuit {
+    type Err = CardError;
+
+    /// Parse a suit from its initial, symbol or name, in any case,
+    /// e.g. `"S"`, `"♠"`, `"♤"`, `"spade"` or `"Spades"`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "h" | "♥" | "♡" | "heart" | "hearts" => Ok(CardSuit::Hearts),
+            "d" | "♦" | "♢" | "diamond" | "diamonds" => Ok(CardSuit::Diamonds),
+            "s" | "♠" | "♤" | "spade" | "spades" => Ok(CardSuit::Spades),
+            "c" | "♣" | "♧" | "club" | "clubs" => Ok(CardSuit::Clubs),
+            _ => Err(CardError::Suit(s.trim().to_string())),
+        }
+    }
+}
+
+impl FromStr for CardFace {
+    type Err = CardError;
+
+    /// Parse a face from its short form or name, in any case,
+    /// e.g. `"A"`, `"ace"`, `"10"`, `"T"`, `"ten"` or `"King"`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let face = match s.trim().to_lowercase().as_str() {
+            "a" | "ace" => CardFace::Ace,
+            "k" | "king" => CardFace::King,
+            "q" | "queen" => CardFace::Queen,
+            "j" | "jack" | "knave" => CardFace::Jack,
+            "t" | "ten" => CardFace::Number(10),
+            "two" => CardFace::Number(2),
+            "three" => CardFace::Number(3),
+            "four" => CardFace::Number(4),
+            "five" => CardFace::Number(5),
+            "six" => CardFace::Number(6),
+            "seven" => CardFace::Number(7),
+            "eight" => CardFace::Number(8),
+            "nine" => CardFace::Number(9),
+            n => match n.parse::<u8>() {
+                Ok(v @ 2..=10) => CardFace::Number(v),
+                _ => return Err(CardError::Face(s.trim().to_string())),
+            },
+        };
+
+        Ok(face)
+    }
+}
+
+impl FromStr for Card {
+    type Err = CardError;
+
+    /// Parse a card written in any of the forms it is displayed in, e.g. `"A♠"`,
+    /// `"AS"` or `"Ace of Spades"`, as well as `"T"` for a ten, lowercase and
+    /// the other names accepted for a [CardFace] and [CardSuit]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+
+        let (face, suit) = match words.as_slice() {
+            [face, of, suit] if of.eq_ignore_ascii_case("of") => (*face, *suit),
+            _ => {
+                let trimmed = s.trim();
+                let suit_len = trimmed.chars().next_back().map_or(0, char::len_utf8);
+
+                trimmed.split_at(trimmed.len() - suit_len)
+            },
+        };
+
+        if face.trim().is_empty() {
+            return Err(CardError::Card(s.to_string()));
+        }
+
+        let face = face.parse()?;
+        let suit = suit.parse()?;
+
+        Ok(Card::new(face, suit))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl CardSuit {
+    const ALL: [CardSuit; 4] = [CardSuit::Hearts, CardSuit::Diamonds, CardSuit::Spades, CardSuit::Clubs];
+
+    fn index(&self) -> u8 {
+        Self::ALL.iter().position(|suit| suit == self).unwrap() as u8
+    }
+}
+
+#[cfg(feature = "serde")]
+impl CardFace {
+    /// Returns the face's rank from Ace (1) to King (13), the inverse of [CardFace::try_from]
+    fn rank(&self) -> u8 {
+        match self {
+            CardFace::Ace => 1,
+            CardFace::Number(v) => *v,
+            CardFace::Jack => 11,
+            CardFace::Queen => 12,
+            CardFace::King => 13,
+        }
+    }
+
+}
+
+#[cfg(feature = "serde")]
+impl Card {
+    /// Returns the position of the card in a sorted deck, from 0 to 51
+    fn index(&self) -> u8 {
+        self.suit.index() * 13 + self.face.rank() - 1
+    }
+
+    fn from_index(index: u8) -> Option<Self> {
+        let suit = *CardSuit::ALL.get(usize::from(index / 13))?;
+        let face = CardFace::try_from(index % 13 + 1).ok()?;
+
+        Some(Self { suit, face })
+    }
+}
+
+// Human readable formats such as JSON get the short form of cards, faces and
+// suits, e.g. `"10H"`, `"10"` and `"H"`. Compact formats get a single byte:
+// the suit's position in Hearts, Diamonds, Spades, Clubs, the face's rank, or
+// the card's position in a deck sorted by suit then rank
+
+#[cfg(feature = "serde")]
+impl Serialize for CardSuit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&format_args!("{self:#}"))
+        } else {
+            serializer.serialize_u8(self.index())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for CardSuit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+        } else {
+            let index = u8::deserialize(deserializer)?;
+
+            Self::ALL.get(usize::from(index)).copied()
+                .ok_or_else(|| de::Error::custom(format!("{index} is not a suit")))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for CardFace {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u8(self.rank())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for CardFace {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+        } else {
+            Self::try_from(u8::deserialize(deserializer)?).map_err(de::Error::custom)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&format_args!("{self:#}"))
+        } else {
+            serializer.serialize_u8(self.index())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+        } else {
+            let index = u8::deserialize(deserializer)?;
+
+            Self::from_index(index).ok_or_else(|| de::Error::custom(format!("{index} is not a card")))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Card, CardFace, CardSuit};
+    use super::{Card, CardError, CardFace, CardSuit};
 
     #[test]
     fn card_strings() {
@@ -241,4 +459,83 @@ mod tests {
             Card::random().expect("Random card generation failed");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parse_cards() {
+        let ace_of_spades = Card::new(CardFace::Ace, CardSuit::Spades);
+
+        for s in ["A♠", "AS", "Ace of Spades", "as", "ace of spades", " a♤ ", "Ace of spade", "A s"] {
+            assert_eq!(s.parse::<Card>(), Ok(ace_of_spades), "{s}");
+        }
+
+        let ten_of_hearts = Card::new(CardFace::Number(10), CardSuit::Hearts);
+
+        for s in ["10♥", "10H", "10 of Hearts", "TH", "th", "Ten of Hearts", "t♡"] {
+            assert_eq!(s.parse::<Card>(), Ok(ten_of_hearts), "{s}");
+        }
+
+        assert_eq!("Queen of Diamonds".parse(), Ok(Card::new(CardFace::Queen, CardSuit::Diamonds)));
+        assert_eq!("7c".parse(), Ok(Card::new(CardFace::Number(7), CardSuit::Clubs)));
+
+        // Every format the card is displayed in can be read back
+        for suit in [CardSuit::Hearts, CardSuit::Diamonds, CardSuit::Spades, CardSuit::Clubs] {
+            for rank in 1..=13 {
+                let card = Card::new(CardFace::try_from(rank).unwrap(), suit);
+
+                assert_eq!(card.to_string().parse(), Ok(card));
+                assert_eq!(format!("{card:#}").parse(), Ok(card));
+                assert_eq!(format!("{card:+}").parse(), Ok(card));
+            }
+        }
+
+        assert_eq!("1H".parse::<Card>(), Err(CardError::Face("1".into())));
+        assert_eq!("AX".parse::<Card>(), Err(CardError::Suit("X".into())));
+        assert_eq!("Ace of Swords".parse::<Card>(), Err(CardError::Suit("Swords".into())));
+        assert_eq!("S".parse::<Card>(), Err(CardError::Card("S".into())));
+        assert_eq!("".parse::<Card>(), Err(CardError::Card("".into())));
+
+        assert_eq!(CardFace::try_from(14), Err(CardError::Value(14)));
+        assert_eq!(CardError::Suit("X".into()).to_string(), "'X' is not a suit, expected H, D, S, C, a symbol such as ♠ or a name such as Spades");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips() {
+        let suits = [CardSuit::Hearts, CardSuit::Diamonds, CardSuit::Spades, CardSuit::Clubs];
+
+        for suit in suits {
+            for rank in 1..=13 {
+                let face = CardFace::try_from(rank).unwrap();
+                let card = Card::new(face, suit);
+
+                let json = serde_json::to_string(&card).unwrap();
+                assert_eq!(json, format!("\"{card:#}\""));
+                assert_eq!(serde_json::from_str::<Card>(&json).unwrap(), card);
+
+                let bytes = bincode::serialize(&card).unwrap();
+                assert_eq!(bytes.len(), 1);
+                assert_eq!(bincode::deserialize::<Card>(&bytes).unwrap(), card);
+
+                let json = serde_json::to_string(&face).unwrap();
+                assert_eq!(serde_json::from_str::<CardFace>(&json).unwrap(), face);
+                assert_eq!(bincode::deserialize::<CardFace>(&bincode::serialize(&face).unwrap()).unwrap(), face);
+            }
+
+            let json = serde_json::to_string(&suit).unwrap();
+            assert_eq!(json, format!("\"{suit:#}\""));
+            assert_eq!(serde_json::from_str::<CardSuit>(&json).unwrap(), suit);
+            assert_eq!(bincode::deserialize::<CardSuit>(&bincode::serialize(&suit).unwrap()).unwrap(), suit);
+        }
+
+        assert_eq!(serde_json::to_string(&Card::new(CardFace::Number(10), CardSuit::Hearts)).unwrap(), "\"10H\"");
+        assert_eq!(serde_json::to_string(&CardFace::Queen).unwrap(), "\"Q\"");
+
+        for bad in ["\"1H\"", "\"11S\"", "\"AX\"", "\"A\"", "\"\""] {
+            assert!(serde_json::from_str::<Card>(bad).is_err(), "{bad} was parsed");
+        }
+
+        assert!(bincode::deserialize::<Card>(&[52]).is_err());
+        assert!(bincode::deserialize::<CardFace>(&[14]).is_err());
+        assert!(bincode::deserialize::<CardSuit>(&[4]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use super::cards::Card;
+
+/// The number of cards of each blackjack value in a shoe.
+///
+/// Tens, Jacks, Queens and Kings all share the value 10,
+/// and Aces are stored with the value 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Composition([u16; 10]);
+
+impl Composition {
+    /// The composition of a full shoe of `decks` standard decks
+    pub fn from_decks(decks: u8) -> Self {
+        let per_rank = 4 * u16::from(decks);
+
+        let mut counts = [per_rank; 10];
+        counts[9] = 4 * per_rank;
+
+        Self(counts)
+    }
+
+    /// The composition of the supplied cards
+    pub fn from_cards<'a, I: IntoIterator<Item = &'a Card>>(cards: I) -> Self {
+        let mut comp = Self([0; 10]);
+
+        for card in cards {
+            comp.add(u8::from(*card));
+        }
+
+        comp
+    }
+
+    /// Returns the number of cards with the given value (1 to 10)
+    pub fn count(&self, value: u8) -> u16 {
+        match value {
+            1..=10 => self.0[usize::from(value - 1)],
+            _ => 0,
+        }
+    }
+
+    /// Returns the total number of cards
+    pub fn total(&self) -> u32 {
+        self.0.iter().map(|c| u32::from(*c)).sum()
+    }
+
+    /// Returns the chance of the next card drawn having the given value
+    pub fn probability(&self, value: u8) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => f64::from(self.count(value)) / f64::from(total),
+        }
+    }
+
+    /// Add a card with the given value
+    pub fn add(&mut self, value: u8) {
+        if let 1..=10 = value {
+            self.0[usize::from(value - 1)] += 1;
+        }
+    }
+
+    /// Remove a card with the given value,
+    /// returning false if there were none left to remove
+    pub fn remove(&mut self, value: u8) -> bool {
+        match value {
+            1..=10 if self.0[usize::from(value - 1)] > 0 => {
+                self.0[usize::from(value - 1)] -= 1;
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::cards::{Card, CardFace, CardSuit};
+    use crate::game_rules::deck::Deck;
+
+    use super::Composition;
+
+    #[test]
+    fn full_shoe() {
+        let comp = Composition::from_decks(2);
+
+        assert_eq!(comp, Deck::new(2).composition());
+
+        assert_eq!(comp.total(), 104);
+        assert_eq!(comp.count(1), 8);
+        assert_eq!(comp.count(10), 32);
+        assert_eq!(comp.probability(10), 32.0 / 104.0);
+    }
+
+    #[test]
+    fn add_and_remove() {
+        let mut comp = Composition::from_cards(&[
+            Card::new(CardFace::King, CardSuit::Hearts),
+            Card::new(CardFace::try_from(10).unwrap(), CardSuit::Clubs),
+            Card::new(CardFace::Ace, CardSuit::Spades),
+        ]);
+
+        assert_eq!(comp.count(10), 2);
+        assert!(comp.remove(1));
+        assert!(!comp.remove(1));
+        assert!(!comp.remove(11));
+
+        comp.add(5);
+        assert_eq!(comp.total(), 3);
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::cards::{Card, CardFace};
+use super::events::{DealerAction, TableEvent, TableObserver};
+
+/// Returns the Hi-Lo tag of a card
+///
+/// Low cards (2-6) are worth +1, neutral cards (7-9) are worth 0,
+/// and tens and aces are worth -1
+pub fn hi_lo_value(card: &Card) -> i32 {
+    match card.face() {
+        CardFace::Number(2..=6) => 1,
+        CardFace::Number(7..=9) => 0,
+        _ => -1,
+    }
+}
+
+/// A running card count using the Hi-Lo system.
+///
+/// Every card that becomes visible should be passed to [RunningCount::observe].
+/// The true count can then be derived from the number of cards
+/// that are still left in the shoe.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RunningCount {
+    count: i32,
+}
+
+impl RunningCount {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the count with a card that has been seen
+    pub fn observe(&mut self, card: &Card) {
+        self.count += hi_lo_value(card);
+    }
+
+    /// Returns the current running count
+    pub fn running(&self) -> i32 {
+        self.count
+    }
+
+    /// Returns the running count divided by the number of decks
+    /// left in the shoe
+    ///
+    /// When fewer than half a deck remains, the remaining cards are
+    /// treated as half a deck to keep the result bounded
+    pub fn true_count(&self, cards_remaining: usize) -> f32 {
+        let decks_remaining = (cards_remaining as f32 / 52.0).max(0.5);
+
+        self.count as f32 / decks_remaining
+    }
+}
+
+/// Keeps the count from the cards shown at a table,
+/// starting again whenever the shoe is shuffled
+impl TableObserver for RunningCount {
+    fn on_event(&mut self, event: &TableEvent) {
+        match event {
+            TableEvent::ShoeShuffled { .. } => *self = Self::new(),
+            TableEvent::CardDealt { card: Some(card), .. } => self.observe(card),
+            TableEvent::DealerAction { action: DealerAction::RevealHoleCard { card } } => self.observe(card),
+            _ => {},
+        }
+    }
+}
+
+/// A card counting system, giving a tag to each card value
+///
+/// Tags are listed for Ace, 2, 3, ... 9 and ten-valued cards, in that order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountingSystem {
+    tags: [i32; 10],
+}
+
+impl CountingSystem {
+    pub fn new(tags: [i32; 10]) -> Self {
+        Self { tags }
+    }
+
+    pub fn hi_lo() -> Self {
+        Self::new([-1, 1, 1, 1, 1, 1, 0, 0, 0, -1])
+    }
+
+    pub fn hi_opt_1() -> Self {
+        Self::new([0, 0, 1, 1, 1, 1, 0, 0, 0, -1])
+    }
+
+    pub fn hi_opt_2() -> Self {
+        Self::new([0, 1, 1, 2, 2, 1, 1, 0, 0, -2])
+    }
+
+    pub fn omega_2() -> Self {
+        Self::new([0, 1, 1, 2, 2, 2, 1, 0, -1, -2])
+    }
+
+    pub fn zen() -> Self {
+        Self::new([-1, 1, 1, 2, 2, 2, 1, 0, 0, -2])
+    }
+
+    /// Returns the tag of a card in this system
+    pub fn tag(&self, card: &Card) -> i32 {
+        self.tags[usize::from(u8::from(*card)) - 1]
+    }
+
+    /// Returns the running count of the supplied cards
+    pub fn count<'a, I: IntoIterator<Item = &'a Card>>(&self, cards: I) -> RunningCount {
+        RunningCount { count: cards.into_iter().map(|c| self.tag(c)).sum() }
+    }
+}
+
+impl Default for CountingSystem {
+    fn default() -> Self {
+        Self::hi_lo()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountingSystemError(String);
+
+impl Display for CountingSystemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is not a known counting system or a list of ten tags for A, 2, ..., 9, 10",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for CountingSystemError {}
+
+impl FromStr for CountingSystem {
+    type Err = CountingSystemError;
+
+    /// Parse a counting system by name (`hi-lo`, `hi-opt-1`, `hi-opt-2`,
+    /// `omega-2` or `zen`) or as ten comma separated tags
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || CountingSystemError(s.to_string());
+
+        match s.to_ascii_lowercase().as_str() {
+            "hi-lo" | "hilo" => return Ok(Self::hi_lo()),
+            "hi-opt-1" | "hi-opt-i" => return Ok(Self::hi_opt_1()),
+            "hi-opt-2" | "hi-opt-ii" => return Ok(Self::hi_opt_2()),
+            "omega-2" | "omega-ii" => return Ok(Self::omega_2()),
+            "zen" => return Ok(Self::zen()),
+            _ => {},
+        }
+
+        let tags = s.split(',')
+            .map(|t| t.trim().parse::<i32>().map_err(|_| err()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::new(tags.try_into().map_err(|_| err())?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::cards::{Card, CardFace, CardSuit};
+
+    use super::{hi_lo_value, CountingSystem, RunningCount};
+
+    #[test]
+    fn hi_lo_tags() {
+        let tag = |v| hi_lo_value(&Card::new(CardFace::try_from(v).unwrap(), CardSuit::Spades));
+
+        assert_eq!(tag(1), -1);
+        assert_eq!(tag(2), 1);
+        assert_eq!(tag(6), 1);
+        assert_eq!(tag(7), 0);
+        assert_eq!(tag(9), 0);
+        assert_eq!(tag(10), -1);
+        assert_eq!(tag(13), -1);
+    }
+
+    #[test]
+    fn running_and_true_count() {
+        let mut count = RunningCount::new();
+
+        for v in [2, 3, 4, 5, 10, 7] {
+            count.observe(&Card::new(CardFace::try_from(v).unwrap(), CardSuit::Hearts));
+        }
+
+        assert_eq!(count.running(), 3);
+        assert_eq!(count.true_count(156), 1.0);
+        assert_eq!(count.true_count(0), 6.0);
+    }
+
+    #[test]
+    fn counting_systems() {
+        let cards: Vec<Card> = (1..=13)
+            .map(|v| Card::new(CardFace::try_from(v).unwrap(), CardSuit::Clubs))
+            .collect();
+
+        for system in ["hi-lo", "hi-opt-1", "hi-opt-2", "omega-2", "zen"] {
+            let system: CountingSystem = system.parse().unwrap();
+
+            // Every built-in system is balanced over a full deck
+            assert_eq!(system.count(&cards).running(), 0);
+        }
+
+        for card in &cards {
+            assert_eq!(CountingSystem::hi_lo().tag(card), hi_lo_value(card));
+        }
+
+        assert_eq!("-1,1,1,1,1,1,0,0,0,-1".parse::<CountingSystem>(), Ok(CountingSystem::hi_lo()));
+        assert!("1,2,3".parse::<CountingSystem>().is_err());
+        assert!("wong".parse::<CountingSystem>().is_err());
+    }
+}
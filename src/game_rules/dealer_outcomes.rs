@@ -0,0 +1,175 @@
+use super::composition::Composition;
+use super::rules::Rules;
+
+/// Index of each final dealer result in a [Distribution]
+pub(super) const BUST: usize = 5;
+pub(super) const BLACKJACK: usize = 6;
+
+/// Probabilities of the dealer finishing on 17, 18, 19, 20, 21,
+/// going bust, or having a natural, in that order
+pub(super) type Distribution = [f64; 7];
+
+/// A total while drawing cards, tracking whether
+/// an Ace could be counted as 11
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct Total {
+    hard: u8,
+    ace: bool,
+}
+
+impl Total {
+    pub(super) fn of_card(value: u8) -> Self {
+        Self { hard: value, ace: value == 1 }
+    }
+
+    pub(super) fn add(self, value: u8) -> Self {
+        Self { hard: self.hard + value, ace: self.ace || value == 1 }
+    }
+
+    pub(super) fn is_soft(self) -> bool {
+        self.ace && self.hard + 10 <= 21
+    }
+
+    pub(super) fn value(self) -> u8 {
+        if self.is_soft() { self.hard + 10 } else { self.hard }
+    }
+}
+
+/// The chance of each way the dealer's hand can finish
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DealerOutcomes {
+    /// Chance of finishing on 17, 18, 19, 20 and 21 (without a natural)
+    pub totals: [f64; 5],
+    pub blackjack: f64,
+    pub bust: f64,
+}
+
+impl DealerOutcomes {
+    /// Returns the chance of the dealer finishing on the given total
+    pub fn total(&self, total: u8) -> f64 {
+        match total {
+            17..=21 => self.totals[usize::from(total - 17)],
+            _ => 0.0,
+        }
+    }
+}
+
+/// Computes the exact chance of each final result of the dealer's hand
+///
+/// `shoe` is the composition of the cards the dealer will draw from, with
+/// the upcard already removed. When the dealer peeks at their hole card,
+/// the round only continues if they do not have a natural, so the results
+/// are conditional on the dealer not having one.
+pub fn dealer_outcomes(shoe: &Composition, upcard: u8, rules: &Rules) -> DealerOutcomes {
+    let mut dist = distribution(&mut shoe.clone(), Total::of_card(upcard), 1, rules.dealer_hits_soft_17);
+
+    if rules.dealer_peeks && dist[BLACKJACK] < 1.0 {
+        let no_natural = 1.0 - dist[BLACKJACK];
+
+        dist[BLACKJACK] = 0.0;
+
+        for p in dist.iter_mut() {
+            *p /= no_natural;
+        }
+    }
+
+    DealerOutcomes {
+        totals: [dist[0], dist[1], dist[2], dist[3], dist[4]],
+        blackjack: dist[BLACKJACK],
+        bust: dist[BUST],
+    }
+}
+
+/// Recursively compute the chance of each final dealer result
+///
+/// If the shoe runs out before the dealer finishes,
+/// that branch is left out of the distribution
+pub(super) fn distribution(shoe: &mut Composition, total: Total, cards: u8, hits_soft_17: bool) -> Distribution {
+    let mut dist = [0.0; 7];
+    let value = total.value();
+
+    if cards == 2 && value == 21 {
+        dist[BLACKJACK] = 1.0;
+        return dist;
+    }
+
+    if value > 21 {
+        dist[BUST] = 1.0;
+        return dist;
+    }
+
+    let stands = cards >= 2 && (value > 17 || (value == 17 && !(hits_soft_17 && total.is_soft())));
+
+    if stands {
+        dist[usize::from(value - 17)] = 1.0;
+        return dist;
+    }
+
+    for card in 1..=10 {
+        let p = shoe.probability(card);
+
+        if p == 0.0 {
+            continue;
+        }
+
+        shoe.remove(card);
+        let next = distribution(shoe, total.add(card), cards + 1, hits_soft_17);
+        shoe.add(card);
+
+        for (d, n) in dist.iter_mut().zip(next) {
+            *d += p * n;
+        }
+    }
+
+    dist
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::composition::Composition;
+    use crate::game_rules::rules::Rules;
+
+    use super::dealer_outcomes;
+
+    fn shoe_without(upcard: u8) -> Composition {
+        let mut shoe = Composition::from_decks(6);
+        shoe.remove(upcard);
+
+        shoe
+    }
+
+    #[test]
+    fn outcomes_sum_to_one() {
+        for upcard in 1..=10 {
+            let o = dealer_outcomes(&shoe_without(upcard), upcard, &Rules::default());
+            let sum: f64 = o.totals.iter().sum::<f64>() + o.blackjack + o.bust;
+
+            assert!((sum - 1.0).abs() < 1e-9, "upcard {upcard} sums to {sum}");
+        }
+    }
+
+    #[test]
+    fn known_bust_rates() {
+        let rules = Rules::default();
+
+        let six = dealer_outcomes(&shoe_without(6), 6, &rules);
+        assert!((six.bust - 0.42).abs() < 0.01);
+
+        let ten = dealer_outcomes(&shoe_without(10), 10, &rules);
+        assert_eq!(ten.blackjack, 0.0);
+        assert!((ten.bust - 0.23).abs() < 0.01);
+
+        let no_peek = Rules { dealer_peeks: false, ..rules };
+        let ace = dealer_outcomes(&shoe_without(1), 1, &no_peek);
+        assert!((ace.blackjack - 0.31).abs() < 0.01);
+    }
+
+    #[test]
+    fn soft_17_rule() {
+        let s17 = dealer_outcomes(&shoe_without(6), 6, &Rules::default());
+        let h17 = dealer_outcomes(&shoe_without(6), 6, &Rules { dealer_hits_soft_17: true, ..Rules::default() });
+
+        assert!(h17.total(17) < s17.total(17));
+        assert!(h17.bust > s17.bust);
+    }
+}
@@ -1,9 +1,36 @@
-
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use super::cards::{Card, CardFace, CardSuit};
+use super::composition::Composition;
 
-pub struct Deck(Vec<Card>);
+/// A shoe of one or more standard decks.
+///
+/// Cards that have been played can be returned to the
+/// discard pile, and are shuffled back in by [Deck::reshuffle_with]
+///
+/// # Example
+///
+/// ```
+/// use blackjack::deck::Deck;
+///
+/// let mut deck = Deck::stacked(vec!["AS".parse().unwrap(), "KH".parse().unwrap()]);
+///
+/// let ace = deck.draw().unwrap();
+/// assert_eq!(ace.to_string(), "A♠");
+///
+/// deck.discard([ace]);
+/// assert_eq!(deck.remaining(), 1);
+/// ```
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Deck {
+    cards: Vec<Card>,
+    discards: Vec<Card>
+}
 
 impl Deck {
     pub fn new(num_decks: u8) -> Self {
@@ -18,18 +45,110 @@ impl Deck {
             }
         };
 
-        Self(v)
+        Self { cards: v, discards: Vec::new() }
+    }
+
+    /// Create a deck that deals the supplied cards in order
+    pub fn stacked(cards: Vec<Card>) -> Self {
+        let mut cards = cards;
+        cards.reverse();
+
+        Self { cards, discards: Vec::new() }
     }
 
     pub fn shuffle(&mut self) {
-        self.0.shuffle(&mut rand::thread_rng());
+        self.shuffle_with(&mut rand::thread_rng());
+    }
+
+    /// Shuffle the cards left in the deck using the supplied
+    /// random number generator, e.g. a seeded one for reproducible shoes
+    pub fn shuffle_with<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.shuffle(rng);
+    }
+
+    /// Return all discarded cards to the deck and shuffle it
+    pub fn reshuffle_with<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards.append(&mut self.discards);
+        self.shuffle_with(rng);
+    }
+
+    /// Shuffle the discards into a new shoe once every card has been drawn,
+    /// returning false if there was nothing to shuffle
+    ///
+    /// The discards are shuffled with a generator seeded from their order,
+    /// so a shoe that was shuffled from a seed still plays out the same way
+    pub fn reshuffle_discards(&mut self) -> bool {
+        if !self.cards.is_empty() || self.discards.is_empty() {
+            return false;
+        }
+
+        let seed = order_hash(&self.discards);
+        self.reshuffle_with(&mut StdRng::seed_from_u64(seed));
+
+        true
     }
 
     pub fn draw(&mut self) -> Option<Card> {
-        self.0.pop()
+        self.cards.pop()
+    }
+
+    /// Remove the card nearest the bottom of the deck with the
+    /// given blackjack value (Aces being 1), if there is one
+    ///
+    /// Taking from the bottom leaves the order of the cards
+    /// about to be drawn unaffected by which card was taken
+    pub fn take(&mut self, value: u8) -> Option<Card> {
+        let i = self.cards.iter().position(|c| u8::from(*c) == value)?;
+
+        Some(self.cards.remove(i))
+    }
+
+    /// Place played cards on the discard pile
+    pub fn discard<I: IntoIterator<Item = Card>>(&mut self, cards: I) {
+        self.discards.extend(cards);
+    }
+
+    /// Returns the number of cards left to be drawn
+    pub fn remaining(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Returns the number of cards of each value left to be drawn
+    pub fn composition(&self) -> Composition {
+        Composition::from_cards(&self.cards)
+    }
+
+    /// Returns the number of cards on the discard pile
+    pub fn discarded(&self) -> usize {
+        self.discards.len()
+    }
+
+    /// Returns a hex hash of the order of the cards left to be drawn,
+    /// which tells shoes apart without giving their order away
+    pub(super) fn fingerprint(&self) -> String {
+        format!("{:016x}", order_hash(&self.cards))
+    }
+
+    /// Returns the cards on the discard pile
+    pub fn discard_pile(&self) -> &[Card] {
+        &self.discards
     }
 }
 
+/// Returns an FNV-1a hash of the order of the cards
+pub(super) fn order_hash(cards: &[Card]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for card in cards {
+        for byte in format!("{card:#}").bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    hash
+}
+
 impl Default for Deck {
     fn default() -> Self {
         Self::new(6)
@@ -38,6 +157,9 @@ impl Default for Deck {
 
 #[cfg(test)]
 mod test {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
     use super::{Card, CardFace, CardSuit};
 
     use super::Deck;
@@ -47,11 +169,71 @@ mod test {
     fn initialize() {
         let mut d = Deck::default();
 
-        assert_eq!(d.0.len(), 312);
-        assert_eq!(d.0[0], Card::new(CardFace::Ace, CardSuit::Clubs));
-        assert_eq!(d.0[311], Card::new(CardFace::King, CardSuit::Spades));
+        assert_eq!(d.cards.len(), 312);
+        assert_eq!(d.cards[0], Card::new(CardFace::Ace, CardSuit::Clubs));
+        assert_eq!(d.cards[311], Card::new(CardFace::King, CardSuit::Spades));
 
         assert_eq!(d.draw(), Some(Card::new(CardFace::King,CardSuit::Spades)));
-        assert_eq!(d.0.len(), 311);
+        assert_eq!(d.cards.len(), 311);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn discard_and_reshuffle() {
+        let mut d = Deck::new(1);
+
+        let drawn: Vec<Card> = (0..10).filter_map(|_| d.draw()).collect();
+        d.discard(drawn);
+
+        assert_eq!(d.remaining(), 42);
+        assert_eq!(d.discarded(), 10);
+
+        d.reshuffle_with(&mut StdRng::seed_from_u64(1));
+
+        assert_eq!(d.remaining(), 52);
+        assert_eq!(d.discarded(), 0);
+    }
+
+    #[test]
+    fn seeded_shuffles_repeat() {
+        let mut a = Deck::new(2);
+        let mut b = Deck::new(2);
+
+        a.shuffle_with(&mut StdRng::seed_from_u64(42));
+        b.shuffle_with(&mut StdRng::seed_from_u64(42));
+
+        assert_eq!(a.cards, b.cards);
+    }
+
+    #[test]
+    fn take_by_value() {
+        let mut d = Deck::new(1);
+
+        assert_eq!(d.take(10), Some(Card::new(CardFace::Number(10), CardSuit::Clubs)));
+        assert_eq!(d.take(1), Some(Card::new(CardFace::Ace, CardSuit::Clubs)));
+        assert_eq!(d.remaining(), 50);
+
+        for _ in 0..3 {
+            assert!(d.take(1).is_some());
+        }
+
+        assert_eq!(d.take(1), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips() {
+        let mut d = Deck::new(2);
+        d.shuffle_with(&mut StdRng::seed_from_u64(3));
+
+        let drawn: Vec<Card> = (0..20).filter_map(|_| d.draw()).collect();
+        d.discard(drawn);
+
+        let json: Deck = serde_json::from_str(&serde_json::to_string(&d).unwrap()).unwrap();
+        let compact: Deck = bincode::deserialize(&bincode::serialize(&d).unwrap()).unwrap();
+
+        for resumed in [json, compact] {
+            assert_eq!(resumed.cards, d.cards);
+            assert_eq!(resumed.discards, d.discards);
+        }
+    }
+}
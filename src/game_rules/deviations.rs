@@ -0,0 +1,413 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::cards::Card;
+use super::hand::Hand;
+use super::rules::Rules;
+use super::strategy::{basic_strategy, upcard_value, Action, AvailableActions};
+
+/// The hands an index play applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerHand {
+    Hard(u8),
+    Soft(u8),
+    /// A pair of cards with the given value, Aces being 1
+    Pair(u8),
+    /// The insurance side bet offered when the dealer shows an Ace
+    Insurance,
+}
+
+/// A single strategy deviation: when the true count is at or above
+/// `index`, play `at_or_above`, otherwise play `below`.
+///
+/// If the action for the current count is [None] or not available,
+/// the deviation is skipped and basic strategy is used instead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexPlay {
+    pub hand: PlayerHand,
+    /// The dealer's upcard, counting an Ace as 11
+    pub upcard: u8,
+    pub index: f32,
+    pub at_or_above: Option<Action>,
+    pub below: Option<Action>,
+}
+
+impl IndexPlay {
+    fn new(hand: PlayerHand, upcard: u8, index: f32, at_or_above: Action, below: Option<Action>) -> Self {
+        Self { hand, upcard, index, at_or_above: Some(at_or_above), below }
+    }
+
+    /// Returns the action this play calls for at the given true count
+    pub fn action(&self, true_count: f32) -> Option<Action> {
+        if true_count >= self.index {
+            self.at_or_above
+        } else {
+            self.below
+        }
+    }
+
+    fn matches(&self, hand: &Hand, upcard: u8) -> bool {
+        if self.upcard != upcard {
+            return false;
+        }
+
+        let cards = hand.cards();
+
+        match self.hand {
+            PlayerHand::Hard(total) => !hand.is_soft() && hand.total_value() == total,
+            PlayerHand::Soft(total) => hand.is_soft() && hand.total_value() == total,
+            PlayerHand::Pair(value) => {
+                cards.len() == 2 && u8::from(cards[0]) == value && u8::from(cards[1]) == value
+            },
+            PlayerHand::Insurance => false,
+        }
+    }
+}
+
+/// A set of index plays that override basic strategy at certain true counts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexTable(Vec<IndexPlay>);
+
+impl IndexTable {
+    pub fn new(plays: Vec<IndexPlay>) -> Self {
+        Self(plays)
+    }
+
+    pub fn plays(&self) -> &[IndexPlay] {
+        &self.0
+    }
+
+    /// The Illustrious 18 Hi-Lo index plays for multi-deck games
+    pub fn illustrious_18() -> Self {
+        use Action::*;
+        use PlayerHand::*;
+
+        Self(vec![
+            IndexPlay::new(Insurance, 11, 3.0, Stand, None),
+            IndexPlay::new(Hard(16), 10, 0.0, Stand, Some(Hit)),
+            IndexPlay::new(Hard(15), 10, 4.0, Stand, Some(Hit)),
+            IndexPlay::new(Pair(10), 5, 5.0, Split, Some(Stand)),
+            IndexPlay::new(Pair(10), 6, 4.0, Split, Some(Stand)),
+            IndexPlay::new(Hard(10), 10, 4.0, Double, Some(Hit)),
+            IndexPlay::new(Hard(12), 3, 2.0, Stand, Some(Hit)),
+            IndexPlay::new(Hard(12), 2, 3.0, Stand, Some(Hit)),
+            IndexPlay::new(Hard(11), 11, 1.0, Double, Some(Hit)),
+            IndexPlay::new(Hard(9), 2, 1.0, Double, Some(Hit)),
+            IndexPlay::new(Hard(10), 11, 4.0, Double, Some(Hit)),
+            IndexPlay::new(Hard(9), 7, 3.0, Double, Some(Hit)),
+            IndexPlay::new(Hard(16), 9, 5.0, Stand, Some(Hit)),
+            IndexPlay::new(Hard(13), 2, -1.0, Stand, Some(Hit)),
+            IndexPlay::new(Hard(12), 4, 0.0, Stand, Some(Hit)),
+            IndexPlay::new(Hard(12), 5, -2.0, Stand, Some(Hit)),
+            IndexPlay::new(Hard(12), 6, -1.0, Stand, Some(Hit)),
+            IndexPlay::new(Hard(13), 3, -2.0, Stand, Some(Hit)),
+        ])
+    }
+
+    /// The Fab 4 Hi-Lo late surrender index plays
+    pub fn fab_4() -> Self {
+        use Action::*;
+        use PlayerHand::*;
+
+        Self(vec![
+            IndexPlay::new(Hard(14), 10, 3.0, Surrender, None),
+            IndexPlay::new(Hard(15), 10, 0.0, Surrender, None),
+            IndexPlay::new(Hard(15), 9, 2.0, Surrender, None),
+            IndexPlay::new(Hard(15), 11, 1.0, Surrender, None),
+        ])
+    }
+
+    /// The Illustrious 18 together with the Fab 4 surrenders
+    pub fn hi_lo() -> Self {
+        let mut table = Self::fab_4();
+        table.0.extend(Self::illustrious_18().0);
+
+        table
+    }
+
+    /// Returns true if insurance should be taken at the given true count
+    pub fn take_insurance(&self, true_count: f32) -> bool {
+        self.0.iter()
+        .find(|p| p.hand == PlayerHand::Insurance)
+        .and_then(|p| p.action(true_count))
+        .is_some()
+    }
+
+    /// Returns the play for a hand at the given true count.
+    ///
+    /// Surrender deviations are checked first, then the other
+    /// deviations in the order they appear in the table. If no deviation
+    /// applies, the basic strategy play is returned. A hand that basic
+    /// strategy surrenders is only overridden by surrender deviations.
+    pub fn decide(&self, hand: &Hand, upcard: &Card, true_count: f32, available: AvailableActions, rules: &Rules) -> Action {
+        let basic = basic_strategy(hand, upcard, available, rules);
+        let up = upcard_value(upcard);
+
+        let is_allowed = |action: Action| match action {
+            Action::Hit | Action::Stand => true,
+            Action::Double => available.double,
+            Action::Split => available.split,
+            Action::Surrender => available.surrender,
+        };
+
+        let surrenders = self.0.iter().filter(|p| p.at_or_above == Some(Action::Surrender));
+        let others = self.0.iter().filter(|p| p.at_or_above != Some(Action::Surrender));
+
+        for play in surrenders.chain(others) {
+            // A pair that basic strategy splits is only
+            // overridden by deviations for that pair
+            if basic == Action::Split && !matches!(play.hand, PlayerHand::Pair(_)) {
+                continue;
+            }
+
+            if basic == Action::Surrender && play.at_or_above != Some(Action::Surrender) {
+                continue;
+            }
+
+            if !play.matches(hand, up) {
+                continue;
+            }
+
+            if let Some(action) = play.action(true_count).filter(|a| is_allowed(*a)) {
+                return action;
+            }
+        }
+
+        basic
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexTableError {
+    line: usize,
+    reason: String,
+}
+
+impl Display for IndexTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for IndexTableError {}
+
+fn parse_action(s: &str) -> Option<Action> {
+    match s.to_ascii_lowercase().as_str() {
+        "h" | "hit" => Some(Action::Hit),
+        "s" | "stand" => Some(Action::Stand),
+        "d" | "double" => Some(Action::Double),
+        "p" | "split" => Some(Action::Split),
+        "r" | "surrender" => Some(Action::Surrender),
+        _ => None,
+    }
+}
+
+fn action_name(action: Action) -> &'static str {
+    match action {
+        Action::Hit => "hit",
+        Action::Stand => "stand",
+        Action::Double => "double",
+        Action::Split => "split",
+        Action::Surrender => "surrender",
+    }
+}
+
+fn parse_card_value(s: &str) -> Option<u8> {
+    match s.to_ascii_uppercase().as_str() {
+        "A" | "11" => Some(11),
+        "T" | "J" | "Q" | "K" => Some(10),
+        v => v.parse::<u8>().ok().filter(|v| (2..=10).contains(v)),
+    }
+}
+
+fn parse_hand(s: &str) -> Option<PlayerHand> {
+    let lower = s.to_ascii_lowercase();
+
+    if lower == "insurance" {
+        return Some(PlayerHand::Insurance);
+    }
+
+    let total = |t: &str| t.parse::<u8>().ok().filter(|t| (4..=21).contains(t));
+
+    let mut chars = lower.chars();
+
+    match (chars.next(), chars.as_str()) {
+        (Some('h'), t) => total(t).map(PlayerHand::Hard),
+        (Some('s'), t) => total(t).map(PlayerHand::Soft),
+        (Some('p'), v) => parse_card_value(v).map(|v| PlayerHand::Pair(if v == 11 { 1 } else { v })),
+        _ => total(&lower).map(PlayerHand::Hard),
+    }
+}
+
+impl FromStr for IndexTable {
+    type Err = IndexTableError;
+
+    /// Parse an index table with one play per line:
+    ///
+    /// `<hand> <upcard> <index> <action at or above> [action below]`
+    ///
+    /// Hands are hard totals (`16` or `h16`), soft totals (`s18`),
+    /// pairs (`p8`, `pA`) or `insurance`. Upcards are `2` to `10` or `A`,
+    /// and actions are `hit`, `stand`, `double`, `split` or `surrender`
+    /// (or their first letter, with `p` for split and `r` for surrender).
+    /// Anything after a `#` is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut plays = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let err = |reason: &str| IndexTableError { line: i + 1, reason: reason.to_string() };
+
+            let line = line.split('#').next().unwrap_or("").trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            if fields.len() < 3 || fields.len() > 5 {
+                return Err(err("expected <hand> <upcard> <index> <action> [action below]"));
+            }
+
+            let hand = parse_hand(fields[0]).ok_or_else(|| err("unrecognised hand"))?;
+            let upcard = parse_card_value(fields[1]).ok_or_else(|| err("unrecognised upcard"))?;
+            let index = fields[2].parse::<f32>().map_err(|_| err("index is not a number"))?;
+
+            let at_or_above = match (hand, fields.get(3)) {
+                (PlayerHand::Insurance, None) => Some(Action::Stand),
+                (_, Some(a)) => Some(parse_action(a).ok_or_else(|| err("unrecognised action"))?),
+                (_, None) => return Err(err("missing action")),
+            };
+
+            let below = match fields.get(4) {
+                Some(a) => Some(parse_action(a).ok_or_else(|| err("unrecognised action"))?),
+                None => None,
+            };
+
+            plays.push(IndexPlay { hand, upcard, index, at_or_above, below });
+        }
+
+        Ok(Self(plays))
+    }
+}
+
+impl Display for IndexPlay {
+    /// Writes the play as a line of the format read by [IndexTable::from_str]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let upcard = match self.upcard {
+            11 => "A".to_string(),
+            v => v.to_string(),
+        };
+
+        match self.hand {
+            PlayerHand::Insurance => return write!(f, "insurance {upcard} {:+}", self.index),
+            PlayerHand::Hard(t) => write!(f, "h{t}")?,
+            PlayerHand::Soft(t) => write!(f, "s{t}")?,
+            PlayerHand::Pair(1) => write!(f, "pA")?,
+            PlayerHand::Pair(v) => write!(f, "p{v}")?,
+        }
+
+        write!(f, " {upcard} {:+}", self.index)?;
+
+        for action in [self.at_or_above, self.below].into_iter().flatten() {
+            write!(f, " {}", action_name(action))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for IndexTable {
+    /// Writes the table in the format read by [IndexTable::from_str]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for play in &self.0 {
+            writeln!(f, "{play}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::cards::{Card, CardFace, CardSuit};
+    use crate::game_rules::hand::Hand;
+    use crate::game_rules::rules::Rules;
+    use crate::game_rules::strategy::{Action, AvailableActions};
+
+    use super::{IndexTable, PlayerHand};
+
+    const ALL: AvailableActions = AvailableActions { double: true, split: true, surrender: true };
+
+    fn card(v: u8) -> Card {
+        Card::new(CardFace::try_from(v).unwrap(), CardSuit::Clubs)
+    }
+
+    fn decide(table: &IndexTable, cards: &[u8], up: u8, tc: f32, available: AvailableActions) -> Action {
+        let hand = Hand::new(cards.iter().map(|v| card(*v)).collect());
+
+        table.decide(&hand, &card(up), tc, available, &Rules::default())
+    }
+
+    #[test]
+    fn illustrious_18_deviations() {
+        let table = IndexTable::hi_lo();
+        let none = AvailableActions::default();
+
+        assert_eq!(decide(&table, &[10, 6], 10, -1.0, none), Action::Hit);
+        assert_eq!(decide(&table, &[10, 6], 10, 0.0, none), Action::Stand);
+        assert_eq!(decide(&table, &[10, 2], 4, -0.5, none), Action::Hit);
+        assert_eq!(decide(&table, &[10, 2], 2, 3.0, none), Action::Stand);
+        assert_eq!(decide(&table, &[10, 10], 6, 4.0, ALL), Action::Split);
+        assert_eq!(decide(&table, &[10, 10], 6, 4.0, none), Action::Stand);
+        assert_eq!(decide(&table, &[8, 8], 10, 2.0, ALL), Action::Split);
+        assert_eq!(decide(&table, &[6, 4], 1, 4.0, ALL), Action::Double);
+        assert_eq!(decide(&table, &[6, 4], 1, 4.0, none), Action::Hit);
+
+        assert!(table.take_insurance(3.0));
+        assert!(!table.take_insurance(2.9));
+    }
+
+    #[test]
+    fn fab_4_surrenders() {
+        let table = IndexTable::hi_lo();
+
+        assert_eq!(decide(&table, &[10, 4], 10, 3.0, ALL), Action::Surrender);
+        assert_eq!(decide(&table, &[10, 4], 10, 2.0, ALL), Action::Hit);
+        assert_eq!(decide(&table, &[10, 5], 10, 5.0, ALL), Action::Surrender);
+        assert_eq!(decide(&table, &[10, 5], 10, 5.0, AvailableActions::default()), Action::Stand);
+
+        // Below the Illustrious 18 index, 16 is still surrendered when it can be
+        assert_eq!(decide(&table, &[10, 6], 10, -1.0, ALL), Action::Surrender);
+        assert_eq!(decide(&table, &[10, 6], 9, 1.0, ALL), Action::Surrender);
+        assert_eq!(decide(&table, &[10, 6], 10, -1.0, AvailableActions::default()), Action::Hit);
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let table: IndexTable = "
+            # A custom table
+            16 10 0 stand hit
+            s18 A 1 d
+            p9 7 +3.5 split stand
+            insurance A 2.5
+        ".parse().unwrap();
+
+        assert_eq!(table.plays().len(), 4);
+        assert_eq!(table.plays()[1].hand, PlayerHand::Soft(18));
+        assert_eq!(table.plays()[1].upcard, 11);
+        assert_eq!(table.plays()[2].index, 3.5);
+        assert!(table.take_insurance(2.5));
+
+        let round_trip: IndexTable = table.to_string().parse().unwrap();
+        assert_eq!(round_trip, table);
+
+        assert_eq!(IndexTable::hi_lo().to_string().parse::<IndexTable>().unwrap(), IndexTable::hi_lo());
+
+        assert!("16 10".parse::<IndexTable>().is_err());
+        assert!("16 1 0 stand".parse::<IndexTable>().is_err());
+        assert!("x16 10 0 stand".parse::<IndexTable>().is_err());
+        assert_eq!("é16 10 0 stand".parse::<IndexTable>().unwrap_err().to_string(), "line 1: unrecognised hand");
+        assert!("ü 10 0 stand".parse::<IndexTable>().is_err());
+    }
+}
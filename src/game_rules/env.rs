@@ -0,0 +1,511 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::action::TableAction;
+use super::game::AnyTable;
+use super::hand::Hand;
+use super::round::{BlackjackRoundError, BlackjackTable, GameOver};
+use super::rules::Rules;
+use super::strategy::upcard_value;
+
+/// The actions an agent chooses between, in the order of [Observation::action_mask]
+pub const ACTIONS: [TableAction; 2] = [TableAction::Hit, TableAction::Stand];
+
+/// What the agent can see of the round after a reset or a step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Observation {
+    pub player_total: u8,
+    pub soft: bool,
+    /// The value of the dealer's face up card, counting an Ace as 11
+    pub upcard: u8,
+    /// The total of the dealer's visible cards, which is the whole
+    /// hand once the round is over
+    pub dealer_total: u8,
+    /// The Hi-Lo true count, if the environment was made [with_count](BlackjackEnv::with_count)
+    pub true_count: Option<f32>,
+    /// Whether each of [ACTIONS] can be taken, all false once the round is over
+    pub action_mask: [bool; ACTIONS.len()],
+}
+
+impl Observation {
+    /// The observation as a vector of numbers: the player's total, 1 if soft or 0 if not,
+    /// the upcard, the dealer's total and then the true count, if it is observed
+    pub fn to_vec(&self) -> Vec<f32> {
+        let mut features = vec![
+            f32::from(self.player_total),
+            if self.soft { 1.0 } else { 0.0 },
+            f32::from(self.upcard),
+            f32::from(self.dealer_total),
+        ];
+
+        features.extend(self.true_count);
+
+        features
+    }
+}
+
+/// A reinforcement learning environment in which each episode is one round,
+/// played from a shoe that carries on between episodes
+///
+/// The shoe is shuffled with the environment's own seeded generator, so the
+/// same seed always deals the same rounds
+///
+/// ```
+/// use blackjack::env::BlackjackEnv;
+/// use blackjack::game::TableAction;
+/// use blackjack::rules::Rules;
+///
+/// let mut env = BlackjackEnv::new(Rules::default());
+/// let mut observation = env.reset(Some(7)).unwrap();
+///
+/// loop {
+///     let action = if observation.player_total < 17 { TableAction::Hit } else { TableAction::Stand };
+///     let (next, reward, done) = env.step(action).unwrap();
+///
+///     if done {
+///         println!("The round paid {reward:+}");
+///         break;
+///     }
+///
+///     observation = next;
+/// }
+/// ```
+pub struct BlackjackEnv {
+    rules: Rules,
+    rng: StdRng,
+    count: bool,
+    /// The table, which is only missing if a round could not be finished
+    table: Option<AnyTable>,
+}
+
+impl BlackjackEnv {
+    /// Create an environment with the house rules, seeded from entropy
+    /// until it is [reset](BlackjackEnv::reset) with a seed
+    pub fn new(rules: Rules) -> Self {
+        let mut rng = StdRng::from_entropy();
+        let table = Some(AnyTable::from(BlackjackTable::with_rules(rules).reshuffle_with(&mut rng)));
+
+        Self { rules, rng, count: false, table }
+    }
+
+    /// Include the true count in every observation
+    pub fn with_count(mut self) -> Self {
+        self.count = true;
+        self
+    }
+
+    /// Start an episode by dealing a new round, returning what is observed of it
+    ///
+    /// With a seed, the generator is reseeded and a new shoe is shuffled from it.
+    /// Otherwise the shoe carries on, and is reshuffled once it needs to be.
+    /// A round still in progress is stood and played out first
+    ///
+    /// Rounds settled at the deal, by a natural, leave nothing to decide and are dealt past,
+    /// so an episode never starts with a natural and a natural's payout is never a reward
+    pub fn reset(&mut self, seed: Option<u64>) -> Result<Observation, BlackjackRoundError> {
+        if let Some(seed) = seed {
+            self.rng = StdRng::seed_from_u64(seed);
+            self.table = None;
+        }
+
+        loop {
+            let table = match self.table.take() {
+                Some(AnyTable::PlayerTurn(t)) => self.finish(AnyTable::PlayerTurn(t), TableAction::Stand)?.next_round(),
+                Some(AnyTable::DealerTurn(t)) => self.finish(AnyTable::DealerTurn(t), TableAction::DealerPlay)?.next_round(),
+                Some(AnyTable::GameOver(t)) => t.next_round(),
+                Some(AnyTable::NotStarted(t)) => t,
+                None => BlackjackTable::with_rules(self.rules).reshuffle_with(&mut self.rng),
+            };
+
+            let table = match table.needs_reshuffle() {
+                true => table.reshuffle_with(&mut self.rng),
+                false => table,
+            };
+
+            let table = self.apply(AnyTable::NotStarted(table), TableAction::Deal)?;
+            let dealt = matches!(table, AnyTable::PlayerTurn(_));
+
+            self.table = Some(table);
+
+            if dealt {
+                return Ok(self.observe());
+            }
+        }
+    }
+
+    /// Take one of [ACTIONS], returning the observation, the reward and whether the episode is over
+    ///
+    /// Standing plays out the dealer's turn. The reward is the round's payout once it is over,
+    /// 1 for a win, 0 for a push or -1 for a loss, and 0 until then. Naturals are dealt past
+    /// by [reset](BlackjackEnv::reset), so they are never rewarded
+    pub fn step(&mut self, action: TableAction) -> Result<(Observation, f64, bool), BlackjackRoundError> {
+        let mask = self.observe().action_mask;
+        let allowed = ACTIONS.iter().position(|a| *a == action).is_some_and(|i| mask[i]);
+
+        let table = match self.table.take() {
+            Some(table) if allowed => table,
+            table => {
+                self.table = table;
+
+                let legal = ACTIONS.iter().zip(mask).filter(|(_, legal)| *legal).map(|(a, _)| *a).collect();
+
+                return Err(BlackjackRoundError::IllegalAction { action, legal });
+            },
+        };
+
+        let table = self.apply(table, action)?;
+
+        let table = match table {
+            AnyTable::DealerTurn(t) => self.finish(AnyTable::DealerTurn(t), TableAction::DealerPlay)?,
+            table => {
+                self.table = Some(table);
+                return Ok((self.observe(), self.reward(), self.is_done()));
+            },
+        };
+
+        self.table = Some(AnyTable::GameOver(table));
+
+        Ok((self.observe(), self.reward(), true))
+    }
+
+    /// Returns true once the round is over
+    pub fn is_done(&self) -> bool {
+        !matches!(self.table, Some(AnyTable::PlayerTurn(_)))
+    }
+
+    /// The table the episodes are played at
+    pub fn table(&self) -> Option<&AnyTable> {
+        self.table.as_ref()
+    }
+
+    /// What is observed of the round as it stands
+    pub fn observe(&self) -> Observation {
+        let Some(table) = &self.table else {
+            return Observation {
+                player_total: 0,
+                soft: false,
+                upcard: 0,
+                dealer_total: 0,
+                true_count: self.count.then_some(0.0),
+                action_mask: [false; ACTIONS.len()],
+            };
+        };
+
+        let in_turn = matches!(table, AnyTable::PlayerTurn(_));
+        let dealer = Hand::new(table.visible_dealer_cards().to_vec());
+
+        Observation {
+            player_total: table.player_hand().total_value(),
+            soft: table.player_hand().is_soft(),
+            upcard: table.showing_card().map_or(0, upcard_value),
+            dealer_total: dealer.total_value(),
+            true_count: self.count.then(|| table.running_count().true_count(table.cards_remaining())),
+            action_mask: [in_turn; ACTIONS.len()],
+        }
+    }
+
+    /// The payout of the finished round, or 0 if it is not over
+    fn reward(&self) -> f64 {
+        self.table.as_ref().and_then(AnyTable::payout).unwrap_or_default()
+    }
+
+    /// Take an action, keeping the table if it cannot be taken
+    fn apply(&mut self, table: AnyTable, action: TableAction) -> Result<AnyTable, BlackjackRoundError> {
+        table.apply(action).map_err(|e| {
+            self.table = e.table.map(|table| *table);
+            e.error
+        })
+    }
+
+    /// Take the action and then let the dealer play until the round is over
+    fn finish(&mut self, table: AnyTable, action: TableAction) -> Result<BlackjackTable<GameOver>, BlackjackRoundError> {
+        let mut table = self.apply(table, action)?;
+
+        loop {
+            table = match table {
+                AnyTable::GameOver(t) => return Ok(t),
+                AnyTable::DealerTurn(_) => self.apply(table, TableAction::DealerPlay)?,
+                table => {
+                    let legal = table.legal_actions();
+                    self.table = Some(table);
+
+                    return Err(BlackjackRoundError::IllegalAction { action, legal });
+                },
+            };
+        }
+    }
+}
+
+/// The number of distinct (total, softness, upcard) states the agent keeps values for
+const STATES: usize = 32 * 2 * 12;
+
+/// A reference tabular Q-learning agent, which learns whether to hit or stand
+/// on each player total, softness and upcard
+///
+/// Actions are chosen epsilon-greedily while learning, and each value is
+/// updated with a step size of one over the number of times it was updated,
+/// so each value is the average of the targets it was moved towards. Left to learn for long
+/// enough, its [policy](QLearningAgent::policy) approaches basic strategy
+/// without doubling, splitting or surrendering
+pub struct QLearningAgent {
+    values: Vec<[f64; ACTIONS.len()]>,
+    updates: Vec<[u32; ACTIONS.len()]>,
+    epsilon: f64,
+    rng: StdRng,
+}
+
+impl QLearningAgent {
+    /// An agent that explores a random action a tenth of the time,
+    /// choosing its explorations with a generator seeded from `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            values: vec![[0.0; ACTIONS.len()]; STATES],
+            updates: vec![[0; ACTIONS.len()]; STATES],
+            epsilon: 0.1,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Explore a random action with this chance instead of the best one
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    /// Choose an allowed action, exploring at random with the agent's epsilon
+    pub fn act(&mut self, observation: &Observation) -> TableAction {
+        let allowed: Vec<usize> = (0..ACTIONS.len()).filter(|i| observation.action_mask[*i]).collect();
+
+        match allowed.is_empty() || self.rng.gen::<f64>() >= self.epsilon {
+            true => self.greedy(observation),
+            false => ACTIONS[allowed[self.rng.gen_range(0..allowed.len())]],
+        }
+    }
+
+    /// The allowed action with the highest value, preferring to stand on a tie
+    pub fn greedy(&self, observation: &Observation) -> TableAction {
+        let values = self.values[state(observation)];
+
+        match observation.action_mask {
+            [true, true] if values[0] > values[1] => TableAction::Hit,
+            [true, false] => TableAction::Hit,
+            _ => TableAction::Stand,
+        }
+    }
+
+    /// The action the agent has learnt to take on the hand against the upcard
+    pub fn policy(&self, player_total: u8, soft: bool, upcard: u8) -> TableAction {
+        self.greedy(&Observation {
+            player_total,
+            soft,
+            upcard,
+            dealer_total: upcard,
+            true_count: None,
+            action_mask: [true; ACTIONS.len()],
+        })
+    }
+
+    /// The learnt value of taking the action, in units won per round
+    pub fn value(&self, observation: &Observation, action: TableAction) -> f64 {
+        let i = ACTIONS.iter().position(|a| *a == action).expect("Only hitting and standing have values");
+
+        self.values[state(observation)][i]
+    }
+
+    /// Move the value of the action towards the reward, plus the value
+    /// of the best action in the next state if the round carries on
+    pub fn learn(&mut self, observation: &Observation, action: TableAction, reward: f64, next: &Observation, done: bool) {
+        let Some(i) = ACTIONS.iter().position(|a| *a == action) else { return };
+
+        let future = match done {
+            true => 0.0,
+            false => {
+                let values = self.values[state(next)];
+
+                (0..ACTIONS.len())
+                    .filter(|a| next.action_mask[*a])
+                    .map(|a| values[a])
+                    .fold(f64::NEG_INFINITY, f64::max)
+            },
+        };
+
+        let s = state(observation);
+
+        self.updates[s][i] += 1;
+        let step_size = 1.0 / f64::from(self.updates[s][i]);
+
+        self.values[s][i] += step_size * (reward + future - self.values[s][i]);
+    }
+
+    /// Learn from the given number of rounds played in the environment
+    pub fn train(&mut self, env: &mut BlackjackEnv, episodes: usize) -> Result<(), BlackjackRoundError> {
+        for _ in 0..episodes {
+            let mut observation = env.reset(None)?;
+
+            loop {
+                let action = self.act(&observation);
+                let (next, reward, done) = env.step(action)?;
+
+                self.learn(&observation, action, reward, &next, done);
+
+                if done {
+                    break;
+                }
+
+                observation = next;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The index of the observation's values, from its total, softness and upcard
+fn state(observation: &Observation) -> usize {
+    let total = usize::from(observation.player_total.min(31));
+    let upcard = usize::from(observation.upcard.min(11));
+
+    (total * 2 + usize::from(observation.soft)) * 12 + upcard
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::cards::{Card, CardFace, CardSuit};
+    use crate::game_rules::action::TableAction;
+    use crate::game_rules::hand::Hand;
+    use crate::game_rules::round::BlackjackRoundError;
+    use crate::game_rules::rules::Rules;
+    use crate::game_rules::strategy::{basic_strategy, AvailableActions};
+
+    use super::{BlackjackEnv, QLearningAgent};
+
+    /// Play every episode standing on 17 or more, returning the rewards
+    fn play(env: &mut BlackjackEnv, seed: u64, episodes: usize) -> Vec<f64> {
+        let mut rewards = Vec::new();
+        let mut observation = env.reset(Some(seed)).unwrap();
+
+        for _ in 0..episodes {
+            loop {
+                let action = if observation.player_total < 17 { TableAction::Hit } else { TableAction::Stand };
+                let (next, reward, done) = env.step(action).unwrap();
+
+                if done {
+                    rewards.push(reward);
+                    break;
+                }
+
+                observation = next;
+            }
+
+            observation = env.reset(None).unwrap();
+        }
+
+        rewards
+    }
+
+    #[test]
+    fn episodes_are_repeated_by_their_seed() {
+        let mut env = BlackjackEnv::new(Rules::default());
+
+        let first = play(&mut env, 3, 500);
+        let again = play(&mut env, 3, 500);
+        let other = play(&mut env, 4, 500);
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert!(first.iter().all(|r| [-1.0, 0.0, 1.0].contains(r)));
+    }
+
+    #[test]
+    fn observations_follow_the_round() {
+        let mut env = BlackjackEnv::new(Rules::default()).with_count();
+
+        let observation = env.reset(Some(1)).unwrap();
+        let table = env.table().unwrap();
+
+        assert_eq!(observation.action_mask, [true, true]);
+        assert_eq!(observation.player_total, table.player_hand().total_value());
+        assert_eq!(observation.dealer_total, observation.upcard);
+        assert!((2..=11).contains(&observation.upcard));
+        assert!(observation.true_count.is_some());
+        assert_eq!(observation.to_vec().len(), 5);
+
+        let (observation, _, done) = env.step(TableAction::Stand).unwrap();
+
+        assert!(done);
+        assert_eq!(observation.action_mask, [false, false]);
+        assert_eq!(observation.dealer_total, env.table().unwrap().dealer_hand().total_value());
+
+        assert!(matches!(env.step(TableAction::Hit), Err(BlackjackRoundError::IllegalAction { .. })));
+        assert!(matches!(env.step(TableAction::Deal), Err(BlackjackRoundError::IllegalAction { .. })));
+    }
+
+    /// The number of two card hard hands and upcards on which the agent plays
+    /// basic strategy without doubling, splitting or surrendering, out of how many there are
+    fn agreement(agent: &QLearningAgent, rules: &Rules) -> (usize, usize) {
+        let card = |value: u8| Card::new(CardFace::try_from(value).unwrap(), CardSuit::Spades);
+
+        let mut agreed = 0;
+        let mut compared = 0;
+
+        for first in 2..=10 {
+            for second in first..=10 {
+                let hand = Hand::new(vec![card(first), card(second)]);
+
+                for up in 2..=11 {
+                    let upcard = card(if up == 11 { 1 } else { up });
+
+                    let basic = basic_strategy(&hand, &upcard, AvailableActions::default(), rules);
+                    let expected = TableAction::try_from(basic).unwrap();
+
+                    compared += 1;
+
+                    if agent.policy(hand.total_value(), hand.is_soft(), up) == expected {
+                        agreed += 1;
+                    }
+                }
+            }
+        }
+
+        (agreed, compared)
+    }
+
+    /// An agent that has learnt from the given number of episodes,
+    /// with how many decisions it agreed on after its first 2,000
+    fn trained(rules: Rules, episodes: usize) -> (QLearningAgent, usize) {
+        let mut env = BlackjackEnv::new(rules);
+        env.reset(Some(12)).unwrap();
+
+        let mut agent = QLearningAgent::new(12).with_epsilon(0.5);
+
+        agent.train(&mut env, 2_000).unwrap();
+        let (early, _) = agreement(&agent, &rules);
+
+        agent.train(&mut env, episodes - 2_000).unwrap();
+
+        (agent, early)
+    }
+
+    #[test]
+    fn q_learning_learns_the_clearest_decisions() {
+        let rules = Rules::default();
+        let (agent, early) = trained(rules, 20_000);
+        let (agreed, _) = agreement(&agent, &rules);
+
+        assert!(agreed > early, "agreed on {agreed} decisions, and {early} early on");
+
+        assert_eq!(agent.policy(20, false, 10), TableAction::Stand);
+        assert_eq!(agent.policy(8, false, 6), TableAction::Hit);
+        assert_eq!(agent.policy(16, false, 11), TableAction::Hit);
+    }
+
+    #[test]
+    fn q_learning_converges_toward_basic_strategy() {
+        let rules = Rules::default();
+        let (agent, _) = trained(rules, 150_000);
+        let (agreed, compared) = agreement(&agent, &rules);
+
+        // Only a few close calls, such as 16 against a 10, are still learnt wrongly
+        assert!(agreed * 100 >= compared * 95, "only agreed on {agreed} of {compared} decisions");
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use super::cards::Card;
+use super::composition::Composition;
+use super::dealer_outcomes::{distribution, Distribution, Total, BLACKJACK, BUST};
+use super::hand::Hand;
+use super::rules::Rules;
+use super::strategy::{Action, AvailableActions};
+
+/// The expected value of each action on a hand, in units of the original bet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionValues {
+    pub stand: f64,
+    pub hit: f64,
+    pub double: f64,
+    /// Only available when the hand is a pair
+    ///
+    /// Approximated by playing both halves from the same shoe,
+    /// so unlike the other values it is not exact
+    pub split: Option<f64>,
+    pub surrender: f64,
+}
+
+impl ActionValues {
+    /// Returns the action with the highest expected value
+    /// out of the actions that are available
+    pub fn best(&self, available: AvailableActions) -> (Action, f64) {
+        let mut best = if self.hit > self.stand {
+            (Action::Hit, self.hit)
+        } else {
+            (Action::Stand, self.stand)
+        };
+
+        let optional = [
+            (available.double, Action::Double, Some(self.double)),
+            (available.split, Action::Split, self.split),
+            (available.surrender, Action::Surrender, Some(self.surrender)),
+        ];
+
+        for (allowed, action, value) in optional {
+            if let (true, Some(v)) = (allowed, value) {
+                if v > best.1 {
+                    best = (action, v);
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// Exact expected values, computed by recursing over every card
+/// that could be drawn from the remaining shoe.
+///
+/// When the dealer peeks, values are computed as the expectation of the
+/// result multiplied by the chance that the dealer does not have a natural,
+/// since the player only gets to act after the dealer has checked for one.
+/// The results are divided by that chance at the end.
+/// Without a peek, the dealer's natural takes every bet on the table.
+struct Solver {
+    upcard: u8,
+    rules: Rules,
+    dealer_cache: HashMap<Composition, Distribution>,
+    hit_cache: HashMap<(Composition, Total), f64>,
+}
+
+impl Solver {
+    fn new(upcard: u8, rules: Rules) -> Self {
+        Self {
+            upcard,
+            rules,
+            dealer_cache: HashMap::new(),
+            hit_cache: HashMap::new(),
+        }
+    }
+
+    /// Chance that the dealer's hole card does not give them a natural,
+    /// or 1 if the dealer does not peek
+    fn no_natural(&self, shoe: &Composition) -> f64 {
+        if !self.rules.dealer_peeks {
+            return 1.0;
+        }
+
+        match self.upcard {
+            1 => 1.0 - shoe.probability(10),
+            10 => 1.0 - shoe.probability(1),
+            _ => 1.0,
+        }
+    }
+
+    fn dealer(&mut self, shoe: &Composition) -> Distribution {
+        if let Some(dist) = self.dealer_cache.get(shoe) {
+            return *dist;
+        }
+
+        let dist = distribution(&mut shoe.clone(), Total::of_card(self.upcard), 1, self.rules.dealer_hits_soft_17);
+        self.dealer_cache.insert(*shoe, dist);
+
+        dist
+    }
+
+    fn stand(&mut self, shoe: &Composition, total: Total) -> f64 {
+        let dist = self.dealer(shoe);
+        let player = total.value();
+
+        if player > 21 {
+            return -self.no_natural(shoe);
+        }
+
+        let mut value = dist[BUST];
+
+        if !self.rules.dealer_peeks {
+            value -= dist[BLACKJACK];
+        }
+
+        for (i, p) in dist.iter().take(5).enumerate() {
+            let dealer = 17 + i as u8;
+
+            if player > dealer {
+                value += p;
+            } else if player < dealer {
+                value -= p;
+            }
+        }
+
+        value
+    }
+
+    /// Value of drawing a single card, then applying `after_draw`
+    /// to every total that did not go bust
+    fn draw<F>(&mut self, shoe: &Composition, total: Total, mut after_draw: F) -> f64
+    where F: FnMut(&mut Self, &Composition, Total) -> f64 {
+        let mut value = 0.0;
+
+        for card in 1..=10 {
+            let p = shoe.probability(card);
+
+            if p == 0.0 {
+                continue;
+            }
+
+            let mut next_shoe = *shoe;
+            next_shoe.remove(card);
+
+            let next = total.add(card);
+
+            value += p * if next.value() > 21 {
+                -self.no_natural(&next_shoe)
+            } else {
+                after_draw(self, &next_shoe, next)
+            };
+        }
+
+        value
+    }
+
+    /// Value of hitting, then continuing to hit or stand optimally
+    fn hit(&mut self, shoe: &Composition, total: Total) -> f64 {
+        if let Some(v) = self.hit_cache.get(&(*shoe, total)) {
+            return *v;
+        }
+
+        let value = self.draw(shoe, total, |solver, next_shoe, next| {
+            solver.stand(next_shoe, next).max(solver.hit(next_shoe, next))
+        });
+
+        self.hit_cache.insert((*shoe, total), value);
+
+        value
+    }
+
+    fn double(&mut self, shoe: &Composition, total: Total) -> f64 {
+        2.0 * self.draw(shoe, total, |solver, next_shoe, next| solver.stand(next_shoe, next))
+    }
+
+    /// Value of splitting a pair, with doubling allowed afterwards
+    /// but no resplitting.
+    ///
+    /// Each half of the pair is treated as drawing from the same
+    /// shoe, ignoring the cards the other half draws, so this is an
+    /// approximation. Split Aces receive a single card
+    fn split(&mut self, shoe: &Composition, card: u8) -> f64 {
+        let one_hand = self.draw(shoe, Total::of_card(card), |solver, next_shoe, next| {
+            if card == 1 {
+                solver.stand(next_shoe, next)
+            } else {
+                solver.stand(next_shoe, next)
+                .max(solver.hit(next_shoe, next))
+                .max(solver.double(next_shoe, next))
+            }
+        });
+
+        2.0 * one_hand
+    }
+}
+
+/// Computes the exact expected value of every action on the player's hand,
+/// except for split, which is approximated as in [ActionValues::split]
+///
+/// `shoe` is the composition of the cards the player has not seen, i.e.
+/// the player's cards and the dealer's upcard must already be removed from it.
+/// If the table's [Rules] have the dealer peek, they are assumed to have already
+/// checked for a natural, and surrender is late surrender.
+pub fn action_values(shoe: &Composition, player: &Hand, upcard: &Card, rules: &Rules) -> ActionValues {
+    let mut solver = Solver::new(u8::from(*upcard), *rules);
+
+    let values: Vec<u8> = player.cards().iter().map(|c| u8::from(*c)).collect();
+    let total = values.iter().fold(Total::default(), |t, v| t.add(*v));
+
+    let no_natural = solver.no_natural(shoe);
+
+    if no_natural <= 0.0 {
+        return ActionValues { stand: -1.0, hit: -1.0, double: -1.0, split: None, surrender: -1.0 };
+    }
+
+    let split = match values.as_slice() {
+        [a, b] if a == b => Some(solver.split(shoe, *a) / no_natural),
+        _ => None,
+    };
+
+    ActionValues {
+        stand: solver.stand(shoe, total) / no_natural,
+        hit: solver.hit(shoe, total) / no_natural,
+        double: solver.double(shoe, total) / no_natural,
+        split,
+        surrender: -0.5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::cards::{Card, CardFace, CardSuit};
+    use crate::game_rules::composition::Composition;
+    use crate::game_rules::deck::Deck;
+    use crate::game_rules::hand::Hand;
+    use crate::game_rules::rules::Rules;
+    use crate::game_rules::strategy::{Action, AvailableActions};
+
+    use super::action_values;
+
+    fn card(v: u8) -> Card {
+        Card::new(CardFace::try_from(v).unwrap(), CardSuit::Hearts)
+    }
+
+    /// Values for a hand dealt from a shoe of `decks` decks
+    fn values(decks: u8, player: &[u8], up: u8) -> super::ActionValues {
+        let mut shoe = Deck::new(decks).composition();
+
+        for v in player.iter().chain([up].iter()) {
+            shoe.remove(*v);
+        }
+
+        let hand = Hand::new(player.iter().map(|v| card(*v)).collect());
+
+        action_values(&shoe, &hand, &card(up), &Rules::default())
+    }
+
+    #[test]
+    fn single_card_shoes() {
+        let only_tens = Composition::from_cards(&[card(10); 20]);
+
+        let stiff = Hand::new(vec![card(10), card(2)]);
+        let v = action_values(&only_tens, &stiff, &card(10), &Rules::default());
+
+        assert_eq!(v.stand, -1.0);
+        assert_eq!(v.hit, -1.0);
+        assert_eq!(v.double, -2.0);
+
+        let twenty = Hand::new(vec![card(10), card(10)]);
+        let v = action_values(&only_tens, &twenty, &card(7), &Rules::default());
+
+        assert_eq!(v.stand, 1.0);
+        assert_eq!(v.split, Some(2.0));
+    }
+
+    #[test]
+    fn known_decisions() {
+        let v = values(6, &[10, 10], 6);
+        assert!(v.stand > 0.6 && v.stand < 0.8);
+        assert_eq!(v.best(AvailableActions::default()).0, Action::Stand);
+
+        let v = values(6, &[6, 5], 6);
+        assert!(v.double > v.hit && v.hit > v.stand);
+
+        let v = values(6, &[8, 8], 10);
+        assert!(v.split.unwrap() > v.hit.max(v.stand));
+
+        let v = values(6, &[10, 6], 10);
+        assert!((v.stand - v.hit).abs() < 0.02);
+        assert!(v.stand < -0.5);
+
+        let v = values(6, &[10, 3], 2);
+        assert_eq!(v.best(AvailableActions::default()).0, Action::Stand);
+    }
+}
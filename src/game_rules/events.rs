@@ -0,0 +1,167 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::cards::Card;
+use super::round::GameResult;
+use super::strategy::Action;
+
+/// Which hand a card was dealt to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Seat {
+    Player,
+    Dealer,
+}
+
+/// Whether a dealt card can be seen by the players
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Visibility {
+    FaceUp,
+    FaceDown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "action", rename_all = "snake_case"))]
+pub enum DealerAction {
+    /// The dealer turned their hole card face up
+    RevealHoleCard { card: Card },
+    Hit,
+    Stand,
+}
+
+/// Something that happened at a [BlackjackTable](super::round::BlackjackTable)
+///
+/// A face down card is only shown to observers once it is revealed,
+/// so the events can be shown to a player without giving anything away
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "event", rename_all = "snake_case"))]
+pub enum TableEvent {
+    /// The discards were shuffled back into the shoe
+    ShoeShuffled {
+        cards: usize,
+        /// Fingerprint of the shuffled order, which tells shoes apart
+        /// without giving the order away
+        shoe: String,
+    },
+    CardDealt {
+        /// The card, or [None] if it was dealt face down
+        card: Option<Card>,
+        to: Seat,
+        visibility: Visibility,
+    },
+    PlayerAction {
+        action: Action,
+    },
+    DealerAction {
+        action: DealerAction,
+    },
+    RoundSettled {
+        result: GameResult,
+        /// Net winnings for each unit wagered
+        payout: f64,
+    },
+}
+
+/// Receives every [TableEvent] at a table it is subscribed to with
+/// [BlackjackTable::subscribe](super::round::BlackjackTable::subscribe)
+///
+/// Implemented for closures, e.g.
+/// `table.subscribe(|event: &TableEvent| println!("{event:?}"))`
+pub trait TableObserver: Send {
+    fn on_event(&mut self, event: &TableEvent);
+}
+
+impl<F: FnMut(&TableEvent) + Send> TableObserver for F {
+    fn on_event(&mut self, event: &TableEvent) {
+        self(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use crate::game_rules::count::RunningCount;
+    use crate::game_rules::round::{BlackjackTable, GameStartResult, PlayerTurnResult};
+    use crate::game_rules::testing::play_dealer;
+
+    use super::{Seat, TableEvent, TableObserver, Visibility};
+
+    #[test]
+    fn observers_can_keep_the_count() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let count = Arc::new(Mutex::new(RunningCount::new()));
+
+        let mut table = BlackjackTable::default();
+
+        let log = events.clone();
+        table.subscribe(move |event: &TableEvent| log.lock().unwrap().push(event.clone()));
+
+        // Count every card the observer is shown, as a player at the table would
+        let counter = count.clone();
+        table.subscribe(move |event: &TableEvent| counter.lock().unwrap().on_event(event));
+
+        let running = || count.lock().unwrap().running();
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut table = table.reshuffle_with(&mut rng);
+
+        assert!(matches!(
+            &events.lock().unwrap()[0],
+            TableEvent::ShoeShuffled { cards, .. } if *cards == table.cards_remaining()
+        ));
+
+        for _ in 0..30 {
+            events.lock().unwrap().clear();
+
+            let finished = match table.deal().unwrap() {
+                GameStartResult::Natural(t) => t,
+                GameStartResult::Normal(t) => 'round: {
+                    assert_eq!(running(), t.running_count().running());
+
+                    // The hole card is dealt face down and not shown to observers
+                    assert_eq!(events.lock().unwrap()[3], TableEvent::CardDealt {
+                        card: None,
+                        to: Seat::Dealer,
+                        visibility: Visibility::FaceDown,
+                    });
+
+                    let dealer_turn = match t.hit().unwrap() {
+                        PlayerTurnResult::Hit(t) => {
+                            assert_eq!(running(), t.running_count().running());
+                            t.stand()
+                        },
+                        PlayerTurnResult::Bust(t) => break 'round t,
+                    };
+
+                    assert_eq!(running(), dealer_turn.running_count().running());
+
+                    play_dealer(dealer_turn)
+                },
+            };
+
+            assert_eq!(running(), finished.running_count().running());
+
+            let events = events.lock().unwrap();
+
+            assert_eq!(events.iter().filter(|e| matches!(e, TableEvent::RoundSettled { .. })).count(), 1);
+            assert_eq!(events.last(), Some(&TableEvent::RoundSettled {
+                result: *finished.result(),
+                payout: finished.payout(),
+            }));
+
+            drop(events);
+            table = finished.next_round();
+        }
+
+        assert_eq!(running(), table.running_count().running());
+    }
+}
@@ -0,0 +1,417 @@
+use std::fmt::Display;
+#[cfg(feature = "serde")]
+use std::fs::{self, File};
+#[cfg(feature = "serde")]
+use std::io::{self, BufReader, BufWriter, Write};
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub use super::action::TableAction;
+use super::cards::Card;
+use super::count::RunningCount;
+use super::events::TableObserver;
+use super::hand::Hand;
+#[cfg(feature = "serde")]
+use super::history::HistoryLog;
+use super::round::{
+    BlackjackRoundError,
+    BlackjackTable,
+    DealerTurn,
+    DealerTurnResult,
+    GameOver,
+    GameResult,
+    GameStartResult,
+    NotStarted,
+    PlayerTurn,
+    PlayerTurnResult,
+};
+use super::rules::Rules;
+use super::session::Session;
+
+/// The reason [AnyTable::apply] could not take an action
+pub struct ApplyError {
+    pub error: BlackjackRoundError,
+    /// The table as it was before the action, if the action
+    /// could not be taken at all. A table whose round could not
+    /// be continued part way through is lost
+    pub table: Option<Box<AnyTable>>,
+}
+
+impl Display for ApplyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::fmt::Debug for ApplyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApplyError")
+            .field("error", &self.error)
+            .field("table_kept", &self.table.is_some())
+            .finish()
+    }
+}
+
+impl std::error::Error for ApplyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The result of applying a [TableAction] to an [AnyTable]
+pub type ApplyResult = Result<AnyTable, ApplyError>;
+
+/// A [BlackjackTable] in any of its states
+///
+/// The state is only known when the program runs, so actions are
+/// checked as they are applied rather than by the compiler.
+/// This lets frontends, bots and network code drive a game from
+/// actions they are sent, while the typestate API can still be used
+/// by matching on the state
+///
+/// # Example
+///
+/// Play a round by always taking the last legal action,
+/// which stands on whatever is dealt:
+/// ```
+/// use blackjack::game::{AnyTable, TableAction};
+/// use blackjack::round::BlackjackTable;
+///
+/// let mut table = AnyTable::from(BlackjackTable::default().reshuffle());
+///
+/// while table.result().is_none() {
+///     let action = *table.legal_actions().last().unwrap();
+///     table = table.apply(action).unwrap();
+/// }
+///
+/// assert_eq!(table.legal_actions(), vec![TableAction::NextRound]);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "state", content = "table", rename_all = "snake_case"))]
+pub enum AnyTable {
+    NotStarted(BlackjackTable<NotStarted>),
+    PlayerTurn(BlackjackTable<PlayerTurn>),
+    DealerTurn(BlackjackTable<DealerTurn>),
+    GameOver(BlackjackTable<GameOver>),
+}
+
+/// Evaluate an expression with the table in whichever state it is in
+macro_rules! with_table {
+    ($table:expr, $t:ident => $e:expr) => {
+        match $table {
+            AnyTable::NotStarted($t) => $e,
+            AnyTable::PlayerTurn($t) => $e,
+            AnyTable::DealerTurn($t) => $e,
+            AnyTable::GameOver($t) => $e,
+        }
+    };
+}
+
+impl AnyTable {
+    /// Returns the actions that can be taken in the table's current state
+    pub fn legal_actions(&self) -> Vec<TableAction> {
+        match self {
+            Self::NotStarted(_) => vec![TableAction::Reshuffle, TableAction::Deal],
+            Self::PlayerTurn(_) => vec![TableAction::Hit, TableAction::Stand],
+            Self::DealerTurn(_) => vec![TableAction::DealerPlay],
+            Self::GameOver(_) => vec![TableAction::NextRound],
+        }
+    }
+
+    /// Returns true if the action can be taken in the table's current state
+    pub fn is_legal(&self, action: TableAction) -> bool {
+        self.legal_actions().contains(&action)
+    }
+
+    /// Take an action, returning the table in its new state
+    ///
+    /// An action that is not in [AnyTable::legal_actions], or a deal
+    /// with [Rules] that cannot be played or a wager that is not positive,
+    /// gives back the table unchanged in the [ApplyError]
+    pub fn apply(self, action: TableAction) -> ApplyResult {
+        if let (Self::NotStarted(t), TableAction::Deal) = (&self, action) {
+            if let Err(error) = t.check_deal() {
+                return Err(self.rejected(error));
+            }
+        }
+
+        let lost = |error| ApplyError { error, table: None };
+
+        let table = match (self, action) {
+            (Self::NotStarted(t), TableAction::Reshuffle) => Self::NotStarted(t.reshuffle()),
+            (Self::NotStarted(t), TableAction::Deal) => match t.deal().map_err(lost)? {
+                GameStartResult::Natural(t) => Self::GameOver(t),
+                GameStartResult::Normal(t) => Self::PlayerTurn(t),
+            },
+            (Self::PlayerTurn(t), TableAction::Hit) => match t.hit().map_err(lost)? {
+                PlayerTurnResult::Hit(t) => Self::PlayerTurn(t),
+                PlayerTurnResult::Bust(t) => Self::GameOver(t),
+            },
+            (Self::PlayerTurn(t), TableAction::Stand) => Self::DealerTurn(t.stand()),
+            (Self::DealerTurn(t), TableAction::DealerPlay) => match t.hit().map_err(lost)? {
+                DealerTurnResult::Hit(t) => Self::DealerTurn(t),
+                DealerTurnResult::Stand(t) => Self::GameOver(t),
+            },
+            (Self::GameOver(t), TableAction::NextRound) => Self::NotStarted(t.next_round()),
+            (table, action) => {
+                let legal = table.legal_actions();
+
+                return Err(table.rejected(BlackjackRoundError::IllegalAction { action, legal }));
+            },
+        };
+
+        Ok(table)
+    }
+
+    /// Returns an error that hands back the table unchanged
+    fn rejected(self, error: BlackjackRoundError) -> ApplyError {
+        ApplyError { error, table: Some(Box::new(self)) }
+    }
+
+    /// Returns the result of the round, if it is over
+    pub fn result(&self) -> Option<GameResult> {
+        match self {
+            Self::GameOver(t) => Some(*t.result()),
+            _ => None,
+        }
+    }
+
+    /// Returns the net winnings of the round, if it is over
+    pub fn payout(&self) -> Option<f64> {
+        match self {
+            Self::GameOver(t) => Some(t.payout()),
+            _ => None,
+        }
+    }
+
+    /// Returns the dealer's cards that can be seen,
+    /// which leaves out the hole card during the player's turn
+    pub fn visible_dealer_cards(&self) -> &[Card] {
+        match self {
+            Self::PlayerTurn(t) => &t.dealer_hand().cards()[..1],
+            _ => self.dealer_hand().cards(),
+        }
+    }
+
+    pub fn showing_card(&self) -> Option<&Card> {
+        with_table!(self, t => t.showing_card())
+    }
+
+    pub fn player_hand(&self) -> &Hand {
+        with_table!(self, t => t.player_hand())
+    }
+
+    pub fn dealer_hand(&self) -> &Hand {
+        with_table!(self, t => t.dealer_hand())
+    }
+
+    pub fn rules(&self) -> &Rules {
+        with_table!(self, t => t.rules())
+    }
+
+    pub fn session(&self) -> &Session {
+        with_table!(self, t => t.session())
+    }
+
+    /// Returns the units wagered on each round
+    pub fn wager(&self) -> f64 {
+        with_table!(self, t => t.wager())
+    }
+
+    pub fn cards_remaining(&self) -> usize {
+        with_table!(self, t => t.cards_remaining())
+    }
+
+    pub fn running_count(&self) -> RunningCount {
+        with_table!(self, t => t.running_count())
+    }
+
+    /// See [BlackjackTable::subscribe]
+    pub fn subscribe<O: TableObserver + 'static>(&mut self, observer: O) {
+        with_table!(self, t => t.subscribe(observer))
+    }
+
+    /// See [BlackjackTable::with_history]
+    #[cfg(feature = "serde")]
+    pub fn with_history(self, log: HistoryLog) -> Self {
+        match self {
+            Self::NotStarted(t) => Self::NotStarted(t.with_history(log)),
+            Self::PlayerTurn(t) => Self::PlayerTurn(t.with_history(log)),
+            Self::DealerTurn(t) => Self::DealerTurn(t.with_history(log)),
+            Self::GameOver(t) => Self::GameOver(t.with_history(log)),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn history(&self) -> Option<&HistoryLog> {
+        with_table!(self, t => t.history())
+    }
+
+    /// Write the table to the file at `path` as JSON, in whichever
+    /// state it was left in, creating its directory if needed
+    ///
+    /// The table's history log and observers are not saved,
+    /// and must be attached again
+    #[cfg(feature = "serde")]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /// Read a table written by [AnyTable::save]
+    #[cfg(feature = "serde")]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+impl From<BlackjackTable<NotStarted>> for AnyTable {
+    fn from(table: BlackjackTable<NotStarted>) -> Self {
+        Self::NotStarted(table)
+    }
+}
+
+impl From<BlackjackTable<PlayerTurn>> for AnyTable {
+    fn from(table: BlackjackTable<PlayerTurn>) -> Self {
+        Self::PlayerTurn(table)
+    }
+}
+
+impl From<BlackjackTable<DealerTurn>> for AnyTable {
+    fn from(table: BlackjackTable<DealerTurn>) -> Self {
+        Self::DealerTurn(table)
+    }
+}
+
+impl From<BlackjackTable<GameOver>> for AnyTable {
+    fn from(table: BlackjackTable<GameOver>) -> Self {
+        Self::GameOver(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use crate::game_rules::round::{
+        BlackjackRoundError,
+        BlackjackTable,
+        GameStartResult,
+        PlayerTurnResult
+    };
+    use crate::game_rules::rules::Rules;
+    use crate::game_rules::testing::play_dealer;
+
+    use super::{AnyTable, ApplyError, TableAction};
+
+    #[test]
+    fn actions_follow_the_typestates() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut typed = BlackjackTable::default().reshuffle_with(&mut rng);
+
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut table = AnyTable::from(BlackjackTable::default().reshuffle_with(&mut rng));
+
+        for round in 0..20 {
+            assert_eq!(table.legal_actions(), vec![TableAction::Reshuffle, TableAction::Deal]);
+
+            // Hit once every other round, then stand
+            let finished = match typed.deal().unwrap() {
+                GameStartResult::Natural(t) => t,
+                GameStartResult::Normal(t) => 'round: {
+                    let t = match round % 2 {
+                        0 => match t.hit().unwrap() {
+                            PlayerTurnResult::Hit(t) => t,
+                            PlayerTurnResult::Bust(t) => break 'round t,
+                        },
+                        _ => t,
+                    };
+
+                    play_dealer(t.stand())
+                },
+            };
+
+            table = table.apply(TableAction::Deal).unwrap();
+
+            if round % 2 == 0 && matches!(table, AnyTable::PlayerTurn(_)) {
+                table = table.apply(TableAction::Hit).unwrap();
+            }
+
+            if let AnyTable::PlayerTurn(_) = table {
+                assert_eq!(table.visible_dealer_cards().len(), 1);
+                table = table.apply(TableAction::Stand).unwrap();
+            }
+
+            while let AnyTable::DealerTurn(_) = table {
+                assert_eq!(table.legal_actions(), vec![TableAction::DealerPlay]);
+                table = table.apply(TableAction::DealerPlay).unwrap();
+            }
+
+            assert_eq!(table.result(), Some(*finished.result()));
+            assert_eq!(table.payout(), Some(finished.payout()));
+            assert_eq!(table.player_hand().cards(), finished.player_hand().cards());
+            assert_eq!(table.visible_dealer_cards(), finished.dealer_hand().cards());
+
+            typed = finished.next_round();
+            table = table.apply(TableAction::NextRound).unwrap();
+        }
+
+        assert_eq!(table.session(), typed.session());
+        assert_eq!(table.cards_remaining(), typed.cards_remaining());
+    }
+
+    #[test]
+    fn illegal_actions_hand_back_the_table() {
+        let table = AnyTable::from(BlackjackTable::default().reshuffle());
+
+        for action in [TableAction::Hit, TableAction::Stand, TableAction::DealerPlay, TableAction::NextRound] {
+            assert!(!table.is_legal(action));
+        }
+
+        let table = match table.apply(TableAction::Stand) {
+            Err(ApplyError { error: BlackjackRoundError::IllegalAction { action, legal }, table: Some(table) }) => {
+                assert_eq!(action, TableAction::Stand);
+                assert_eq!(legal, vec![TableAction::Reshuffle, TableAction::Deal]);
+                *table
+            },
+            _ => panic!("standing before the deal was allowed"),
+        };
+
+        assert!(matches!(table, AnyTable::NotStarted(_)));
+        assert_eq!(table.cards_remaining(), 312);
+
+        let rules = Rules { penetration: 1.5, ..Rules::default() };
+
+        match AnyTable::from(BlackjackTable::with_rules(rules)).apply(TableAction::Deal) {
+            Err(ApplyError { error: BlackjackRoundError::RuleViolation(_), table: Some(table) }) => {
+                assert!(table.player_hand().cards().is_empty());
+            },
+            _ => panic!("a round was dealt with rules that cannot be played"),
+        }
+
+        for wager in [f64::NAN, 0.0, -1.0] {
+            let table = AnyTable::from(BlackjackTable::default().with_bankroll(40.0).with_wager(wager).reshuffle());
+
+            match table.apply(TableAction::Deal) {
+                Err(ApplyError { error: BlackjackRoundError::InvalidWager(_), table: Some(table) }) => {
+                    assert!(matches!(*table, AnyTable::NotStarted(_)));
+                    assert_eq!(table.cards_remaining(), 312);
+                    assert_eq!(table.session().bankroll, 40.0);
+                },
+                _ => panic!("a round was dealt with a wager of {wager}"),
+            }
+        }
+    }
+}
@@ -1,10 +1,29 @@
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use super::cards::{Card, CardFace};
 
 /// A player's hand. Wrapper struct for a vec of Cards.
 /// 
 /// Can also provide the total value of the hand, accounting
 /// for Aces being either 11 or 1
+///
+/// # Example
+///
+/// ```
+/// use blackjack::hand::Hand;
+///
+/// let mut hand = Hand::new(vec!["AS".parse().unwrap(), "6H".parse().unwrap()]);
+/// assert_eq!(hand.total_value(), 17);
+/// assert!(hand.is_soft());
+///
+/// hand.push("KD".parse().unwrap());
+/// assert_eq!(hand.total_value(), 17);
+/// assert!(!hand.is_soft());
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Hand(Vec<Card>);
 
 impl Hand {
@@ -20,6 +39,11 @@ impl Hand {
         self.0.push(card);
     }
 
+    /// Consume the hand, returning the cards it held
+    pub fn into_cards(self) -> Vec<Card> {
+        self.0
+    }
+
     fn values(&self) -> Vec<ValueInHand> {
         self.cards().iter().map(|c| {
             match c.face() {
@@ -58,6 +82,19 @@ impl Hand {
 
         sum
     }
+
+    /// Returns true if one of the hand's Aces is being counted as 11
+    pub fn is_soft(&self) -> bool {
+        let hard_total: u8 = self.cards().iter().map(|c| u8::from(*c)).sum();
+        let has_ace = self.cards().iter().any(|c| matches!(c.face(), CardFace::Ace));
+
+        has_ace && hard_total + 10 <= 21
+    }
+
+    /// Returns true if the hand is a two card 21
+    pub fn is_natural(&self) -> bool {
+        self.cards().len() == 2 && self.total_value() == 21
+    }
 }
 
 impl Default for Hand {
@@ -128,6 +165,34 @@ mod tests {
         assert_eq!(h.total_value(), 15)
     }
 
+    #[test]
+    fn soft_hands_and_naturals() {
+        let h = Hand::new(vec![
+            Card::new(CardFace::Ace, CardSuit::Clubs),
+            Card::new(CardFace::try_from(6).unwrap(), CardSuit::Hearts)
+        ]);
+
+        assert!(h.is_soft());
+        assert!(!h.is_natural());
+
+        let h = Hand::new(vec![
+            Card::new(CardFace::Ace, CardSuit::Clubs),
+            Card::new(CardFace::try_from(6).unwrap(), CardSuit::Hearts),
+            Card::new(CardFace::try_from(9).unwrap(), CardSuit::Hearts)
+        ]);
+
+        assert!(!h.is_soft());
+        assert_eq!(h.total_value(), 16);
+
+        let h = Hand::new(vec![
+            Card::new(CardFace::Ace, CardSuit::Clubs),
+            Card::new(CardFace::Queen, CardSuit::Hearts)
+        ]);
+
+        assert!(h.is_soft());
+        assert!(h.is_natural());
+    }
+
     #[test]
     fn push_to_values() {
         let mut h = Hand::new(vec![
@@ -150,4 +215,22 @@ mod tests {
         assert_eq!(i.next(), Some(ValueInHand::Set(6)));
         assert_eq!(i.next(), None);
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips() {
+        let h = Hand::new(vec![
+            Card::new(CardFace::Ace, CardSuit::Hearts),
+            Card::new(CardFace::Number(10), CardSuit::Clubs),
+        ]);
+
+        let json = serde_json::to_string(&h).unwrap();
+        assert_eq!(json, "[\"AH\",\"10C\"]");
+
+        let json: Hand = serde_json::from_str(&json).unwrap();
+        let compact: Hand = bincode::deserialize(&bincode::serialize(&h).unwrap()).unwrap();
+
+        assert_eq!(json.cards(), h.cards());
+        assert_eq!(compact.cards(), h.cards());
+    }
+}
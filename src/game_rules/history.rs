@@ -0,0 +1,502 @@
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::cards::Card;
+use super::events::{DealerAction, Seat, TableEvent, TableObserver};
+use super::hand::Hand;
+use super::round::GameResult;
+use super::rules::Rules;
+use super::strategy::Action;
+
+/// A single line of the hand history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    /// Fingerprint of the shoe's shuffled order, shared by every round dealt from it
+    pub shoe: String,
+    /// Number of the round within the shoe, starting at 1,
+    /// or 0 for events before the first round
+    pub round: u64,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    /// The shoe was shuffled, starting a new shoe
+    ///
+    /// When the discards are shuffled part way through a round,
+    /// the rounds carry on being numbered within the same shoe
+    Shuffle {
+        rules: Rules,
+    },
+    Deal {
+        player: Vec<Card>,
+        dealer_upcard: Card,
+        dealer_hole: Card,
+    },
+    PlayerAction {
+        action: Action,
+        /// The card drawn by the action, if any
+        card: Option<Card>,
+        player_total: u8,
+    },
+    DealerDraw {
+        card: Card,
+        dealer_total: u8,
+    },
+    Result {
+        result: GameResult,
+        /// Net winnings for each unit wagered
+        payout: f64,
+        player_total: u8,
+        dealer_total: u8,
+    },
+}
+
+/// Writes the events of every round played at a table as JSON Lines
+///
+/// Attach a log to a table with
+/// [BlackjackTable::with_history](super::round::BlackjackTable::with_history),
+/// which feeds it the table's [TableEvent]s. The deal is written once the
+/// dealer's hole card is revealed, followed by the actions taken before then.
+/// If writing fails, the log stops writing and keeps the error,
+/// so that a failing log never interrupts a round
+pub struct HistoryLog {
+    writer: Box<dyn Write + Send>,
+    shoe: String,
+    round: u64,
+    rules: Rules,
+    /// The hands of the round in play, without the dealer's hole card until it is revealed
+    player: Hand,
+    dealer: Hand,
+    /// The player's action waiting for the card it draws
+    drawing: Option<Action>,
+    /// The events of the round held back until the hole card is revealed
+    held: Option<Vec<EventKind>>,
+    /// The log was attached part way through a round, which is not written
+    joined_mid_round: bool,
+    error: Option<io::Error>,
+}
+
+impl HistoryLog {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self {
+            writer: Box::new(writer),
+            shoe: String::new(),
+            round: 0,
+            rules: Rules::default(),
+            player: Hand::default(),
+            dealer: Hand::default(),
+            drawing: None,
+            held: None,
+            joined_mid_round: false,
+            error: None,
+        }
+    }
+
+    /// Open a log that appends to the file at `path`, creating it if needed
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file: File = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Returns the error that stopped the log from writing, if any
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Start logging a table, whose remaining shoe is logged as a new shoe
+    pub(super) fn attach(&mut self, shoe: String, rules: Rules, mid_round: bool) {
+        self.rules = rules;
+        self.joined_mid_round = mid_round;
+
+        self.new_shoe(shoe);
+    }
+
+    /// Start numbering rounds from a newly shuffled shoe
+    fn new_shoe(&mut self, shoe: String) {
+        self.shoe = shoe;
+        self.round = 0;
+
+        self.write(EventKind::Shuffle { rules: self.rules });
+    }
+
+    /// Write the event, or hold it back if the deal has not been written yet
+    fn write(&mut self, kind: EventKind) {
+        match &mut self.held {
+            Some(held) => held.push(kind),
+            None => self.record(kind),
+        }
+    }
+
+    fn record(&mut self, kind: EventKind) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let event = HistoryEvent { shoe: self.shoe.clone(), round: self.round, kind };
+
+        let written = serde_json::to_writer(&mut self.writer, &event)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(self.writer))
+            .and_then(|_| self.writer.flush());
+
+        if let Err(e) = written {
+            self.error = Some(e);
+        }
+    }
+
+    fn in_round(&self) -> bool {
+        self.joined_mid_round || !self.player.cards().is_empty()
+    }
+}
+
+impl TableObserver for HistoryLog {
+    fn on_event(&mut self, event: &TableEvent) {
+        match event {
+            // The rounds carry on being numbered within the same shoe
+            // if the discards are shuffled part way through a round
+            TableEvent::ShoeShuffled { shoe, .. } if !self.in_round() => self.new_shoe(shoe.clone()),
+            TableEvent::ShoeShuffled { .. } => self.write(EventKind::Shuffle { rules: self.rules }),
+
+            _ if self.joined_mid_round => {
+                if let TableEvent::RoundSettled { .. } = event {
+                    self.joined_mid_round = false;
+                }
+            },
+
+            TableEvent::CardDealt { card: Some(card), to: Seat::Player, .. } => {
+                if self.player.cards().is_empty() {
+                    self.round += 1;
+                }
+
+                self.player.push(*card);
+
+                if let Some(action) = self.drawing.take() {
+                    let player_total = self.player.total_value();
+                    self.write(EventKind::PlayerAction { action, card: Some(*card), player_total });
+                }
+            },
+
+            // The upcard, or a card the dealer drew after revealing the hole card
+            TableEvent::CardDealt { card: Some(card), to: Seat::Dealer, .. } => {
+                self.dealer.push(*card);
+
+                if self.dealer.cards().len() > 2 {
+                    let dealer_total = self.dealer.total_value();
+                    self.write(EventKind::DealerDraw { card: *card, dealer_total });
+                }
+            },
+
+            TableEvent::CardDealt { card: None, .. } => self.held = Some(Vec::new()),
+
+            TableEvent::PlayerAction { action: Action::Hit } => self.drawing = Some(Action::Hit),
+            TableEvent::PlayerAction { action } => {
+                let player_total = self.player.total_value();
+                self.write(EventKind::PlayerAction { action: *action, card: None, player_total });
+            },
+
+            TableEvent::DealerAction { action: DealerAction::RevealHoleCard { card } } => {
+                self.dealer.push(*card);
+
+                if let Some(held) = self.held.take() {
+                    self.record(EventKind::Deal {
+                        player: self.player.cards()[..2].to_vec(),
+                        dealer_upcard: self.dealer.cards()[0],
+                        dealer_hole: *card,
+                    });
+
+                    for kind in held {
+                        self.record(kind);
+                    }
+                }
+            },
+
+            TableEvent::DealerAction { .. } => {},
+
+            TableEvent::RoundSettled { result, payout } => {
+                self.write(EventKind::Result {
+                    result: *result,
+                    payout: *payout,
+                    player_total: self.player.total_value(),
+                    dealer_total: self.dealer.total_value(),
+                });
+
+                self.player = Hand::default();
+                self.dealer = Hand::default();
+                self.drawing = None;
+                self.held = None;
+            },
+        }
+    }
+}
+
+/// A round read back from a hand history
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRound {
+    pub shoe: String,
+    pub round: u64,
+    /// The rules of the most recent shuffle, or the default rules if none was recorded
+    pub rules: Rules,
+    pub player: Vec<Card>,
+    pub dealer_upcard: Card,
+    pub dealer_hole: Card,
+    /// Each action the player took, with the card it drew
+    pub actions: Vec<(Action, Option<Card>)>,
+    pub dealer_draws: Vec<Card>,
+    /// The result and payout, if the round was finished
+    pub result: Option<(GameResult, f64)>,
+}
+
+impl RecordedRound {
+    /// Returns every card of the round in the order it was drawn from the shoe
+    pub fn cards_in_draw_order(&self) -> Vec<Card> {
+        let mut cards = Vec::new();
+
+        // The player and dealer are dealt a card each in turn
+        for (i, player_card) in self.player.iter().enumerate() {
+            cards.push(*player_card);
+
+            match i {
+                0 => cards.push(self.dealer_upcard),
+                1 => cards.push(self.dealer_hole),
+                _ => {},
+            }
+        }
+
+        cards.extend(self.actions.iter().filter_map(|(_, card)| *card));
+        cards.extend(self.dealer_draws.iter().copied());
+
+        cards
+    }
+}
+
+#[derive(Debug)]
+pub enum HistoryReadError {
+    Io(io::Error),
+    Parse { line: usize, error: serde_json::Error },
+}
+
+impl Display for HistoryReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryReadError::Io(e) => write!(f, "{e}"),
+            HistoryReadError::Parse { line, error } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for HistoryReadError {}
+
+/// Read a hand history written by a [HistoryLog], grouping its events into rounds
+///
+/// Events of a round that was not dealt in the history are skipped
+pub fn read_history<R: BufRead>(reader: R) -> Result<Vec<RecordedRound>, HistoryReadError> {
+    let mut rounds: Vec<RecordedRound> = Vec::new();
+    let mut rules = Rules::default();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(HistoryReadError::Io)?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event: HistoryEvent = serde_json::from_str(&line)
+            .map_err(|error| HistoryReadError::Parse { line: i + 1, error })?;
+
+        if let EventKind::Shuffle { rules: r } = event.kind {
+            rules = r;
+            continue;
+        }
+
+        if let EventKind::Deal { player, dealer_upcard, dealer_hole } = event.kind {
+            rounds.push(RecordedRound {
+                shoe: event.shoe,
+                round: event.round,
+                rules,
+                player,
+                dealer_upcard,
+                dealer_hole,
+                actions: Vec::new(),
+                dealer_draws: Vec::new(),
+                result: None,
+            });
+
+            continue;
+        }
+
+        let current = match rounds.last_mut() {
+            Some(r) if r.shoe == event.shoe && r.round == event.round => r,
+            _ => continue,
+        };
+
+        match event.kind {
+            EventKind::PlayerAction { action, card, .. } => current.actions.push((action, card)),
+            EventKind::DealerDraw { card, .. } => current.dealer_draws.push(card),
+            EventKind::Result { result, payout, .. } => current.result = Some((result, payout)),
+            EventKind::Shuffle { .. } | EventKind::Deal { .. } => {},
+        }
+    }
+
+    Ok(rounds)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use crate::game_rules::round::{BlackjackTable, DealerTurnResult, GameStartResult, PlayerTurnResult};
+    use crate::game_rules::rules::Rules;
+    use crate::game_rules::testing::{play_dealer, stacked_deck};
+
+    use super::{read_history, HistoryLog};
+
+    /// A writer that can still be read after it has been moved into a log
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rounds_are_written_as_json_lines() {
+        let buffer = SharedBuffer::default();
+        let mut rng = StdRng::seed_from_u64(4);
+
+        let mut table = BlackjackTable::default()
+            .with_history(HistoryLog::new(buffer.clone()))
+            .reshuffle_with(&mut rng);
+
+        for _ in 0..5 {
+            let finished = match table.deal().unwrap() {
+                GameStartResult::Natural(t) => t,
+                GameStartResult::Normal(t) => {
+                    let t = match t.hit().unwrap() {
+                        PlayerTurnResult::Hit(t) => t,
+                        PlayerTurnResult::Bust(t) => {
+                            table = t.next_round();
+                            continue;
+                        },
+                    };
+
+                    play_dealer(t.stand())
+                },
+            };
+
+            table = finished.next_round();
+        }
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output.lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        // Attaching the log and then shuffling both start a shoe
+        assert_eq!(events[0]["event"], "shuffle");
+        assert_eq!(events[1]["event"], "shuffle");
+        assert_eq!(events[1]["rules"]["decks"], 6);
+
+        let shoe = &events[1]["shoe"];
+
+        assert_eq!(events[2]["event"], "deal");
+        assert_eq!(events[2]["round"], 1);
+        assert_eq!(events[2]["player"].as_array().unwrap().len(), 2);
+        assert!(events[1..].iter().all(|e| &e["shoe"] == shoe));
+        assert_eq!(events.iter().filter(|e| e["event"] == "deal").count(), 5);
+        assert_eq!(events.iter().filter(|e| e["event"] == "result").count(), 5);
+        assert_eq!(events.last().unwrap()["round"], 5);
+        assert!(events.iter().any(|e| e["event"] == "player_action" && e["action"] == "hit"));
+
+        let rounds = read_history(output.as_bytes()).unwrap();
+
+        assert_eq!(rounds.len(), 5);
+        assert!(rounds.iter().all(|r| r.result.is_some()));
+        assert_eq!(rounds[2].round, 3);
+        assert_eq!(rounds[0].cards_in_draw_order()[1], rounds[0].dealer_upcard);
+
+        assert!(read_history("{\"event\":\"deal\"}".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn the_deal_is_written_once_the_hole_card_is_revealed() {
+        let buffer = SharedBuffer::default();
+        let cards = ["5H", "10S", "6D", "7C", "2C", "9D", "9H", "10C", "8S"];
+        let table = BlackjackTable::with_deck(Rules::default(), stacked_deck(&cards))
+            .with_history(HistoryLog::new(buffer.clone()));
+
+        let Ok(GameStartResult::Normal(table)) = table.deal() else { panic!("the stacked shoe dealt a natural") };
+        let Ok(PlayerTurnResult::Hit(table)) = table.hit() else { panic!("the player went bust on 13") };
+        let Ok(DealerTurnResult::Stand(finished)) = table.stand().hit() else { panic!("the dealer hit 17") };
+
+        // A log attached part way through a round only writes the rounds after it
+        let late = SharedBuffer::default();
+        let table = finished.next_round().deal().unwrap();
+        let GameStartResult::Normal(table) = table else { panic!("the stacked shoe dealt a natural") };
+        let _ = table.with_history(HistoryLog::new(late.clone())).stand().hit();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output.lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        let kinds: Vec<&str> = events.iter().map(|e| e["event"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["shuffle", "deal", "player_action", "player_action", "result"]);
+
+        assert_eq!(events[1]["dealer_hole"], "7C");
+        assert_eq!(events[2]["card"], "2C");
+        assert_eq!(events[2]["player_total"], 13);
+        assert_eq!(events[3]["action"], "stand");
+        assert_eq!(events[4]["result"], "dealer_win");
+        assert_eq!(events[4]["dealer_total"], 17);
+
+        let rounds = read_history(output.as_bytes()).unwrap();
+        let drawn: Vec<String> = rounds[0].cards_in_draw_order().iter().map(|c| format!("{c:#}")).collect();
+        assert_eq!(drawn, cards[..5]);
+
+        let late = String::from_utf8(late.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(late.lines().count(), 1);
+        assert!(read_history(late.as_bytes()).unwrap().is_empty());
+    }
+    #[test]
+    fn rounds_that_end_early_write_one_result() {
+        let buffer = SharedBuffer::default();
+
+        // A natural, then a bust on 16
+        let cards = ["AS", "9S", "KD", "8C", "10H", "9D", "6D", "7C", "10C"];
+        let table = BlackjackTable::with_deck(Rules::default(), stacked_deck(&cards))
+            .with_history(HistoryLog::new(buffer.clone()));
+
+        let Ok(GameStartResult::Natural(natural)) = table.deal() else { panic!("the stacked shoe did not deal a natural") };
+        let Ok(GameStartResult::Normal(table)) = natural.next_round().deal() else { panic!("the stacked shoe dealt a natural") };
+        let Ok(PlayerTurnResult::Bust(_)) = table.hit() else { panic!("the player did not go bust on 16") };
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let results: Vec<serde_json::Value> = output.lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .filter(|e| e["event"] == "result")
+            .collect();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["result"], "player_win");
+        assert_eq!(results[1]["result"], "player_bust");
+    }
+}
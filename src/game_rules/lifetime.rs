@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::hand::Hand;
+use super::round::{BlackjackTable, GameOver, GameResult};
+
+/// The number of rounds ending in each [GameResult]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultCounts {
+    pub player_win: u64,
+    pub dealer_bust: u64,
+    pub dealer_win: u64,
+    pub player_bust: u64,
+    pub stand_off: u64,
+}
+
+impl ResultCounts {
+    fn record(&mut self, result: GameResult) {
+        match result {
+            GameResult::PlayerWin => self.player_win += 1,
+            GameResult::DealerBust => self.dealer_bust += 1,
+            GameResult::DealerWin => self.dealer_win += 1,
+            GameResult::PlayerBust => self.player_bust += 1,
+            GameResult::StandOff => self.stand_off += 1,
+        }
+    }
+
+    pub fn wins(&self) -> u64 {
+        self.player_win + self.dealer_bust
+    }
+
+    pub fn losses(&self) -> u64 {
+        self.dealer_win + self.player_bust
+    }
+
+    pub fn pushes(&self) -> u64 {
+        self.stand_off
+    }
+}
+
+/// The results of the rounds dealt a particular starting total and upcard
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundCounts {
+    pub hands: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub pushes: u64,
+    /// Net winnings in units
+    pub net: f64,
+}
+
+impl RoundCounts {
+    fn record(&mut self, result: GameResult, net: f64) {
+        self.hands += 1;
+        self.net += net;
+
+        match result {
+            GameResult::PlayerWin | GameResult::DealerBust => self.wins += 1,
+            GameResult::DealerWin | GameResult::PlayerBust => self.losses += 1,
+            GameResult::StandOff => self.pushes += 1,
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.hands += other.hands;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.pushes += other.pushes;
+        self.net += other.net;
+    }
+
+    /// Returns the fraction of hands won, or [None] if none were played
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.hands > 0).then(|| self.wins as f64 / self.hands as f64)
+    }
+}
+
+/// Statistics of every round played, accumulated across sessions
+/// in a file in the user's data directory
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    pub hands: u64,
+    pub results: ResultCounts,
+    pub blackjacks: u64,
+    /// Net winnings in units
+    pub net: f64,
+    pub longest_win_streak: u64,
+    pub longest_loss_streak: u64,
+    /// The current run of wins when positive, or of losses when negative.
+    /// Stand offs do not end a streak
+    pub streak: i64,
+    /// Results by the player's starting total and then the dealer's upcard,
+    /// with Aces valued 1 in the upcard
+    pub by_start: BTreeMap<u8, BTreeMap<u8, RoundCounts>>,
+}
+
+impl LifetimeStats {
+    /// Read the statistics from the file at `path`,
+    /// starting from nothing if the file does not exist yet
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        match File::open(path) {
+            Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the statistics to the file at `path` as JSON,
+    /// creating its directory if needed
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if let Some(dir) = path.as_ref().parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /// Add a finished round to the statistics kept in the file at `path`
+    pub fn update<P: AsRef<Path>>(path: P, table: &BlackjackTable<GameOver>) -> io::Result<()> {
+        let mut stats = Self::load(&path)?;
+
+        stats.record(table);
+        stats.save(path)
+    }
+
+    pub fn record(&mut self, table: &BlackjackTable<GameOver>) {
+        let result = *table.result();
+        let net = table.payout() * table.wager();
+
+        self.hands += 1;
+        self.net += net;
+        self.results.record(result);
+
+        if table.player_hand().is_natural() && result == GameResult::PlayerWin {
+            self.blackjacks += 1;
+        }
+
+        match result {
+            GameResult::PlayerWin | GameResult::DealerBust => {
+                self.streak = self.streak.max(0) + 1;
+                self.longest_win_streak = self.longest_win_streak.max(self.streak.unsigned_abs());
+            },
+            GameResult::DealerWin | GameResult::PlayerBust => {
+                self.streak = self.streak.min(0) - 1;
+                self.longest_loss_streak = self.longest_loss_streak.max(self.streak.unsigned_abs());
+            },
+            GameResult::StandOff => {},
+        }
+
+        let start = Hand::new(table.player_hand().cards().iter().take(2).copied().collect());
+        let upcard = table.showing_card().map_or(0, |c| u8::from(*c));
+
+        self.by_start
+            .entry(start.total_value())
+            .or_default()
+            .entry(upcard)
+            .or_default()
+            .record(result, net);
+    }
+
+    /// Returns the results for each dealer upcard, with Aces valued 1
+    pub fn by_upcard(&self) -> BTreeMap<u8, RoundCounts> {
+        let mut by_upcard: BTreeMap<u8, RoundCounts> = BTreeMap::new();
+
+        for (upcard, counts) in self.by_start.values().flatten() {
+            by_upcard.entry(*upcard).or_default().add(counts);
+        }
+
+        by_upcard
+    }
+
+    /// Returns the results for each of the player's starting totals
+    pub fn by_starting_total(&self) -> BTreeMap<u8, RoundCounts> {
+        self.by_start.iter()
+            .map(|(total, upcards)| {
+                let mut counts = RoundCounts::default();
+
+                for c in upcards.values() {
+                    counts.add(c);
+                }
+
+                (*total, counts)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use crate::game_rules::round::{BlackjackTable, GameStartResult};
+    use crate::game_rules::testing::play_dealer;
+
+    use super::LifetimeStats;
+
+    #[test]
+    fn rounds_accumulate_across_saves() {
+        let path = std::env::temp_dir().join(format!("blackjack-stats-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut rng = StdRng::seed_from_u64(8);
+        // The net winnings are counted in units, as the session counts them
+        let mut table = BlackjackTable::default().with_wager(2.5).reshuffle_with(&mut rng);
+        let mut expected = LifetimeStats::default();
+
+        for _ in 0..40 {
+            let finished = match table.deal().unwrap() {
+                GameStartResult::Natural(t) => t,
+                GameStartResult::Normal(t) => play_dealer(t.stand()),
+            };
+
+            LifetimeStats::update(&path, &finished).unwrap();
+            expected.record(&finished);
+
+            table = finished.next_round();
+        }
+
+        let stats = LifetimeStats::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(stats, expected);
+        assert_eq!(stats.hands, 40);
+        assert_eq!(stats.results.wins() + stats.results.losses() + stats.results.pushes(), 40);
+        assert_eq!(stats.results.player_bust, 0);
+        assert!(stats.longest_win_streak >= 1 && stats.longest_loss_streak >= 1);
+        assert!((stats.net - table.session().stats.net).abs() < 1e-9);
+
+        let by_upcard = stats.by_upcard();
+        let by_total = stats.by_starting_total();
+
+        assert_eq!(by_upcard.values().map(|c| c.hands).sum::<u64>(), 40);
+        assert_eq!(by_total.values().map(|c| c.hands).sum::<u64>(), 40);
+        assert!(by_upcard.keys().all(|u| (1..=10).contains(u)));
+        assert!(by_total.keys().all(|t| (4..=21).contains(t)));
+    }
+}
@@ -1,6 +1,25 @@
 
 pub mod round;
 pub mod cards;
-mod deck;
-mod hand;
-mod card_icons;
\ No newline at end of file
+pub mod deck;
+pub mod hand;
+pub mod count;
+pub mod rules;
+pub mod strategy;
+pub mod deviations;
+pub mod composition;
+pub mod ev;
+pub mod env;
+pub mod dealer_outcomes;
+#[cfg(feature = "serde")]
+pub mod history;
+pub mod events;
+pub mod game;
+pub mod session;
+pub mod shared;
+#[cfg(feature = "serde")]
+pub mod lifetime;
+mod card_icons;
+mod action;
+#[cfg(test)]
+mod testing;
@@ -1,24 +1,40 @@
 
-use super::{deck::Deck, hand::Hand, cards::Card};
-use std::fmt::Debug;
+use super::{deck::Deck, hand::Hand, cards::Card, rules::{Rules, RuleViolation}, composition::Composition, count::RunningCount};
+#[cfg(feature = "serde")]
+use super::history::HistoryLog;
+use super::events::{DealerAction, Seat, TableEvent, TableObserver, Visibility};
+use super::session::Session;
+use super::strategy::Action;
+use super::action::TableAction;
+use std::fmt::{Debug, Display};
+
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 // The game is a state machine with the following states
 // These states are types so that we can guard certain actions
 // So they can only occur during the appropriate game state.
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NotStarted;
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlayerTurn;
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DealerTurn;
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GameOver(GameResult);
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum GameResult {
     #[default] DealerWin,
     PlayerWin,
@@ -27,16 +43,77 @@ pub enum GameResult {
     StandOff
 }
 
-pub trait BlackjackTableState: Debug + Default + Sized {}
+impl GameResult {
+    /// Returns the result of comparing the hands once the dealer stands
+    ///
+    /// A dealer's natural beats any other 21
+    pub fn showdown(player: &Hand, dealer: &Hand) -> Self {
+        let dealer_value = dealer.total_value();
+        let player_value = player.total_value();
+
+        if player_value > 21 {
+            GameResult::PlayerBust
+        } else if dealer_value > 21 {
+            GameResult::DealerBust
+        } else if dealer.is_natural() || dealer_value > player_value {
+            GameResult::DealerWin
+        } else if dealer_value == player_value {
+            GameResult::StandOff
+        } else {
+            GameResult::PlayerWin
+        }
+    }
+}
+
+pub trait BlackjackTableState: Debug + Default + Sized {
+    /// Whether the dealer's hole card is face down in this state
+    const HOLE_CARD_HIDDEN: bool = false;
+}
 
 impl BlackjackTableState for NotStarted {}
-impl BlackjackTableState for PlayerTurn {}
+impl BlackjackTableState for PlayerTurn {
+    const HOLE_CARD_HIDDEN: bool = true;
+}
 impl BlackjackTableState for DealerTurn {}
 impl BlackjackTableState for GameOver {}
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BlackjackRoundError {
-    DeckEmpty
+    /// There were no cards left to draw, even after shuffling the discards
+    DeckEmpty,
+    /// The action cannot be taken in the table's current state
+    IllegalAction {
+        action: TableAction,
+        /// The actions that could have been taken instead
+        legal: Vec<TableAction>,
+    },
+    /// The table's [Rules] cannot be played, e.g. a shoe without any decks
+    RuleViolation(RuleViolation),
+    /// Wagers must be a positive number of units
+    InvalidWager(f64),
+}
+
+impl Display for BlackjackRoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeckEmpty => write!(f, "there are no cards left in the shoe"),
+            Self::IllegalAction { action, legal } => {
+                let legal: Vec<String> = legal.iter().map(|a| a.to_string()).collect();
+
+                write!(f, "cannot {action} now, expected one of: {}", legal.join(", "))
+            },
+            Self::RuleViolation(rule) => write!(f, "the rules cannot be played: {rule}"),
+            Self::InvalidWager(units) => write!(f, "cannot wager {units} units, wagers must be positive"),
+        }
+    }
+}
+
+impl std::error::Error for BlackjackRoundError {}
+
+impl From<RuleViolation> for BlackjackRoundError {
+    fn from(violation: RuleViolation) -> Self {
+        Self::RuleViolation(violation)
+    }
 }
 
 /// The blackjack table, containing all state necessary to keep
@@ -48,10 +125,31 @@ pub enum BlackjackRoundError {
 /// 
 /// The state of the game is also tracked, so that actions
 /// can only be taken during the appropriate game state
+/// 
+/// The player's [Session] is settled at the end of every round,
+/// with a wager of one unit unless another is set with [BlackjackTable::with_wager]
+/// 
+/// Observers can [subscribe](BlackjackTable::subscribe) to the
+/// [TableEvent]s of every transition, as they happen
+/// 
+/// If a [HistoryLog] is attached, it is sent the same events and writes
+/// every deal, action, dealer draw and result. Neither the log nor
+/// the observers are serialized with the rest of the table
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlackjackTable<S: BlackjackTableState = NotStarted> {
     deck: Deck,
     dealer: Hand,
     player: Hand,
+    rules: Rules,
+    session: Session,
+    /// Units wagered on each round
+    #[cfg_attr(feature = "serde", serde(default = "default_wager"))]
+    wager: f64,
+    #[cfg(feature = "serde")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: Option<HistoryLog>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    observers: Vec<Box<dyn TableObserver>>,
     game_state: S
 }
 
@@ -62,64 +160,136 @@ pub enum GameStartResult {
 
 impl BlackjackTable<NotStarted> {
 
+    /// Create a table with an unshuffled shoe built for the supplied [Rules]
+    pub fn with_rules(rules: Rules) -> Self {
+        Self::with_deck(rules, Deck::new(rules.decks))
+    }
+
+    /// Create a table that deals from the supplied deck,
+    /// e.g. a stacked one to replay a recorded round
+    pub fn with_deck(rules: Rules, deck: Deck) -> Self {
+        Self {
+            deck,
+            dealer: Hand::default(),
+            player: Hand::default(),
+            rules,
+            session: Session::default(),
+            wager: 1.0,
+            #[cfg(feature = "serde")]
+            history: None,
+            observers: Vec::new(),
+            game_state: NotStarted
+        }
+    }
+
+    /// Start the player's [Session] with the supplied bankroll, in units
+    pub fn with_bankroll(mut self, bankroll: f64) -> Self {
+        self.session = Session::new(bankroll);
+
+        self
+    }
+
+    /// Wager the supplied number of units on each round from now on
+    ///
+    /// The wager is checked when the next round is dealt
+    pub fn with_wager(mut self, units: f64) -> Self {
+        self.wager = units;
+
+        self
+    }
+
+    /// Returns true once the shoe has been dealt past the
+    /// penetration set in the table's [Rules]
+    pub fn needs_reshuffle(&self) -> bool {
+        let total = self.deck.remaining() + self.deck.discarded();
+
+        total == 0 || self.deck.discarded() as f64 / total as f64 >= self.rules.penetration
+    }
+
+    /// Shuffle the deck so that drawn cards are random
+    #[deprecated(note = "use `reshuffle`, which also returns the discard pile to the shoe")]
+    pub fn shuffle(self) -> Self {
+        self.reshuffle()
+    }
+
+    /// Return the discard pile to the shoe and shuffle it
+    pub fn reshuffle(self) -> Self {
+        self.reshuffle_with(&mut rand::thread_rng())
+    }
+
+    /// Return the discard pile to the shoe and shuffle it
+    /// with the supplied random number generator
+    pub fn reshuffle_with<R: Rng + ?Sized>(mut self, rng: &mut R) -> Self {
+        self.deck.reshuffle_with(rng);
+        self.emit(TableEvent::ShoeShuffled { cards: self.deck.remaining(), shoe: self.deck.fingerprint() });
+
+        self
+    }
+
+    /// Check that the table's [Rules] and wager allow a round to be dealt
+    pub(super) fn check_deal(&self) -> Result<(), BlackjackRoundError> {
+        self.rules.validate()?;
+
+        if !self.wager.is_finite() || self.wager <= 0.0 {
+            return Err(BlackjackRoundError::InvalidWager(self.wager));
+        }
+
+        Ok(())
+    }
 
     /// Start the game by dealing cards from the deck
     /// into the dealer's and player's hand.
     /// 
     /// The game can end immediately if the player, dealer, or both
-    /// draw a natural
+    /// draw a natural. If the dealer does not peek at their hole card,
+    /// the player still plays their hand against a dealer's natural
+    /// 
+    /// Returns a [BlackjackRoundError::RuleViolation] without dealing
+    /// if the table's [Rules] cannot be played, or a
+    /// [BlackjackRoundError::InvalidWager] if the wager is not positive
     pub fn deal(mut self) -> Result<GameStartResult, BlackjackRoundError> {
-
-        for _ in 0..2 {
-            self.player.push(
-                self.deck.draw()
-                .ok_or(BlackjackRoundError::DeckEmpty)?
-            );
-
-            self.dealer.push(
-                self.deck.draw()
-                .ok_or(BlackjackRoundError::DeckEmpty)?
-            );
-
+        self.check_deal()?;
+
+        // The dealer's second card is their hole card, dealt face down
+        for visibility in [Visibility::FaceUp, Visibility::FaceDown] {
+            let card = self.draw()?;
+            self.player.push(card);
+            self.emit(TableEvent::CardDealt { card: Some(card), to: Seat::Player, visibility: Visibility::FaceUp });
+
+            let card = self.draw()?;
+            self.dealer.push(card);
+            self.emit(TableEvent::CardDealt {
+                card: (visibility == Visibility::FaceUp).then_some(card),
+                to: Seat::Dealer,
+                visibility,
+            });
         }
 
         let player_score = self.player.total_value();
         let dealer_score = self.dealer.total_value();
 
+        if player_score >= 21 || (dealer_score >= 21 && self.rules.dealer_peeks) {
+            self.reveal_hole_card();
+        }
+
         if player_score >= 21 && dealer_score >= 21 {
             Ok(GameStartResult::Natural(
-                BlackjackTable::new(
-                    self.deck, 
-                    self.player,
-                    self.dealer
-                )
-                .with_result(GameResult::StandOff)
+                self.into_state()
+                .settle(GameResult::StandOff)
             ))
         } else if player_score >= 21 {
             Ok(GameStartResult::Natural(
-                BlackjackTable::new(
-                    self.deck, 
-                    self.player,
-                    self.dealer
-                )
-                .with_result(GameResult::PlayerWin)
+                self.into_state()
+                .settle(GameResult::PlayerWin)
             ))
-        } else if dealer_score >= 21 {
+        } else if dealer_score >= 21 && self.rules.dealer_peeks {
             Ok(GameStartResult::Natural(
-                BlackjackTable::new(
-                    self.deck, 
-                    self.player,
-                    self.dealer
-                )
-                .with_result(GameResult::DealerWin)
+                self.into_state()
+                .settle(GameResult::DealerWin)
             ))
         } else {
             Ok(GameStartResult::Normal(
-                BlackjackTable::new(
-                    self.deck, 
-                    self.player,
-                    self.dealer
-                )
+                self.into_state()
             ))
         }
 
@@ -142,40 +312,45 @@ impl BlackjackTable<PlayerTurn> {
     /// Otherwise, returns a [PlayerTurnResult::Hit]
     pub fn hit(mut self) -> Result<PlayerTurnResult, BlackjackRoundError> {
 
-        self.player.push(
-            self.deck.draw()
-            .ok_or(BlackjackRoundError::DeckEmpty)?
-        );
+        let card = self.draw()?;
+        self.player.push(card);
 
+        self.emit(TableEvent::PlayerAction { action: Action::Hit });
+        self.emit(TableEvent::CardDealt { card: Some(card), to: Seat::Player, visibility: Visibility::FaceUp });
 
         if self.player.total_value() > 21 {
+            self.reveal_hole_card();
+
             Ok(PlayerTurnResult::Bust(
-                BlackjackTable::new(
-                    self.deck,
-                    self.player,
-                    self.dealer
-                )
-                .with_result(GameResult::PlayerBust)
+                self.into_state()
+                .settle(GameResult::PlayerBust)
             ))
         } else {
             Ok(PlayerTurnResult::Hit(
-                BlackjackTable::new(
-                    self.deck,
-                    self.player,
-                    self.dealer
-                )
+                self.into_state()
             ))
         }
     }
 
+    /// Returns the composition of every card the player cannot see,
+    /// i.e. the rest of the shoe and the dealer's hole card
+    pub fn unseen_cards(&self) -> Composition {
+        let mut unseen = self.deck.composition();
+
+        for card in self.dealer.cards().iter().skip(1) {
+            unseen.add(u8::from(*card));
+        }
+
+        unseen
+    }
+
     /// End the player's turn and start the dealer's turn
-    pub fn stand(self) -> BlackjackTable<DealerTurn> {
+    pub fn stand(mut self) -> BlackjackTable<DealerTurn> {
+
+        self.emit(TableEvent::PlayerAction { action: Action::Stand });
+        self.reveal_hole_card();
 
-        BlackjackTable::new(
-            self.deck,
-            self.player,
-            self.dealer
-        )
+        self.into_state()
     }
 }
 
@@ -188,27 +363,29 @@ impl BlackjackTable<DealerTurn> {
 
     /// Draw a card into the dealer's hand
     /// 
-    /// The game can end immediately if the dealer goes bust.
+    /// The game can end immediately if the dealer goes bust
+    /// or reaches a total they must stand on.
     /// In this case, function returns a [DealerTurnResult::Stand],
     /// which ends the game.
+    /// If the dealer's hand is already at a standing total,
+    /// no card is drawn.
     /// 
-    /// Otherwise, returns a [PlayerTurnResult::Hit]
+    /// Otherwise, returns a [DealerTurnResult::Hit]
     pub fn hit(mut self) -> Result<DealerTurnResult, BlackjackRoundError> {
 
-        self.dealer.push(
-            self.deck.draw()
-            .ok_or(BlackjackRoundError::DeckEmpty)?
-        );
+        if !self.dealer_must_hit() {
+            return Ok(DealerTurnResult::Stand(self.stand()));
+        }
 
-        let resulting_value = self.dealer.total_value();
+        let card = self.draw()?;
+        self.dealer.push(card);
 
-        if resulting_value < 17 {
+        self.emit(TableEvent::DealerAction { action: DealerAction::Hit });
+        self.emit(TableEvent::CardDealt { card: Some(card), to: Seat::Dealer, visibility: Visibility::FaceUp });
+
+        if self.dealer_must_hit() {
             Ok(DealerTurnResult::Hit(
-                BlackjackTable::new(
-                    self.deck,
-                    self.player,
-                    self.dealer
-                )
+                self.into_state()
             ))
         } else {
             Ok(DealerTurnResult::Stand(self.stand()))
@@ -216,32 +393,22 @@ impl BlackjackTable<DealerTurn> {
     }
 
     /// End the Dealer's turn immediately, ending the game
-    pub fn stand(self) -> BlackjackTable<GameOver> {
-        
-        let dealer_value = self.dealer.total_value();
-
-        if dealer_value > 21 {
-            BlackjackTable::new(
-                self.deck,
-                self.player,
-                self.dealer
-            )
-            .with_result(GameResult::DealerBust)
-        } else if dealer_value > self.player.total_value() {
-            BlackjackTable::new(
-                self.deck,
-                self.player,
-                self.dealer
-            )
-            .with_result(GameResult::DealerWin)
-        } else {
-            BlackjackTable::new(
-                self.deck,
-                self.player,
-                self.dealer
-            )
-            .with_result(GameResult::PlayerWin)
+    pub fn stand(mut self) -> BlackjackTable<GameOver> {
+
+        if self.dealer.total_value() <= 21 {
+            self.emit(TableEvent::DealerAction { action: DealerAction::Stand });
         }
+
+        let result = GameResult::showdown(&self.player, &self.dealer);
+
+        self.into_state()
+        .settle(result)
+    }
+
+    /// Returns true if the table's [Rules] require the
+    /// dealer to draw another card
+    fn dealer_must_hit(&self) -> bool {
+        self.rules.dealer_must_hit(&self.dealer)
     }
 }
 
@@ -253,28 +420,124 @@ impl BlackjackTable<GameOver> {
         &self.game_state.0
     }
 
-    /// Set the result of the finished game to the
-    /// supplied [GameResult] enum
-    pub fn with_result(self, res: GameResult) -> Self {
-        Self {
+    /// End the round with the supplied [GameResult], pay the player's [Session]
+    /// and tell the table's observers and history log that it is settled
+    ///
+    /// Only called as a round finishes, so each round is settled once
+    fn settle(self, res: GameResult) -> Self {
+        let mut table = Self {
             deck: self.deck,
             dealer: self.dealer,
             player: self.player,
+            rules: self.rules,
+            session: self.session,
+            wager: self.wager,
+            #[cfg(feature = "serde")]
+            history: self.history,
+            observers: self.observers,
             game_state: GameOver(res)
+        };
+
+        let payout = table.payout();
+        table.session.settle(res, payout * table.wager, table.player.is_natural());
+
+        table.emit(TableEvent::RoundSettled { result: res, payout });
+
+        table
+    }
+
+    /// Returns the player's net winnings for each unit wagered
+    /// 
+    /// A player's natural is paid at the rate set in the table's [Rules]
+    pub fn payout(&self) -> f64 {
+        self.rules.payout(*self.result(), &self.player)
+    }
+
+    /// Discard both hands and prepare the table for another round,
+    /// keeping the rest of the shoe
+    pub fn next_round(mut self) -> BlackjackTable<NotStarted> {
+        self.deck.discard(self.player.into_cards());
+        self.deck.discard(self.dealer.into_cards());
+
+        BlackjackTable {
+            deck: self.deck,
+            dealer: Hand::default(),
+            player: Hand::default(),
+            rules: self.rules,
+            session: self.session,
+            wager: self.wager,
+            #[cfg(feature = "serde")]
+            history: self.history,
+            observers: self.observers,
+            game_state: NotStarted
         }
     }
 }
 
 impl<S: BlackjackTableState> BlackjackTable<S> {
-    pub fn new(deck: Deck, player: Hand, dealer: Hand) -> Self {
-        Self { deck, dealer, player, game_state: S::default() }
+    /// Move the table into another state, keeping the shoe, hands and rules
+    fn into_state<T: BlackjackTableState>(self) -> BlackjackTable<T> {
+        BlackjackTable {
+            deck: self.deck,
+            dealer: self.dealer,
+            player: self.player,
+            rules: self.rules,
+            session: self.session,
+            wager: self.wager,
+            #[cfg(feature = "serde")]
+            history: self.history,
+            observers: self.observers,
+            game_state: T::default()
+        }
     }
 
-    /// Shuffle the deck so that drawn cards are random
-    pub fn shuffle(mut self) -> Self {
-        self.deck.shuffle();
+    /// Write the history of every round played at this table to the log
+    /// 
+    /// The remaining shoe is logged as a new shoe, so a table resumed
+    /// part way through a round logs the following rounds
+    #[cfg(feature = "serde")]
+    pub fn with_history(mut self, mut log: HistoryLog) -> Self {
+        log.attach(self.deck.fingerprint(), self.rules, !self.player.cards().is_empty());
+        self.history = Some(log);
+
+        self
+    }
 
-        Self::new(self.deck, self.player, self.dealer)
+    /// Draw the next card from the shoe
+    ///
+    /// If the shoe runs out part way through a round, the discards are
+    /// shuffled into a new shoe and the round carries on, as it would
+    /// in a casino. The cards in play stay on the table
+    fn draw(&mut self) -> Result<Card, BlackjackRoundError> {
+        if self.deck.reshuffle_discards() {
+            self.emit(TableEvent::ShoeShuffled { cards: self.deck.remaining(), shoe: self.deck.fingerprint() });
+        }
+
+        self.deck.draw().ok_or(BlackjackRoundError::DeckEmpty)
+    }
+
+    /// Register an observer to be sent every [TableEvent] at this table from now on
+    pub fn subscribe<O: TableObserver + 'static>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Send an event to the history log, if there is one, and every observer
+    fn emit(&mut self, event: TableEvent) {
+        #[cfg(feature = "serde")]
+        if let Some(log) = &mut self.history {
+            log.on_event(&event);
+        }
+
+        for observer in &mut self.observers {
+            observer.on_event(&event);
+        }
+    }
+
+    /// Turn the dealer's hole card face up
+    fn reveal_hole_card(&mut self) {
+        if let Some(card) = self.dealer.cards().get(1).copied() {
+            self.emit(TableEvent::DealerAction { action: DealerAction::RevealHoleCard { card } });
+        }
     }
 
     /// Returns an optional reference to the first
@@ -284,7 +547,7 @@ impl<S: BlackjackTableState> BlackjackTable<S> {
     /// Should only return [None] when the dealer's hand
     /// is empty, i.e. before the game has been dealt
     pub fn showing_card(&self) -> Option<&Card> {
-        self.dealer.cards().get(0)
+        self.dealer.cards().first()
     }
 
     /// Returns a reference to the player's [Hand]
@@ -296,14 +559,183 @@ impl<S: BlackjackTableState> BlackjackTable<S> {
     pub fn dealer_hand(&self) -> &Hand {
         &self.dealer
     }
+
+    /// Returns a reference to the [Rules] the table is played with
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Returns the player's bankroll and the results of their rounds
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Returns the units wagered on each round
+    pub fn wager(&self) -> f64 {
+        self.wager
+    }
+
+    /// Returns the table's history log, if it has one
+    #[cfg(feature = "serde")]
+    pub fn history(&self) -> Option<&HistoryLog> {
+        self.history.as_ref()
+    }
+
+    /// Returns the number of cards left in the shoe
+    pub fn cards_remaining(&self) -> usize {
+        self.deck.remaining()
+    }
+
+    /// Returns the Hi-Lo count of every card seen since the shoe
+    /// was last shuffled, i.e. the discard pile and the face up cards
+    pub fn running_count(&self) -> RunningCount {
+        let mut count = RunningCount::new();
+        let hidden = usize::from(S::HOLE_CARD_HIDDEN);
+
+        let dealer = self.dealer.cards().iter()
+            .enumerate()
+            .filter(|(i, _)| *i == 0 || *i > hidden)
+            .map(|(_, c)| c);
+
+        for card in self.deck.discard_pile().iter().chain(self.player.cards()).chain(dealer) {
+            count.observe(card);
+        }
+
+        count
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_wager() -> f64 {
+    1.0
 }
 
 impl Default for BlackjackTable {
     fn default() -> Self {
-        Self::new(
-            Default::default(),
-            Default::default(),
-            Default::default()
-        )
+        Self::with_rules(Rules::default())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::game_rules::events::TableEvent;
+    use crate::game_rules::rules::Rules;
+    use crate::game_rules::testing::{play_dealer, stacked};
+
+    use super::{BlackjackRoundError, BlackjackTable, GameResult, GameStartResult, PlayerTurnResult};
+
+    #[test]
+    #[allow(deprecated)]
+    fn shuffle_still_shuffles_the_whole_shoe() {
+        let finished = match BlackjackTable::default().reshuffle().deal().unwrap() {
+            GameStartResult::Natural(t) => t,
+            GameStartResult::Normal(t) => t.stand().stand(),
+        };
+
+        assert_eq!(finished.next_round().shuffle().cards_remaining(), 312);
+    }
+
+    #[test]
+    fn discards_are_shuffled_to_finish_a_round() {
+        let mut table = stacked(&["10H", "9S", "7D", "8C", "5H", "6S"]);
+
+        let shuffles = Arc::new(Mutex::new(0));
+        let counter = shuffles.clone();
+
+        table.subscribe(move |event: &TableEvent| {
+            if let TableEvent::ShoeShuffled { .. } = event {
+                *counter.lock().unwrap() += 1;
+            }
+        });
+
+        let first = match table.deal().unwrap() {
+            GameStartResult::Normal(t) => t.stand().stand(),
+            GameStartResult::Natural(_) => panic!("the stacked shoe dealt a natural"),
+        };
+
+        assert_eq!(*first.result(), GameResult::StandOff);
+
+        // Only two cards are left, so the discards are shuffled part way through the deal
+        let table = first.next_round();
+        assert_eq!(table.cards_remaining(), 2);
+
+        let dealer = match table.deal().unwrap() {
+            GameStartResult::Normal(t) => t.stand(),
+            GameStartResult::Natural(_) => panic!("the stacked shoe dealt a natural"),
+        };
+
+        assert_eq!(*shuffles.lock().unwrap(), 1);
+        assert_eq!(dealer.player_hand().cards()[0], "5H".parse().unwrap());
+        assert_eq!(dealer.dealer_hand().cards()[0], "6S".parse().unwrap());
+
+        let finished = play_dealer(dealer);
+
+        let in_play = finished.player_hand().cards().len() + finished.dealer_hand().cards().len();
+
+        assert_eq!(in_play + finished.cards_remaining(), 6);
+        assert!(finished.dealer_hand().total_value() >= 17);
+    }
+
+    #[test]
+    fn rounds_that_cannot_be_dealt_are_errors() {
+        assert!(matches!(stacked(&["10H", "9S", "7D"]).deal(), Err(BlackjackRoundError::DeckEmpty)));
+
+        let table = BlackjackTable::with_rules(Rules { decks: 0, ..Rules::default() });
+        assert!(matches!(table.deal(), Err(BlackjackRoundError::RuleViolation(_))));
+
+        for payout in [f64::NAN, f64::INFINITY, -1.0] {
+            let table = BlackjackTable::with_rules(Rules { blackjack_payout: payout, ..Rules::default() });
+            assert!(matches!(table.deal(), Err(BlackjackRoundError::RuleViolation(_))));
+        }
+
+        assert!(matches!(stacked(&["10H", "9S", "10D", "8C"]).with_wager(0.0).deal(), Err(BlackjackRoundError::InvalidWager(_))));
+    }
+
+    #[test]
+    fn wagers_scale_the_winnings() {
+        let table = stacked(&["10H", "9S", "10D", "8C"]).with_wager(2.5);
+
+        let finished = match table.deal().unwrap() {
+            GameStartResult::Normal(t) => t.stand().stand(),
+            GameStartResult::Natural(_) => panic!("the stacked shoe dealt a natural"),
+        };
+
+        assert_eq!(*finished.result(), GameResult::PlayerWin);
+        assert_eq!(finished.payout(), 1.0);
+        assert_eq!(finished.session().bankroll, 102.5);
+
+        // The wager stays on the table for the next round
+        assert_eq!(finished.next_round().wager(), 2.5);
+    }
+    #[test]
+    fn every_way_a_round_ends_settles_it_once() {
+        // A natural, a bust on 16 and a showdown of 20 against 18
+        let table = stacked(&["AS", "9S", "KD", "8C", "10H", "9D", "6D", "7C", "10C", "10S", "10H", "QD", "8D"]);
+
+        let natural = match table.deal().unwrap() {
+            GameStartResult::Natural(t) => t,
+            GameStartResult::Normal(_) => panic!("the stacked shoe did not deal a natural"),
+        };
+
+        let bust = match natural.next_round().deal().unwrap() {
+            GameStartResult::Normal(t) => match t.hit().unwrap() {
+                PlayerTurnResult::Bust(t) => t,
+                PlayerTurnResult::Hit(_) => panic!("the player did not go bust on 16"),
+            },
+            GameStartResult::Natural(_) => panic!("the stacked shoe dealt a natural"),
+        };
+
+        let showdown = match bust.next_round().deal().unwrap() {
+            GameStartResult::Normal(t) => t.stand().stand(),
+            GameStartResult::Natural(_) => panic!("the stacked shoe dealt a natural"),
+        };
+
+        let session = showdown.session();
+
+        assert_eq!(session.stats.rounds, 3);
+        assert_eq!(session.bankroll, 101.5);
+        assert_eq!(session.bankroll_history().len(), 4);
+    }
+}
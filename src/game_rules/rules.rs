@@ -0,0 +1,104 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::fmt::Display;
+
+use super::hand::Hand;
+use super::round::GameResult;
+
+/// The house rules a [BlackjackTable](super::round::BlackjackTable)
+/// is played with
+///
+/// # Example
+///
+/// A single deck game where the dealer hits soft 17:
+/// ```
+/// use blackjack::round::BlackjackTable;
+/// use blackjack::rules::Rules;
+///
+/// let rules = Rules { decks: 1, dealer_hits_soft_17: true, ..Rules::default() };
+/// let table = BlackjackTable::with_rules(rules).reshuffle();
+///
+/// assert_eq!(table.cards_remaining(), 52);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rules {
+    /// Number of decks shuffled together into the shoe
+    pub decks: u8,
+
+    /// Whether the dealer draws to a soft 17 (H17) or stands on it (S17)
+    pub dealer_hits_soft_17: bool,
+
+    /// Whether the dealer checks for a natural before the player acts.
+    /// Without a peek, the player plays out their hand first and
+    /// loses to the dealer's natural afterwards
+    pub dealer_peeks: bool,
+
+    /// Amount paid for each unit wagered when the player draws a natural
+    pub blackjack_payout: f64,
+
+    /// Fraction of the shoe that is dealt before it is reshuffled
+    pub penetration: f64,
+}
+
+impl Rules {
+    /// Returns true if the dealer has to draw another card to their hand
+    pub fn dealer_must_hit(&self, dealer: &Hand) -> bool {
+        let value = dealer.total_value();
+
+        value < 17 || (value == 17 && self.dealer_hits_soft_17 && dealer.is_soft())
+    }
+
+    /// Returns the player's net winnings for each unit wagered on a hand
+    /// 
+    /// A player's natural is paid at the rate set by [Rules::blackjack_payout]
+    pub fn payout(&self, result: GameResult, player: &Hand) -> f64 {
+        match result {
+            GameResult::PlayerWin if player.is_natural() => self.blackjack_payout,
+            GameResult::PlayerWin | GameResult::DealerBust => 1.0,
+            GameResult::StandOff => 0.0,
+            GameResult::DealerWin | GameResult::PlayerBust => -1.0,
+        }
+    }
+
+    /// Check that a round can be played with the rules,
+    /// returning a [RuleViolation] describing the first problem
+    pub fn validate(&self) -> Result<(), RuleViolation> {
+        let violation = if self.decks == 0 {
+            "the shoe must have at least one deck".to_string()
+        } else if !(self.penetration > 0.0 && self.penetration <= 1.0) {
+            format!("the penetration must be above 0 and at most 1, not {}", self.penetration)
+        } else if !self.blackjack_payout.is_finite() || self.blackjack_payout < 0.0 {
+            format!("a natural cannot pay {}", self.blackjack_payout)
+        } else {
+            return Ok(());
+        };
+
+        Err(RuleViolation(violation))
+    }
+}
+
+/// The reason a set of [Rules] cannot be played, e.g. a shoe without any decks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleViolation(pub String);
+
+impl Display for RuleViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for RuleViolation {}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            decks: 6,
+            dealer_hits_soft_17: false,
+            dealer_peeks: true,
+            blackjack_payout: 1.5,
+            penetration: 0.75,
+        }
+    }
+}
@@ -0,0 +1,252 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::round::GameResult;
+
+/// The bankroll the player starts a session with, in units
+pub const STARTING_BANKROLL: f64 = 100.0;
+
+/// The player's bankroll and the results of every round
+/// they have played at a table
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Session {
+    /// The player's bankroll in units
+    pub bankroll: f64,
+    pub stats: SessionStats,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub history: BankrollHistory,
+}
+
+impl Session {
+    pub fn new(bankroll: f64) -> Self {
+        Self {
+            bankroll,
+            stats: SessionStats::default(),
+            history: BankrollHistory::new(bankroll),
+        }
+    }
+
+    /// Pay the player's winnings for a finished round and count its result
+    pub(super) fn settle(&mut self, result: GameResult, payout: f64, player_natural: bool) {
+        self.bankroll += payout;
+        self.stats.record(result, payout, player_natural);
+        self.history.push(self.bankroll);
+    }
+
+    /// Returns the bankroll over the session, from the opening bankroll to the current one,
+    /// with at most [BankrollHistory::MAX_POINTS] and one more point
+    pub fn bankroll_history(&self) -> Vec<f64> {
+        let mut points = self.history.points.clone();
+
+        if !self.history.rounds.is_multiple_of(self.history.stride) || points.is_empty() {
+            points.push(self.bankroll);
+        }
+
+        points
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new(STARTING_BANKROLL)
+    }
+}
+
+/// The bankroll sampled every `stride` rounds
+///
+/// Once the history is full, every other point is dropped and the stride
+/// doubled, so that a long session keeps a bounded history of its whole length
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BankrollHistory {
+    points: Vec<f64>,
+    stride: u64,
+    rounds: u64,
+}
+
+impl BankrollHistory {
+    pub const MAX_POINTS: usize = 128;
+
+    fn new(opening: f64) -> Self {
+        Self { points: vec![opening], stride: 1, rounds: 0 }
+    }
+
+    fn push(&mut self, bankroll: f64) {
+        self.rounds += 1;
+
+        if !self.rounds.is_multiple_of(self.stride) {
+            return;
+        }
+
+        self.points.push(bankroll);
+
+        if self.points.len() >= Self::MAX_POINTS {
+            self.points = self.points.iter().step_by(2).copied().collect();
+            self.stride *= 2;
+        }
+    }
+}
+
+impl Default for BankrollHistory {
+    fn default() -> Self {
+        Self { points: Vec::new(), stride: 1, rounds: 0 }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionStats {
+    pub rounds: u64,
+    /// Net winnings in units
+    pub net: f64,
+    pub wins: u64,
+    pub losses: u64,
+    pub pushes: u64,
+    pub blackjacks: u64,
+    pub player_busts: u64,
+    pub dealer_busts: u64,
+}
+
+impl SessionStats {
+    fn record(&mut self, result: GameResult, payout: f64, player_natural: bool) {
+        self.rounds += 1;
+        self.net += payout;
+
+        if player_natural && result == GameResult::PlayerWin {
+            self.blackjacks += 1;
+        }
+
+        match result {
+            GameResult::PlayerWin => self.wins += 1,
+            GameResult::DealerBust => {
+                self.wins += 1;
+                self.dealer_busts += 1;
+            },
+            GameResult::DealerWin => self.losses += 1,
+            GameResult::PlayerBust => {
+                self.losses += 1;
+                self.player_busts += 1;
+            },
+            GameResult::StandOff => self.pushes += 1,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use crate::game_rules::round::{
+        BlackjackTable,
+        BlackjackTableState,
+        GameStartResult,
+        PlayerTurnResult
+    };
+
+    use crate::game_rules::game::AnyTable;
+    use crate::game_rules::round::GameResult;
+    use crate::game_rules::testing::play_dealer;
+
+    use super::{BankrollHistory, Session};
+
+    /// Round trip the table through JSON and a compact encoding,
+    /// checking both give back the same table
+    fn round_trip<S>(table: BlackjackTable<S>) -> BlackjackTable<S>
+    where
+        S: BlackjackTableState + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let compact: BlackjackTable<S> = bincode::deserialize(&bincode::serialize(&table).unwrap()).unwrap();
+        let json = serde_json::to_string(&table).unwrap();
+
+        assert_eq!(serde_json::to_string(&compact).unwrap(), json);
+
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn saved_tables_resume_mid_round() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut table = BlackjackTable::default().with_bankroll(50.0).reshuffle_with(&mut rng);
+
+        // Play a few rounds so the discard pile and stats are not empty
+        let player_turn = loop {
+            table = match table.deal().unwrap() {
+                GameStartResult::Natural(t) => t.next_round(),
+                GameStartResult::Normal(t) if t.session().stats.rounds < 3 => match t.hit().unwrap() {
+                    PlayerTurnResult::Hit(t) => t.stand().stand().next_round(),
+                    PlayerTurnResult::Bust(t) => t.next_round(),
+                },
+                GameStartResult::Normal(t) => break t,
+            };
+        };
+
+        let saved = AnyTable::from(player_turn);
+        let json = serde_json::to_string(&saved).unwrap();
+
+        let (original, resumed) = match (saved, serde_json::from_str(&json).unwrap()) {
+            (AnyTable::PlayerTurn(a), AnyTable::PlayerTurn(b)) => (a, b),
+            _ => panic!("the table was not resumed in the player's turn"),
+        };
+
+        assert_eq!(resumed.player_hand().cards(), original.player_hand().cards());
+        assert_eq!(resumed.dealer_hand().cards(), original.dealer_hand().cards());
+        assert_eq!(resumed.cards_remaining(), original.cards_remaining());
+        assert_eq!(resumed.running_count().running(), original.running_count().running());
+        assert_eq!(resumed.session(), original.session());
+        assert_eq!(resumed.session().stats.rounds, 3);
+        assert_eq!(resumed.session().bankroll, 50.0 + resumed.session().stats.net);
+
+        // Both tables draw the same cards from the resumed shoe
+        match (original.hit().unwrap(), resumed.hit().unwrap()) {
+            (PlayerTurnResult::Hit(a), PlayerTurnResult::Hit(b)) => assert_eq!(a.player_hand().cards(), b.player_hand().cards()),
+            (PlayerTurnResult::Bust(a), PlayerTurnResult::Bust(b)) => assert_eq!(a.player_hand().cards(), b.player_hand().cards()),
+            _ => panic!("the resumed shoe dealt a different card"),
+        }
+    }
+
+    #[test]
+    fn bankroll_history_is_thinned() {
+        let mut session = Session::new(10.0);
+
+        for _ in 0..1000 {
+            session.settle(GameResult::PlayerWin, 1.0, false);
+        }
+
+        let history = session.bankroll_history();
+
+        assert!(history.len() <= BankrollHistory::MAX_POINTS + 1);
+        assert_eq!(history.first(), Some(&10.0));
+        assert_eq!(history.last(), Some(&1010.0));
+
+        // The points stay evenly spaced through the session
+        let step = history[1] - history[0];
+        assert!(history.windows(2).take(history.len() - 2).all(|w| w[1] - w[0] == step));
+    }
+
+    #[test]
+    fn every_table_state_round_trips() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let table = round_trip(BlackjackTable::default().reshuffle_with(&mut rng));
+
+        let player_turn = match table.deal().unwrap() {
+            GameStartResult::Normal(t) => round_trip(t),
+            GameStartResult::Natural(_) => panic!("the seeded shoe dealt a natural"),
+        };
+
+        let finished = play_dealer(round_trip(player_turn.stand()));
+
+        let result = *finished.result();
+        let finished = round_trip(finished);
+
+        assert_eq!(*finished.result(), result);
+        assert_eq!(finished.session().stats.rounds, 1);
+
+        let remaining = finished.cards_remaining();
+        let table = round_trip(finished.next_round());
+
+        assert_eq!(table.cards_remaining(), remaining);
+        assert!(table.player_hand().cards().is_empty());
+    }
+}
@@ -0,0 +1,606 @@
+use std::fmt::Display;
+
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::cards::Card;
+use super::deck::Deck;
+use super::action::TableAction;
+use super::hand::Hand;
+use super::round::{BlackjackRoundError, GameResult};
+use super::rules::Rules;
+use super::session::Session;
+
+/// The point a round at a [SharedTable] has reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "phase", rename_all = "snake_case"))]
+pub enum Phase {
+    /// Bets are being placed for the next round
+    Betting,
+    /// The player in the seat is playing their hand
+    PlayerTurn { seat: usize },
+    DealerTurn,
+    /// Every hand has been settled
+    RoundOver,
+}
+
+/// How a player's hand was settled
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SeatOutcome {
+    pub result: GameResult,
+    /// Net winnings for the hand, in units
+    pub payout: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeatError {
+    /// Every seat at the table is taken
+    TableFull,
+    /// Nobody is sitting in the seat, or the table has no such seat
+    EmptySeat(usize),
+    /// Another seat is playing their hand
+    NotYourTurn,
+    /// Bets can only be placed before the cards are dealt
+    BettingClosed,
+    /// Bets must be a positive number of units
+    InvalidBet(f64),
+    /// There is nobody to deal to
+    NoBets,
+    /// The player cannot leave until their hand has been settled
+    HandInPlay,
+    /// The round itself could not be played
+    Round(BlackjackRoundError),
+}
+
+impl Display for SeatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TableFull => write!(f, "every seat is taken"),
+            Self::EmptySeat(seat) => write!(f, "nobody is sitting in seat {seat}"),
+            Self::NotYourTurn => write!(f, "it is not your turn"),
+            Self::BettingClosed => write!(f, "bets cannot be placed until the round is over"),
+            Self::InvalidBet(units) => write!(f, "cannot bet {units} units, bets must be positive"),
+            Self::NoBets => write!(f, "nobody has placed a bet"),
+            Self::HandInPlay => write!(f, "the hand has to be finished first"),
+            Self::Round(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for SeatError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Round(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<BlackjackRoundError> for SeatError {
+    fn from(e: BlackjackRoundError) -> Self {
+        Self::Round(e)
+    }
+}
+
+/// A player sitting at a [SharedTable]
+#[derive(Debug, Clone)]
+pub struct SeatedPlayer {
+    name: String,
+    session: Session,
+    hand: Hand,
+    bet: Option<f64>,
+    /// Set once the player has finished acting on their hand
+    done: bool,
+    outcome: Option<SeatOutcome>,
+}
+
+impl SeatedPlayer {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the player's bankroll and the results of their rounds
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn hand(&self) -> &Hand {
+        &self.hand
+    }
+
+    /// Returns the units wagered on the current round, if any
+    pub fn bet(&self) -> Option<f64> {
+        self.bet
+    }
+
+    /// Returns how the player's hand was settled, once it has been
+    pub fn outcome(&self) -> Option<SeatOutcome> {
+        self.outcome
+    }
+
+    /// Returns true if the player was dealt into the current round
+    pub fn in_round(&self) -> bool {
+        !self.hand.cards().is_empty()
+    }
+
+    /// Returns true if the player still has to act on their hand
+    fn to_act(&self) -> bool {
+        self.in_round() && !self.done
+    }
+}
+
+/// A table where several players each play a hand against the same dealer,
+/// dealt from the same shoe
+///
+/// Players take a seat, bet, and then act on their hands in seat order.
+/// Naturals are paid as soon as they are dealt, unless the dealer peeks
+/// and also has one, and the dealer only plays their hand if a player is
+/// still waiting on it. Each player keeps their own [Session]
+///
+/// Unlike [BlackjackTable](super::round::BlackjackTable), the table is
+/// changed in place, since the players act on it in whatever order
+/// they send their actions
+///
+/// # Example
+///
+/// ```
+/// use blackjack::shared::{Phase, SharedTable};
+/// use blackjack::rules::Rules;
+///
+/// let mut table = SharedTable::new(Rules::default(), 2);
+/// table.reshuffle();
+///
+/// let alice = table.sit("Alice", 100.0).unwrap();
+/// let bob = table.sit("Bob", 100.0).unwrap();
+///
+/// table.bet(alice, 1.0).unwrap();
+/// table.bet(bob, 2.0).unwrap();
+/// table.deal().unwrap();
+///
+/// while let Phase::PlayerTurn { seat } = table.phase() {
+///     table.stand(seat).unwrap();
+/// }
+///
+/// while table.phase() == Phase::DealerTurn {
+///     table.dealer_play().unwrap();
+/// }
+///
+/// assert_eq!(table.phase(), Phase::RoundOver);
+/// assert!(table.seats().iter().flatten().all(|p| p.outcome().is_some()));
+/// ```
+#[derive(Clone)]
+pub struct SharedTable {
+    deck: Deck,
+    rules: Rules,
+    dealer: Hand,
+    seats: Vec<Option<SeatedPlayer>>,
+    phase: Phase,
+}
+
+impl SharedTable {
+
+    /// Create a table with the supplied number of seats and
+    /// an unshuffled shoe built for the supplied [Rules]
+    pub fn new(rules: Rules, seats: usize) -> Self {
+        Self::with_deck(rules, Deck::new(rules.decks), seats)
+    }
+
+    /// Create a table that deals from the supplied deck,
+    /// e.g. a stacked one
+    pub fn with_deck(rules: Rules, deck: Deck, seats: usize) -> Self {
+        Self {
+            deck,
+            rules,
+            dealer: Hand::default(),
+            seats: vec![None; seats],
+            phase: Phase::Betting,
+        }
+    }
+
+    /// Returns true once the shoe has been dealt past the
+    /// penetration set in the table's [Rules]
+    pub fn needs_reshuffle(&self) -> bool {
+        let total = self.deck.remaining() + self.deck.discarded();
+
+        total == 0 || self.deck.discarded() as f64 / total as f64 >= self.rules.penetration
+    }
+
+    /// Return the discard pile to the shoe and shuffle it
+    pub fn reshuffle(&mut self) {
+        self.reshuffle_with(&mut rand::thread_rng());
+    }
+
+    /// Return the discard pile to the shoe and shuffle it
+    /// with the supplied random number generator
+    pub fn reshuffle_with<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.deck.reshuffle_with(rng);
+    }
+
+    /// Seat a player in the first empty seat, returning its number
+    ///
+    /// A player who sits down during a round is dealt in from the next one
+    pub fn sit(&mut self, name: impl Into<String>, bankroll: f64) -> Result<usize, SeatError> {
+        let seat = self.seats.iter().position(Option::is_none).ok_or(SeatError::TableFull)?;
+
+        self.seats[seat] = Some(SeatedPlayer {
+            name: name.into(),
+            session: Session::new(bankroll),
+            hand: Hand::default(),
+            bet: None,
+            done: false,
+            outcome: None,
+        });
+
+        Ok(seat)
+    }
+
+    /// Remove the player from their seat, returning them with their [Session]
+    ///
+    /// Returns [SeatError::HandInPlay] if the player was dealt into
+    /// a round that has not been settled yet
+    pub fn leave(&mut self, seat: usize) -> Result<SeatedPlayer, SeatError> {
+        let player = self.player(seat)?;
+
+        if player.in_round() && self.phase != Phase::RoundOver {
+            return Err(SeatError::HandInPlay);
+        }
+
+        let mut player = self.seats[seat].take().ok_or(SeatError::EmptySeat(seat))?;
+        self.deck.discard(std::mem::take(&mut player.hand).into_cards());
+
+        Ok(player)
+    }
+
+    /// Wager units on the next round, replacing any earlier bet
+    pub fn bet(&mut self, seat: usize, units: f64) -> Result<(), SeatError> {
+        if self.phase != Phase::Betting {
+            return Err(SeatError::BettingClosed);
+        }
+
+        if !units.is_finite() || units <= 0.0 {
+            return Err(SeatError::InvalidBet(units));
+        }
+
+        self.player_mut(seat)?.bet = Some(units);
+
+        Ok(())
+    }
+
+    /// Deal two cards to every player with a bet and to the dealer,
+    /// in seat order with the dealer last
+    ///
+    /// Naturals are settled straight away. If the dealer peeks and has
+    /// a natural, every hand is settled and the round is over
+    pub fn deal(&mut self) -> Result<(), SeatError> {
+        self.expect(TableAction::Deal)?;
+        self.rules.validate().map_err(BlackjackRoundError::from)?;
+
+        let betting: Vec<usize> = (0..self.seats.len())
+            .filter(|&i| self.seats[i].as_ref().is_some_and(|p| p.bet.is_some()))
+            .collect();
+
+        if betting.is_empty() {
+            return Err(SeatError::NoBets);
+        }
+
+        for _ in 0..2 {
+            for &seat in &betting {
+                let card = self.draw()?;
+                self.player_mut(seat)?.hand.push(card);
+            }
+
+            let card = self.draw()?;
+            self.dealer.push(card);
+        }
+
+        let dealer_natural = self.dealer.is_natural();
+
+        for &seat in &betting {
+            let natural = self.player(seat)?.hand.is_natural();
+
+            let result = match (natural, dealer_natural && self.rules.dealer_peeks) {
+                (true, _) if dealer_natural => GameResult::StandOff,
+                (true, _) => GameResult::PlayerWin,
+                (false, true) => GameResult::DealerWin,
+                (false, false) => continue,
+            };
+
+            self.settle(seat, result);
+        }
+
+        self.advance(0);
+
+        Ok(())
+    }
+
+    /// Draw a card into the hand of the player whose turn it is,
+    /// ending their turn if they go bust
+    pub fn hit(&mut self, seat: usize) -> Result<(), SeatError> {
+        self.expect_turn(seat, TableAction::Hit)?;
+
+        let card = self.draw()?;
+        let player = self.player_mut(seat)?;
+        player.hand.push(card);
+
+        if player.hand.total_value() > 21 {
+            self.settle(seat, GameResult::PlayerBust);
+            self.advance(seat + 1);
+        }
+
+        Ok(())
+    }
+
+    /// End the turn of the player whose turn it is
+    pub fn stand(&mut self, seat: usize) -> Result<(), SeatError> {
+        self.expect_turn(seat, TableAction::Stand)?;
+
+        self.player_mut(seat)?.done = true;
+        self.advance(seat + 1);
+
+        Ok(())
+    }
+
+    /// Play one step of the dealer's turn, drawing a card if the
+    /// [Rules] require one
+    ///
+    /// Once the dealer reaches a total they stand on, every hand
+    /// still in play is settled and the round is over
+    pub fn dealer_play(&mut self) -> Result<(), SeatError> {
+        self.expect(TableAction::DealerPlay)?;
+
+        if self.rules.dealer_must_hit(&self.dealer) {
+            let card = self.draw()?;
+            self.dealer.push(card);
+        }
+
+        if !self.rules.dealer_must_hit(&self.dealer) {
+            for seat in 0..self.seats.len() {
+                let waiting = self.seats[seat].as_ref()
+                    .is_some_and(|p| p.in_round() && p.outcome.is_none());
+
+                if waiting {
+                    let result = GameResult::showdown(&self.seats[seat].as_ref().unwrap().hand, &self.dealer);
+                    self.settle(seat, result);
+                }
+            }
+
+            self.phase = Phase::RoundOver;
+        }
+
+        Ok(())
+    }
+
+    /// Discard every hand and open the betting for another round,
+    /// keeping the rest of the shoe
+    pub fn next_round(&mut self) -> Result<(), SeatError> {
+        self.expect(TableAction::NextRound)?;
+
+        self.deck.discard(std::mem::take(&mut self.dealer).into_cards());
+
+        for player in self.seats.iter_mut().flatten() {
+            self.deck.discard(std::mem::take(&mut player.hand).into_cards());
+            player.bet = None;
+            player.done = false;
+            player.outcome = None;
+        }
+
+        self.phase = Phase::Betting;
+
+        Ok(())
+    }
+
+    /// Returns the actions that can be taken in the current phase,
+    /// as [AnyTable::legal_actions](super::game::AnyTable::legal_actions) does
+    pub fn legal_actions(&self) -> Vec<TableAction> {
+        match self.phase {
+            Phase::Betting => vec![TableAction::Reshuffle, TableAction::Deal],
+            Phase::PlayerTurn { .. } => vec![TableAction::Hit, TableAction::Stand],
+            Phase::DealerTurn => vec![TableAction::DealerPlay],
+            Phase::RoundOver => vec![TableAction::NextRound],
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Returns every seat at the table, with [None] for the empty ones
+    pub fn seats(&self) -> &[Option<SeatedPlayer>] {
+        &self.seats
+    }
+
+    /// Returns the player sitting in the seat, if there is one
+    pub fn seat(&self, seat: usize) -> Option<&SeatedPlayer> {
+        self.seats.get(seat)?.as_ref()
+    }
+
+    /// Returns a reference to the dealer's [Hand]
+    pub fn dealer_hand(&self) -> &Hand {
+        &self.dealer
+    }
+
+    /// Returns the dealer's cards that the players can see,
+    /// i.e. only the upcard while the players are acting
+    pub fn visible_dealer_cards(&self) -> &[Card] {
+        let cards = self.dealer.cards();
+
+        match self.phase {
+            Phase::PlayerTurn { .. } => &cards[..cards.len().min(1)],
+            _ => cards,
+        }
+    }
+
+    /// Returns a reference to the [Rules] the table is played with
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Returns the number of cards left in the shoe
+    pub fn cards_remaining(&self) -> usize {
+        self.deck.remaining()
+    }
+
+    /// Move on to the first player from `seat` who still has to act,
+    /// or to the dealer once every player has
+    fn advance(&mut self, seat: usize) {
+        let next = (seat..self.seats.len())
+            .find(|&i| self.seats[i].as_ref().is_some_and(SeatedPlayer::to_act));
+
+        let waiting = self.seats.iter().flatten().any(|p| p.in_round() && p.outcome.is_none());
+
+        self.phase = match next {
+            Some(seat) => Phase::PlayerTurn { seat },
+            None if waiting => Phase::DealerTurn,
+            None => Phase::RoundOver,
+        };
+    }
+
+    /// Pay the player's winnings for their hand and count its result
+    fn settle(&mut self, seat: usize, result: GameResult) {
+        let rules = self.rules;
+
+        if let Some(player) = self.seats[seat].as_mut() {
+            let payout = rules.payout(result, &player.hand) * player.bet.unwrap_or(0.0);
+
+            player.session.settle(result, payout, player.hand.is_natural());
+            player.done = true;
+            player.outcome = Some(SeatOutcome { result, payout });
+        }
+    }
+
+    /// Draw the next card from the shoe, shuffling the
+    /// discards into a new shoe if it has run out
+    fn draw(&mut self) -> Result<Card, BlackjackRoundError> {
+        self.deck.reshuffle_discards();
+
+        self.deck.draw().ok_or(BlackjackRoundError::DeckEmpty)
+    }
+
+    fn expect(&self, action: TableAction) -> Result<(), SeatError> {
+        let legal = self.legal_actions();
+
+        match legal.contains(&action) {
+            true => Ok(()),
+            false => Err(BlackjackRoundError::IllegalAction { action, legal }.into()),
+        }
+    }
+
+    /// Check it is the seat's turn to take the action
+    fn expect_turn(&self, seat: usize, action: TableAction) -> Result<(), SeatError> {
+        self.expect(action)?;
+        self.player(seat)?;
+
+        match self.phase {
+            Phase::PlayerTurn { seat: turn } if turn == seat => Ok(()),
+            _ => Err(SeatError::NotYourTurn),
+        }
+    }
+
+    fn player(&self, seat: usize) -> Result<&SeatedPlayer, SeatError> {
+        self.seat(seat).ok_or(SeatError::EmptySeat(seat))
+    }
+
+    fn player_mut(&mut self, seat: usize) -> Result<&mut SeatedPlayer, SeatError> {
+        self.seats.get_mut(seat)
+            .and_then(Option::as_mut)
+            .ok_or(SeatError::EmptySeat(seat))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::cards::Card;
+    use crate::game_rules::action::TableAction;
+    use crate::game_rules::round::{BlackjackRoundError, GameResult};
+    use crate::game_rules::rules::Rules;
+    use crate::game_rules::testing::stacked_deck;
+
+    use super::{Phase, SeatError, SeatOutcome, SharedTable};
+
+    fn stacked(cards: &[&str], seats: usize) -> SharedTable {
+        SharedTable::with_deck(Rules { decks: 1, ..Rules::default() }, stacked_deck(cards), seats)
+    }
+
+    #[test]
+    fn players_share_the_dealer_and_shoe() {
+        let mut table = stacked(&["10H", "9S", "6D", "7C", "10D", "10S", "5H"], 3);
+
+        let alice = table.sit("Alice", 100.0).unwrap();
+        let bob = table.sit("Bob", 100.0).unwrap();
+
+        table.bet(alice, 1.0).unwrap();
+        table.bet(bob, 2.0).unwrap();
+        table.deal().unwrap();
+
+        assert_eq!(table.phase(), Phase::PlayerTurn { seat: alice });
+        assert_eq!(table.seat(alice).unwrap().hand().total_value(), 17);
+        assert_eq!(table.seat(bob).unwrap().hand().total_value(), 19);
+        assert_eq!(table.visible_dealer_cards(), &["6D".parse::<Card>().unwrap()]);
+
+        assert_eq!(table.hit(bob), Err(SeatError::NotYourTurn));
+
+        table.stand(alice).unwrap();
+        table.stand(bob).unwrap();
+        assert_eq!(table.phase(), Phase::DealerTurn);
+
+        table.dealer_play().unwrap();
+        assert_eq!(table.phase(), Phase::RoundOver);
+        assert_eq!(table.dealer_hand().total_value(), 21);
+
+        let bob = table.seat(bob).unwrap();
+        assert_eq!(bob.outcome(), Some(SeatOutcome { result: GameResult::DealerWin, payout: -2.0 }));
+        assert_eq!(bob.session().bankroll, 98.0);
+
+        table.next_round().unwrap();
+        assert_eq!(table.phase(), Phase::Betting);
+        assert!(table.seats().iter().flatten().all(|p| p.bet().is_none() && !p.in_round()));
+    }
+
+    #[test]
+    fn naturals_are_paid_without_waiting_for_the_dealer() {
+        let mut table = stacked(&["AS", "9S", "6D", "KC", "10D", "10S", "5H"], 2);
+
+        let alice = table.sit("Alice", 100.0).unwrap();
+        let bob = table.sit("Bob", 100.0).unwrap();
+
+        table.bet(alice, 2.0).unwrap();
+        table.bet(bob, 1.0).unwrap();
+        table.deal().unwrap();
+
+        assert_eq!(table.phase(), Phase::PlayerTurn { seat: bob });
+        assert_eq!(table.seat(alice).unwrap().outcome(), Some(SeatOutcome { result: GameResult::PlayerWin, payout: 3.0 }));
+
+        table.hit(bob).unwrap();
+        assert_eq!(table.seat(bob).unwrap().outcome().map(|o| o.result), Some(GameResult::PlayerBust));
+
+        // Nobody is waiting on the dealer's hand
+        assert_eq!(table.phase(), Phase::RoundOver);
+        assert_eq!(table.dealer_hand().cards().len(), 2);
+    }
+
+    #[test]
+    fn seats_and_bets_are_checked() {
+        let mut table = stacked(&["10H", "6D", "7C", "10S"], 1);
+
+        assert_eq!(table.deal(), Err(SeatError::NoBets));
+
+        let alice = table.sit("Alice", 100.0).unwrap();
+        assert_eq!(table.sit("Bob", 100.0), Err(SeatError::TableFull));
+        assert_eq!(table.bet(1, 1.0), Err(SeatError::EmptySeat(1)));
+        assert_eq!(table.bet(alice, 0.0), Err(SeatError::InvalidBet(0.0)));
+        assert_eq!(table.bet(alice, f64::INFINITY), Err(SeatError::InvalidBet(f64::INFINITY)));
+
+        table.bet(alice, 1.0).unwrap();
+        table.deal().unwrap();
+
+        assert_eq!(table.bet(alice, 1.0), Err(SeatError::BettingClosed));
+        assert!(matches!(
+            table.next_round(),
+            Err(SeatError::Round(BlackjackRoundError::IllegalAction { action: TableAction::NextRound, .. }))
+        ));
+        assert_eq!(table.leave(alice).map(|_| ()), Err(SeatError::HandInPlay));
+    }
+}
@@ -0,0 +1,164 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::cards::{Card, CardFace};
+use super::hand::Hand;
+use super::rules::Rules;
+
+/// An action a player can take on their hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Action {
+    Hit,
+    Stand,
+    Double,
+    Split,
+    Surrender,
+}
+
+/// The optional actions that are currently allowed on a hand.
+///
+/// Hitting and standing are always allowed. When an action
+/// is not available, basic strategy falls back to hitting or standing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AvailableActions {
+    pub double: bool,
+    pub split: bool,
+    pub surrender: bool,
+}
+
+/// Returns the blackjack value of a dealer's upcard,
+/// counting an Ace as 11
+pub fn upcard_value(upcard: &Card) -> u8 {
+    match upcard.face() {
+        CardFace::Ace => 11,
+        _ => u8::from(*upcard),
+    }
+}
+
+/// Returns the basic strategy play for a hand against the dealer's upcard
+///
+/// The strategy is the standard multi-deck chart with doubling
+/// after splits and late surrender, adjusted for dealers that hit soft 17
+pub fn basic_strategy(hand: &Hand, upcard: &Card, available: AvailableActions, rules: &Rules) -> Action {
+    let up = upcard_value(upcard);
+    let h17 = rules.dealer_hits_soft_17;
+    let cards = hand.cards();
+    let total = hand.total_value();
+
+    if total > 21 {
+        return Action::Stand;
+    }
+
+    if available.surrender && cards.len() == 2 && !hand.is_soft() {
+        let is_eights = u8::from(cards[0]) == 8 && u8::from(cards[1]) == 8;
+
+        let surrender = match (total, up) {
+            (16, 9..=11) if !is_eights => true,
+            (16, 11) => h17,
+            (15, 10) => true,
+            (15, 11) | (17, 11) => h17,
+            _ => false,
+        };
+
+        if surrender {
+            return Action::Surrender;
+        }
+    }
+
+    if available.split && cards.len() == 2 && u8::from(cards[0]) == u8::from(cards[1]) {
+        let split = match u8::from(cards[0]) {
+            1 | 8 => true,
+            9 => matches!(up, 2..=6 | 8 | 9),
+            7 | 2 | 3 => (2..=7).contains(&up),
+            6 => (2..=6).contains(&up),
+            4 => (5..=6).contains(&up),
+            _ => false,
+        };
+
+        if split {
+            return Action::Split;
+        }
+    }
+
+    let double_or = |fallback| if available.double { Action::Double } else { fallback };
+
+    if hand.is_soft() {
+        match total {
+            13 | 14 if (5..=6).contains(&up) => double_or(Action::Hit),
+            15 | 16 if (4..=6).contains(&up) => double_or(Action::Hit),
+            17 if (3..=6).contains(&up) => double_or(Action::Hit),
+            18 if (3..=6).contains(&up) || (h17 && up == 2) => double_or(Action::Stand),
+            18 if (9..=11).contains(&up) => Action::Hit,
+            19 if h17 && up == 6 => double_or(Action::Stand),
+            18..=21 => Action::Stand,
+            _ => Action::Hit,
+        }
+    } else {
+        match total {
+            9 if (3..=6).contains(&up) => double_or(Action::Hit),
+            10 if (2..=9).contains(&up) => double_or(Action::Hit),
+            11 if up <= 10 || h17 => double_or(Action::Hit),
+            12 if (4..=6).contains(&up) => Action::Stand,
+            13..=16 if up <= 6 => Action::Stand,
+            17..=21 => Action::Stand,
+            _ => Action::Hit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::cards::{Card, CardFace, CardSuit};
+    use crate::game_rules::hand::Hand;
+    use crate::game_rules::rules::Rules;
+
+    use super::{basic_strategy, Action, AvailableActions};
+
+    const ALL: AvailableActions = AvailableActions { double: true, split: true, surrender: true };
+
+    fn card(v: u8) -> Card {
+        Card::new(CardFace::try_from(v).unwrap(), CardSuit::Spades)
+    }
+
+    fn play(cards: &[u8], up: u8, available: AvailableActions) -> Action {
+        let hand = Hand::new(cards.iter().map(|v| card(*v)).collect());
+
+        basic_strategy(&hand, &card(up), available, &Rules::default())
+    }
+
+    #[test]
+    fn hard_totals() {
+        let none = AvailableActions::default();
+
+        assert_eq!(play(&[10, 6], 10, none), Action::Hit);
+        assert_eq!(play(&[10, 6], 6, none), Action::Stand);
+        assert_eq!(play(&[10, 2], 3, none), Action::Hit);
+        assert_eq!(play(&[10, 2], 4, none), Action::Stand);
+        assert_eq!(play(&[10, 7], 1, none), Action::Stand);
+        assert_eq!(play(&[6, 5], 6, none), Action::Hit);
+        assert_eq!(play(&[6, 5], 6, ALL), Action::Double);
+    }
+
+    #[test]
+    fn soft_totals() {
+        let none = AvailableActions::default();
+
+        assert_eq!(play(&[1, 7], 2, none), Action::Stand);
+        assert_eq!(play(&[1, 7], 9, none), Action::Hit);
+        assert_eq!(play(&[1, 7], 4, none), Action::Stand);
+        assert_eq!(play(&[1, 7], 4, ALL), Action::Double);
+        assert_eq!(play(&[1, 6], 10, none), Action::Hit);
+    }
+
+    #[test]
+    fn pairs_and_surrender() {
+        assert_eq!(play(&[8, 8], 10, ALL), Action::Split);
+        assert_eq!(play(&[1, 1], 6, ALL), Action::Split);
+        assert_eq!(play(&[10, 10], 6, ALL), Action::Stand);
+        assert_eq!(play(&[9, 9], 7, ALL), Action::Stand);
+        assert_eq!(play(&[10, 6], 10, ALL), Action::Surrender);
+        assert_eq!(play(&[8, 8], 10, AvailableActions::default()), Action::Hit);
+    }
+}
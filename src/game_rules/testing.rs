@@ -0,0 +1,24 @@
+use super::cards::Card;
+use super::deck::Deck;
+use super::round::{BlackjackTable, DealerTurn, DealerTurnResult, GameOver};
+use super::rules::Rules;
+
+/// A shoe that deals the named cards in order
+pub(crate) fn stacked_deck(cards: &[&str]) -> Deck {
+    Deck::stacked(cards.iter().map(|c| c.parse::<Card>().unwrap()).collect())
+}
+
+/// A single deck table that deals the named cards in order
+pub(crate) fn stacked(cards: &[&str]) -> BlackjackTable {
+    BlackjackTable::with_deck(Rules { decks: 1, ..Rules::default() }, stacked_deck(cards))
+}
+
+/// Let the dealer draw until they stand or go bust
+pub(crate) fn play_dealer(mut dealer: BlackjackTable<DealerTurn>) -> BlackjackTable<GameOver> {
+    loop {
+        dealer = match dealer.hit().unwrap() {
+            DealerTurnResult::Hit(t) => t,
+            DealerTurnResult::Stand(t) => break t,
+        };
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use blackjack::cards::Card;
+use blackjack::events::TableEvent;
+use blackjack::game::{AnyTable, TableAction};
+use blackjack::round::{BlackjackRoundError, BlackjackTable, GameResult};
+use blackjack::rules::Rules;
+use blackjack::session::STARTING_BANKROLL;
+
+use clap::Args;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Response, Server};
+
+#[derive(Args, Clone, Copy)]
+pub struct HttpConfig {
+
+    /// Port to listen on. Only connections from this machine are accepted
+    #[arg(short, long, default_value_t = 8080)]
+    port: u16,
+}
+
+/// Serve the API on localhost until the process is stopped
+pub fn run_http(cfg: HttpConfig) {
+    let Some(server) = bind(cfg.port) else { return };
+
+    println!("Serving the API on http://127.0.0.1:{}", cfg.port);
+
+    serve(server, &mut Api::default(), None);
+}
+
+/// Listen on the port on localhost, reporting why if it cannot be used
+pub fn bind(port: u16) -> Option<Server> {
+    match Server::http(("127.0.0.1", port)) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            eprintln!("Unable to listen on port {port}: {e}");
+            None
+        },
+    }
+}
+
+/// Answer every request to the server, one at a time, with
+/// `page` served as HTML at `/` if there is one
+pub fn serve(server: Server, api: &mut Api, page: Option<&str>) {
+    let json = Header::from_bytes("Content-Type", "application/json")
+        .expect("The header is valid");
+    let html = Header::from_bytes("Content-Type", "text/html; charset=utf-8")
+        .expect("The header is valid");
+
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+
+        let response = match (request.method(), request.url(), page) {
+            (Method::Get, "/", Some(page)) => Response::from_string(page).with_header(html.clone()),
+            _ => {
+                let reply = match request.as_reader().read_to_string(&mut body) {
+                    Ok(_) => api.handle(request.method(), request.url(), &body),
+                    Err(e) => Reply::error(400, format!("The request body could not be read: {e}")),
+                };
+
+                Response::from_string(reply.body.to_string())
+                    .with_status_code(reply.status)
+                    .with_header(json.clone())
+            },
+        };
+
+        if let Err(e) = request.respond(response) {
+            eprintln!("Unable to answer a request: {e}");
+        }
+    }
+}
+
+/// The status and JSON body to answer a request with
+#[derive(Debug)]
+struct Reply {
+    status: u16,
+    body: Value,
+}
+
+impl Reply {
+    fn ok<T: Serialize>(status: u16, body: &T) -> Self {
+        let body = serde_json::to_value(body).expect("Replies can always be serialized");
+
+        Self { status, body }
+    }
+
+    fn error<S: Into<String>>(status: u16, message: S) -> Self {
+        Self { status, body: json!({ "error": message.into() }) }
+    }
+
+    /// The reply for an action the table could not take
+    fn refused(error: &BlackjackRoundError) -> Self {
+        let status = match error {
+            BlackjackRoundError::IllegalAction { .. } => 409,
+            BlackjackRoundError::RuleViolation(_) | BlackjackRoundError::InvalidWager(_) => 400,
+            BlackjackRoundError::DeckEmpty => 500,
+        };
+
+        Self::error(status, error.to_string())
+    }
+}
+
+/// The body of a request to create a table, where every field is optional
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct NewTable {
+    rules: Option<Rules>,
+    bankroll: Option<f64>,
+    /// Units wagered on each round
+    wager: Option<f64>,
+    /// Seed for shuffling, so that the table's shoes can be repeated exactly
+    seed: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Bet {
+    units: f64,
+}
+
+#[derive(Deserialize)]
+struct Act {
+    action: TableAction,
+}
+
+/// Everything a player can see at a table
+#[derive(Debug, Serialize, Deserialize)]
+struct TableState {
+    id: u64,
+    /// The table's state, as it is named in a saved table
+    state: String,
+    legal_actions: Vec<TableAction>,
+    player: Vec<Card>,
+    player_total: u8,
+    soft: bool,
+    /// The dealer's cards, without the hole card during the player's turn
+    dealer: Vec<Card>,
+    dealer_total: u8,
+    result: Option<GameResult>,
+    /// Net winnings for each unit wagered on the finished round
+    payout: Option<f64>,
+    wager: f64,
+    bankroll: f64,
+    rounds: u64,
+    cards_remaining: usize,
+    running_count: i32,
+}
+
+/// A table created through the API, with every event that has happened at it
+struct ApiTable {
+    table: AnyTable,
+    rng: StdRng,
+    events: Arc<Mutex<Vec<TableEvent>>>,
+}
+
+impl ApiTable {
+    fn state(&self, id: u64) -> TableState {
+        let table = &self.table;
+        let dealer = table.visible_dealer_cards().to_vec();
+
+        let state = match table {
+            AnyTable::NotStarted(_) => "not_started",
+            AnyTable::PlayerTurn(_) => "player_turn",
+            AnyTable::DealerTurn(_) => "dealer_turn",
+            AnyTable::GameOver(_) => "game_over",
+        };
+
+        TableState {
+            id,
+            state: state.to_string(),
+            legal_actions: table.legal_actions(),
+            player: table.player_hand().cards().clone(),
+            player_total: table.player_hand().total_value(),
+            soft: table.player_hand().is_soft(),
+            dealer_total: blackjack::hand::Hand::new(dealer.clone()).total_value(),
+            dealer,
+            result: table.result(),
+            payout: table.payout(),
+            wager: table.wager(),
+            bankroll: table.session().bankroll,
+            rounds: table.session().stats.rounds,
+            cards_remaining: table.cards_remaining(),
+            running_count: table.running_count().running(),
+        }
+    }
+
+    /// Take an action at the table, shuffling with the table's own generator.
+    /// Returns the reply and the table, unless it was lost, if it cannot be taken
+    fn apply(self, action: TableAction) -> Result<Self, (Reply, Option<Box<Self>>)> {
+        let Self { table, mut rng, events } = self;
+
+        let result = match (table, action) {
+            (AnyTable::NotStarted(t), TableAction::Reshuffle) => Ok(AnyTable::from(t.reshuffle_with(&mut rng))),
+            (table, action) => table.apply(action),
+        };
+
+        match result {
+            Ok(table) => Ok(Self { table, rng, events }),
+            Err(e) => {
+                let reply = Reply::refused(&e.error);
+                let kept = e.table.map(|table| Box::new(Self { table: *table, rng, events }));
+
+                Err((reply, kept))
+            },
+        }
+    }
+}
+
+/// The tables created through the API
+#[derive(Default)]
+pub struct Api {
+    tables: HashMap<u64, ApiTable>,
+    next_id: u64,
+}
+
+impl Api {
+    /// Route a request to the endpoint for its method and path
+    fn handle(&mut self, method: &Method, url: &str, body: &str) -> Reply {
+        let path = url.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        let id = match segments.get(1) {
+            Some(id) => match id.parse::<u64>() {
+                Ok(id) => Some(id),
+                Err(_) => return Reply::error(404, format!("`{id}` is not a table")),
+            },
+            None => None,
+        };
+
+        match (method, &segments[..], id) {
+            (Method::Post, ["tables"], None) => self.create(body),
+            (Method::Get, ["tables"], None) => {
+                let mut ids: Vec<u64> = self.tables.keys().copied().collect();
+                ids.sort();
+
+                Reply::ok(200, &json!({ "tables": ids }))
+            },
+            (Method::Get, ["tables", _], Some(id)) => self.with_table(id, Ok),
+            (Method::Delete, ["tables", _], Some(id)) => match self.tables.remove(&id) {
+                Some(_) => Reply::ok(200, &json!({ "deleted": id })),
+                None => Reply::error(404, format!("There is no table {id}")),
+            },
+            (Method::Post, ["tables", _, "bet"], Some(id)) => match parse::<Bet>(body) {
+                Ok(bet) => self.with_table(id, |t| bet_on(t, bet.units)),
+                Err(reply) => reply,
+            },
+            (Method::Post, ["tables", _, "deal"], Some(id)) => self.with_table(id, deal),
+            (Method::Post, ["tables", _, "act"], Some(id)) => match parse::<Act>(body) {
+                Ok(act) => self.with_table(id, |t| t.apply(act.action)),
+                Err(reply) => reply,
+            },
+            (Method::Get, ["tables", _, "history"], Some(id)) => match self.tables.get(&id) {
+                Some(t) => Reply::ok(200, &json!({ "id": id, "events": *t.events.lock().unwrap() })),
+                None => Reply::error(404, format!("There is no table {id}")),
+            },
+            _ => Reply::error(404, format!("There is no endpoint for {method} {path}")),
+        }
+    }
+
+    fn create(&mut self, body: &str) -> Reply {
+        let new = match body.trim() {
+            "" => NewTable::default(),
+            body => match parse::<NewTable>(body) {
+                Ok(new) => new,
+                Err(reply) => return reply,
+            },
+        };
+
+        let rules = new.rules.unwrap_or_default();
+
+        if let Err(e) = rules.validate() {
+            return Reply::refused(&e.into());
+        }
+
+        let wager = new.wager.unwrap_or(1.0);
+
+        if !wager.is_finite() || wager <= 0.0 {
+            return Reply::refused(&BlackjackRoundError::InvalidWager(wager));
+        }
+
+        let mut rng = match new.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let mut table = BlackjackTable::with_rules(rules)
+            .with_bankroll(new.bankroll.unwrap_or(STARTING_BANKROLL))
+            .with_wager(wager);
+
+        let log = events.clone();
+        table.subscribe(move |event: &TableEvent| log.lock().unwrap().push(event.clone()));
+
+        let table = AnyTable::from(table.reshuffle_with(&mut rng));
+
+        self.next_id += 1;
+        let id = self.next_id;
+
+        let table = ApiTable { table, rng, events };
+        let reply = Reply::ok(201, &table.state(id));
+
+        self.tables.insert(id, table);
+
+        reply
+    }
+
+    /// Run the endpoint on a table, replying with its new state.
+    /// A table whose round could not be continued is removed
+    fn with_table<F>(&mut self, id: u64, endpoint: F) -> Reply
+    where
+        F: FnOnce(ApiTable) -> Result<ApiTable, (Reply, Option<Box<ApiTable>>)>,
+    {
+        let Some(table) = self.tables.remove(&id) else {
+            return Reply::error(404, format!("There is no table {id}"));
+        };
+
+        match endpoint(table) {
+            Ok(table) => {
+                let reply = Reply::ok(200, &table.state(id));
+                self.tables.insert(id, table);
+
+                reply
+            },
+            Err((reply, Some(table))) => {
+                self.tables.insert(id, *table);
+                reply
+            },
+            Err((reply, None)) => reply,
+        }
+    }
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, Reply> {
+    serde_json::from_str(body).map_err(|e| Reply::error(400, format!("The request body is not valid: {e}")))
+}
+
+/// Wager units on each round from the next one, clearing away a finished round
+fn bet_on(table: ApiTable, units: f64) -> Result<ApiTable, (Reply, Option<Box<ApiTable>>)> {
+    if !units.is_finite() || units <= 0.0 {
+        return Err((Reply::refused(&BlackjackRoundError::InvalidWager(units)), Some(Box::new(table))));
+    }
+
+    let table = match table.table {
+        AnyTable::GameOver(_) => table.apply(TableAction::NextRound)?,
+        _ => table,
+    };
+
+    match table.table {
+        AnyTable::NotStarted(t) => Ok(ApiTable { table: AnyTable::from(t.with_wager(units)), ..table }),
+        _ => Err((Reply::error(409, "Bets cannot be changed during a round"), Some(Box::new(table)))),
+    }
+}
+
+/// Deal a round, clearing away a finished one and reshuffling the shoe if it needs to be
+fn deal(table: ApiTable) -> Result<ApiTable, (Reply, Option<Box<ApiTable>>)> {
+    let table = match table.table {
+        AnyTable::GameOver(_) => table.apply(TableAction::NextRound)?,
+        _ => table,
+    };
+
+    let table = match &table.table {
+        AnyTable::NotStarted(t) if t.needs_reshuffle() => table.apply(TableAction::Reshuffle)?,
+        _ => table,
+    };
+
+    table.apply(TableAction::Deal)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+
+    use blackjack::events::TableEvent;
+    use blackjack::game::TableAction;
+    use tiny_http::{Method, Server};
+
+    use super::{serve, Api, TableState};
+
+    fn state(api: &mut Api, method: Method, url: &str, body: &str) -> TableState {
+        let reply = api.handle(&method, url, body);
+        assert!(reply.status < 300, "{method} {url} failed: {}", reply.body);
+
+        serde_json::from_value(reply.body).unwrap()
+    }
+
+    #[test]
+    fn tables_are_played_through_the_api() {
+        let mut api = Api::default();
+
+        let table = state(&mut api, Method::Post, "/tables", r#"{"seed": 4, "bankroll": 50, "rules": {
+            "decks": 2, "dealer_hits_soft_17": true, "dealer_peeks": true, "blackjack_payout": 1.5, "penetration": 0.5
+        }}"#);
+
+        assert_eq!(table.state, "not_started");
+        assert_eq!(table.bankroll, 50.0);
+
+        let url = format!("/tables/{}", table.id);
+
+        state(&mut api, Method::Post, &format!("{url}/bet"), r#"{"units": 2}"#);
+
+        let mut table = state(&mut api, Method::Post, &format!("{url}/deal"), "");
+
+        while table.result.is_none() {
+            let action = match table.legal_actions[..] {
+                [TableAction::Hit, TableAction::Stand] => "stand",
+                _ => "dealer_play",
+            };
+
+            table = state(&mut api, Method::Post, &format!("{url}/act"), &format!(r#"{{"action": "{action}"}}"#));
+        }
+
+        assert_eq!(table.state, "game_over");
+        assert_eq!(table.bankroll, 50.0 + 2.0 * table.payout.unwrap());
+
+        // The round is over, so the player cannot act
+        let reply = api.handle(&Method::Post, &format!("{url}/act"), r#"{"action": "hit"}"#);
+        assert_eq!(reply.status, 409);
+
+        let reply = api.handle(&Method::Get, &format!("{url}/history"), "");
+        let events: Vec<TableEvent> = serde_json::from_value(reply.body["events"].clone()).unwrap();
+
+        assert!(matches!(events.first(), Some(TableEvent::ShoeShuffled { .. })));
+        assert!(matches!(events.last(), Some(TableEvent::RoundSettled { .. })));
+
+        // The next deal clears away the finished round
+        let table = state(&mut api, Method::Post, &format!("{url}/deal"), "");
+        assert_eq!(table.rounds, 1);
+    }
+
+    #[test]
+    fn bad_requests_are_refused() {
+        let mut api = Api::default();
+
+        assert_eq!(api.handle(&Method::Get, "/tables/1", "").status, 404);
+        assert_eq!(api.handle(&Method::Get, "/cards", "").status, 404);
+        assert_eq!(api.handle(&Method::Post, "/tables", "{not json").status, 400);
+        assert_eq!(api.handle(&Method::Post, "/tables", r#"{"wager": -1}"#).status, 400);
+
+        let table = state(&mut api, Method::Post, "/tables", "");
+
+        let url = format!("/tables/{}", table.id);
+        assert_eq!(api.handle(&Method::Post, &format!("{url}/bet"), r#"{"units": 0}"#).status, 400);
+        assert_eq!(api.handle(&Method::Post, &format!("{url}/act"), r#"{"action": "split"}"#).status, 400);
+        assert_eq!(api.handle(&Method::Post, &format!("{url}/act"), r#"{"action": "stand"}"#).status, 409);
+
+        assert_eq!(api.handle(&Method::Delete, &url, "").status, 200);
+        assert_eq!(api.handle(&Method::Get, &url, "").status, 404);
+    }
+
+    #[test]
+    fn requests_are_served_on_localhost() {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+
+        thread::spawn(move || serve(server, &mut Api::default(), None));
+
+        let body = r#"{"seed": 1}"#;
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST /tables HTTP/1.1\r\nHost: {addr}\r\nContent-Type: application/json\r\n\
+            Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len(),
+        ).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 201"), "{response}");
+        assert!(response.contains("application/json"));
+        assert!(response.contains(r#""state":"not_started""#));
+    }
+}
@@ -0,0 +1,68 @@
+//! A Blackjack engine, with the rules of the game, card counting and strategy
+//!
+//! A round is played on a [BlackjackTable](round::BlackjackTable), whose type
+//! changes as the round moves from the deal, to the player's turn,
+//! to the dealer's turn and then the result. Each state only has the
+//! actions that can be taken in it, so a round cannot be played out of order.
+//! Several players can share a dealer and shoe at a [SharedTable](shared::SharedTable).
+//! Agents can learn to play in the [BlackjackEnv](env::BlackjackEnv) reinforcement learning environment.
+//!
+//! # Example
+//!
+//! Play a round, standing on whatever is dealt:
+//! ```
+//! use blackjack::round::{BlackjackTable, DealerTurnResult, GameStartResult};
+//!
+//! let table = BlackjackTable::default().reshuffle();
+//!
+//! let finished = match table.deal().unwrap() {
+//!     GameStartResult::Natural(table) => table,
+//!     GameStartResult::Normal(table) => {
+//!         let mut dealer = table.stand();
+//!
+//!         loop {
+//!             dealer = match dealer.hit().unwrap() {
+//!                 DealerTurnResult::Hit(table) => table,
+//!                 DealerTurnResult::Stand(table) => break table,
+//!             };
+//!         }
+//!     },
+//! };
+//!
+//! println!("{:?}, paying {:+}", finished.result(), finished.payout());
+//!
+//! // The shoe and the player's session carry on into the next round
+//! let table = finished.next_round();
+//! assert_eq!(table.session().stats.rounds, 1);
+//! ```
+//!
+//! # Features
+//!
+//! - `serde` (default): serialization of the engine's types, saved tables,
+//!   hand histories and lifetime statistics
+//! - `game` (default): the TUI and CLI binary, which is all
+//!   the `clap`, `cursive` and `dirs` dependencies are needed for
+
+mod game_rules;
+
+pub use game_rules::{
+    cards,
+    composition,
+    count,
+    dealer_outcomes,
+    deck,
+    deviations,
+    env,
+    ev,
+    events,
+    game,
+    hand,
+    round,
+    rules,
+    session,
+    shared,
+    strategy,
+};
+
+#[cfg(feature = "serde")]
+pub use game_rules::{history, lifetime};
@@ -1,32 +1,275 @@
+use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{error::ErrorKind, Args, CommandFactory, Parser, Subcommand};
 
 mod cli;
+mod bot;
 mod tui;
-mod game_rules;
+mod simulation;
+mod probabilities;
+mod stats;
+mod files;
+mod server;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "http")]
+mod web;
+#[cfg(test)]
+mod testing;
 
-#[derive(Parser, Clone, Copy)]
+use blackjack::deviations::IndexTable;
+use blackjack::history::HistoryLog;
+use blackjack::lifetime::LifetimeStats;
+use blackjack::rules::Rules;
+use blackjack::game::AnyTable;
+use blackjack::session::STARTING_BANKROLL;
+
+#[derive(Parser)]
 #[command(author = "Bronson Jordan")]
 #[command(about = "Play Blackjack in a CLI or TUI")]
+struct Arguments {
+
+    #[command(flatten)]
+    config: Config,
+
+    /// Append a JSON Lines history of every round played to a file.
+    /// Only the game keeps a history, so this cannot be used with a subcommand
+    #[arg(long, value_name = "FILE")]
+    history: Option<PathBuf>,
+
+    /// Resume a session saved when the TUI was quit,
+    /// saving it back to the same file on quitting
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["cli", "bot_protocol"])]
+    resume: Option<PathBuf>,
+
+    /// Clear the statistics kept across sessions before starting
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    reset_stats: bool,
+
+    #[command(subcommand)]
+    mode: Option<Mode>
+}
+
+#[derive(Args, Clone, Copy)]
 pub struct Config {
 
     /// Only use ASCII text
     /// (By default UTF-8 characters are used for card suits)
-    #[arg(short, long, action = clap::ArgAction::SetTrue)]
+    #[arg(short, long, action = clap::ArgAction::SetTrue, global = true)]
     ascii: bool,
 
     /// Run the game as a CLI instead of a TUI
     #[arg(short, long, action = clap::ArgAction::SetTrue)]
-    cli: bool
+    cli: bool,
+
+    /// Show the expected value of each action during your turn
+    #[arg(short = 'e', long, action = clap::ArgAction::SetTrue)]
+    show_ev: bool,
+
+    /// Show the true count and the Hi-Lo index play during your turn
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    hints: bool,
+
+    /// Write each decision as a line of JSON to stdout and read the chosen
+    /// action from stdin, so that another program can play the game
+    #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "cli")]
+    bot_protocol: bool,
+
+    /// Bankroll to start a new session with, in units
+    #[arg(long, default_value_t = STARTING_BANKROLL)]
+    bankroll: f64,
+}
+
+/// House rules shared by the modes that play without a human player
+#[derive(Args, Clone, Copy)]
+pub struct RuleArgs {
+
+    /// Number of decks in the shoe
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u8).range(1..))]
+    decks: u8,
+
+    /// The dealer hits soft 17 instead of standing
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    h17: bool,
+
+    /// The dealer does not check for a natural before the player acts
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    no_peek: bool,
+
+    /// Amount paid per unit wagered on a player's natural
+    #[arg(long, default_value_t = 1.5, value_parser = parse_payout)]
+    blackjack_payout: f64,
+
+    /// Fraction of the shoe dealt before it is reshuffled
+    #[arg(long, default_value_t = 0.75, value_parser = parse_fraction)]
+    penetration: f64,
+}
+
+impl From<RuleArgs> for Rules {
+    fn from(args: RuleArgs) -> Self {
+        Self {
+            decks: args.decks,
+            dealer_hits_soft_17: args.h17,
+            dealer_peeks: !args.no_peek,
+            blackjack_payout: args.blackjack_payout,
+            penetration: args.penetration,
+        }
+    }
+}
+
+/// Count-based deviations from basic strategy for automated players
+#[derive(Args, Clone)]
+pub struct DeviationArgs {
+
+    /// Play the Illustrious 18 and Fab 4 Hi-Lo index plays
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    deviations: bool,
+
+    /// Play the index plays listed in a file instead of the built-in ones
+    #[arg(long, value_name = "FILE", value_parser = read_index_table)]
+    indices: Option<IndexTable>,
+}
+
+impl DeviationArgs {
+    /// Returns the index table selected by the arguments, if any
+    pub fn index_table(self) -> Option<IndexTable> {
+        match self.indices {
+            Some(table) => Some(table),
+            None if self.deviations => Some(IndexTable::hi_lo()),
+            None => None,
+        }
+    }
+}
+
+fn read_index_table(path: &str) -> Result<IndexTable, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    contents.parse().map_err(|e: blackjack::deviations::IndexTableError| e.to_string())
+}
+
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(v) if v > 0.0 && v <= 1.0 => Ok(v),
+        Ok(_) => Err("must be greater than 0 and at most 1".into()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_payout(s: &str) -> Result<f64, String> {
+    let payout = s.parse::<f64>().map_err(|e| e.to_string())?;
+
+    Rules { blackjack_payout: payout, ..Rules::default() }.validate()
+        .map(|()| payout)
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Subcommand)]
+enum Mode {
+    /// Practice keeping the running and true count
+    Train(tui::trainer::TrainerConfig),
+
+    /// Step through the rounds of a hand history written with `--history`
+    Replay(tui::replay::ReplayConfig),
+
+    /// Play many rounds headlessly and report the results
+    Simulate(simulation::SimulationConfig),
+
+    /// Estimate the risk of ruin and the growth of a bankroll
+    Bankroll(simulation::bankroll::BankrollConfig),
+
+    /// Simulate the true count at which each index play becomes profitable
+    Indices(simulation::indices::IndexConfig),
+
+    /// Print the chance of each final dealer total for every upcard
+    Probabilities(probabilities::ProbabilitiesConfig),
+
+    /// Print the statistics of every hand played across sessions
+    Stats,
+
+    /// Host a table with several seats for players connecting over TCP
+    Serve(server::ServerConfig),
+
+    /// Host a table on this machine and play at it in the TUI
+    Host(tui::remote::HostConfig),
+
+    /// Play in the TUI at a table hosted with `serve` or `host`
+    Connect(tui::remote::ConnectConfig),
+
+    /// Let players on this machine play the CLI game with `nc localhost <port>`
+    Telnet(cli::TelnetConfig),
+
+    /// Serve a REST API for creating and playing tables on localhost
+    #[cfg(feature = "http")]
+    Http(http::HttpConfig),
+
+    /// Serve a page on localhost for playing in a browser
+    #[cfg(feature = "http")]
+    Web(web::WebConfig),
 }
 
 fn main() {
 
-    let config = Config::parse();
+    let args = Arguments::parse();
+    let config = args.config;
 
-    if config.cli {
-        cli::run_game(config);
-    } else {
-        tui::run_game(config);
+    // Clap cannot mark an argument as conflicting with subcommands, so this is checked here
+    if args.mode.is_some() && (args.history.is_some() || args.resume.is_some()) {
+        Arguments::command()
+            .error(ErrorKind::ArgumentConflict, "--history and --resume can only be used to play the game, not with a subcommand")
+            .exit();
     }
-}
\ No newline at end of file
+
+    let history = match args.history.map(HistoryLog::create).transpose() {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("Unable to open the history file: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    if args.reset_stats {
+        match files::stats_path() {
+            Some(path) => {
+                if let Err(e) = LifetimeStats::default().save(&path) {
+                    eprintln!("Unable to reset the statistics: {e}");
+                    std::process::exit(1);
+                }
+
+                println!("The statistics have been reset");
+            },
+            None => eprintln!("No data directory was found to keep statistics in, so there are none to reset"),
+        }
+    }
+
+    let resumed = match args.resume.as_ref().map(AnyTable::load).transpose() {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Unable to resume the session: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    // A resumed session is saved back to its own file
+    let save_path = args.resume.or_else(files::default_save_path);
+
+    match args.mode {
+        Some(Mode::Train(trainer_cfg)) => tui::trainer::run_trainer(config, trainer_cfg),
+        Some(Mode::Replay(replay_cfg)) => tui::replay::run_replay(config, replay_cfg),
+        Some(Mode::Simulate(sim_cfg)) => simulation::run_simulation(sim_cfg),
+        Some(Mode::Bankroll(bankroll_cfg)) => simulation::bankroll::run_bankroll(bankroll_cfg),
+        Some(Mode::Indices(index_cfg)) => simulation::indices::run_index_generator(index_cfg),
+        Some(Mode::Probabilities(prob_cfg)) => probabilities::print_table(prob_cfg),
+        Some(Mode::Stats) => stats::print_stats(),
+        Some(Mode::Serve(server_cfg)) => server::run_server(server_cfg),
+        Some(Mode::Host(host_cfg)) => tui::remote::run_host(config, host_cfg),
+        Some(Mode::Connect(connect_cfg)) => tui::remote::run_remote(config, connect_cfg),
+        Some(Mode::Telnet(telnet_cfg)) => cli::run_telnet(config, telnet_cfg),
+        #[cfg(feature = "http")]
+        Some(Mode::Http(http_cfg)) => http::run_http(http_cfg),
+        #[cfg(feature = "http")]
+        Some(Mode::Web(web_cfg)) => web::run_web(config, web_cfg),
+        None if config.cli => cli::run_game(config, history),
+        None if config.bot_protocol => bot::run_bot_protocol(config, history),
+        None => tui::run_game(config, history, resumed, save_path),
+    }
+}
@@ -0,0 +1,89 @@
+use clap::Args;
+
+use crate::RuleArgs;
+use blackjack::composition::Composition;
+use blackjack::dealer_outcomes::dealer_outcomes;
+use blackjack::rules::Rules;
+
+#[derive(Args, Clone, Copy)]
+pub struct ProbabilitiesConfig {
+
+    #[command(flatten)]
+    rules: RuleArgs,
+}
+
+/// Print the chance of every final dealer result for each upcard
+pub fn print_table(prob_cfg: ProbabilitiesConfig) {
+    print!("{}", table(&Rules::from(prob_cfg.rules)));
+}
+
+/// Lay out the chance of every final dealer result for each upcard
+fn table(rules: &Rules) -> String {
+    let mut table = format!(
+        "Dealer final totals ({} deck{}, {}, {})\n\n",
+        rules.decks,
+        if rules.decks == 1 { "" } else { "s" },
+        if rules.dealer_hits_soft_17 { "H17" } else { "S17" },
+        if rules.dealer_peeks { "dealer peeks" } else { "no peek" },
+    );
+
+    table.push_str(&format!(
+        "{:<7}{:>8}{:>8}{:>8}{:>8}{:>8}{:>11}{:>8}\n",
+        "Upcard", "17", "18", "19", "20", "21", "Blackjack", "Bust"
+    ));
+
+    // Upcards are listed in the usual order, from 2 through to the Ace
+    for upcard in (2..=10).chain([1]) {
+        let mut shoe = Composition::from_decks(rules.decks);
+        shoe.remove(upcard);
+
+        let outcomes = dealer_outcomes(&shoe, upcard, rules);
+
+        let label = match upcard {
+            1 => "A".to_string(),
+            v => v.to_string(),
+        };
+
+        table.push_str(&format!("{label:<7}"));
+
+        for total in 17..=21 {
+            table.push_str(&format!("{:>7.2}%", outcomes.total(total) * 100.0));
+        }
+
+        table.push_str(&format!("{:>10.2}%{:>7.2}%\n", outcomes.blackjack * 100.0, outcomes.bust * 100.0));
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use blackjack::rules::Rules;
+
+    use super::table;
+
+    #[test]
+    fn every_upcard_has_a_row_of_chances() {
+        let rules = Rules { decks: 2, dealer_hits_soft_17: true, ..Rules::default() };
+        let table = table(&rules);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines[0], "Dealer final totals (2 decks, H17, dealer peeks)");
+        assert_eq!(lines.len(), 3 + 10);
+
+        for (line, upcard) in lines[3..].iter().zip(["2", "3", "4", "5", "6", "7", "8", "9", "10", "A"]) {
+            let mut columns = line.split_whitespace();
+            assert_eq!(columns.next(), Some(upcard));
+
+            let chances: Vec<f64> = columns.map(|c| c.trim_end_matches('%').parse().unwrap()).collect();
+            assert_eq!(chances.len(), 7);
+
+            // Every round ends on one of the results
+            let total: f64 = chances.iter().sum();
+            assert!((total - 100.0).abs() < 0.05, "{line}");
+        }
+
+        // A dealer showing a 2 can never have a blackjack
+        assert!(lines[3].contains(" 0.00%"));
+    }
+}
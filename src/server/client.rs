@@ -0,0 +1,145 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+
+use super::protocol::{Command, ServerMessage};
+
+/// A connection to a table hosted by a [Server](super::Server)
+pub struct Connection {
+    stream: TcpStream,
+}
+
+impl Connection {
+    /// Connect to the table and pass every message it sends to `on_message`
+    /// from a new thread, which is called with [None] once the connection closes
+    ///
+    /// Lines that are not a [ServerMessage] are skipped
+    pub fn open<A, F>(addr: A, mut on_message: F) -> io::Result<Self>
+    where
+        A: ToSocketAddrs,
+        F: FnMut(Option<ServerMessage>) + Send + 'static,
+    {
+        let stream = TcpStream::connect(addr)?;
+        let reader = BufReader::new(stream.try_clone()?);
+
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+
+                if let Ok(message) = serde_json::from_str(&line) {
+                    on_message(Some(message));
+                }
+            }
+
+            on_message(None);
+        });
+
+        Ok(Self { stream })
+    }
+
+    pub fn send(&mut self, command: &Command) -> io::Result<()> {
+        writeln!(self.stream, "{command}")
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    use blackjack::hand::Hand;
+    use blackjack::rules::Rules;
+    use blackjack::shared::Phase;
+
+    use crate::server::protocol::{Command, ServerMessage};
+    use crate::server::{Server, Settings};
+
+    use super::Connection;
+
+    fn connect(addr: std::net::SocketAddr) -> (Connection, Receiver<ServerMessage>) {
+        let (tx, rx) = mpsc::channel();
+
+        let connection = Connection::open(addr, move |message| {
+            if let Some(message) = message {
+                let _ = tx.send(message);
+            }
+        }).unwrap();
+
+        (connection, rx)
+    }
+
+    fn next(rx: &Receiver<ServerMessage>) -> ServerMessage {
+        rx.recv_timeout(Duration::from_secs(10)).unwrap()
+    }
+
+    #[test]
+    fn dealer_draws_are_sent_as_they_happen() {
+        let settings = Settings {
+            seats: 2,
+            rules: Rules::default(),
+            bankroll: 100.0,
+            timeout: Duration::from_secs(10),
+            grace: Duration::from_secs(10),
+            dealer_pause: Duration::from_millis(20),
+            seed: Some(11),
+        };
+
+        let server = Server::bind("127.0.0.1:0", settings).unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        let (mut player, messages) = connect(addr);
+        let (_watcher, watched) = connect(addr);
+
+        player.send(&Command::Join { name: "Alice".to_string() }).unwrap();
+
+        let seat = loop {
+            if let ServerMessage::Seated { seat, .. } = next(&messages) {
+                break seat;
+            }
+        };
+
+        // Play a few rounds so that the dealer has to draw in at least one
+        let mut draws = 0;
+
+        for _ in 0..5 {
+            player.send(&Command::Bet(1.0)).unwrap();
+
+            let mut drawn = Vec::new();
+
+            let view = loop {
+                match next(&watched) {
+                    ServerMessage::Turn { seat: turn, .. } if turn == seat => {
+                        player.send(&Command::Stand).unwrap();
+                    },
+                    ServerMessage::DealerDrew { card } => drawn.push(card),
+                    ServerMessage::State(view) if view.phase == Phase::RoundOver => break view,
+                    _ => {},
+                }
+            };
+
+            // Every card after the first two was announced as it was drawn
+            assert_eq!(&view.dealer[2..], &drawn[..]);
+            assert_eq!(view.dealer_total, Hand::new(view.dealer.clone()).total_value());
+
+            draws += drawn.len();
+
+            loop {
+                if let ServerMessage::State(view) = next(&watched) {
+                    if view.phase == Phase::Betting {
+                        break;
+                    }
+                }
+            }
+        }
+
+        assert!(draws > 0);
+    }
+}
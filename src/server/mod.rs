@@ -0,0 +1,740 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::RuleArgs;
+use blackjack::rules::Rules;
+use blackjack::session::STARTING_BANKROLL;
+use blackjack::shared::{Phase, SeatError, SharedTable};
+
+use clap::Args;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+pub mod client;
+pub mod protocol;
+
+use protocol::{Command, ServerMessage, TableView};
+
+/// The longest the server waits before checking its timers again
+const POLL: Duration = Duration::from_millis(250);
+
+/// How long a write to a client may block before it is given up on
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Args, Clone, Copy)]
+pub struct ServerConfig {
+
+    /// Port to listen on. Only connections from this machine are accepted
+    #[arg(short, long, default_value_t = 7878)]
+    port: u16,
+
+    /// Number of seats at the table
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u8).range(1..))]
+    seats: u8,
+
+    /// Seconds a player has to act on their hand before they are stood,
+    /// and to bet once someone else has
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+
+    /// Seconds the seat of a player who lost their connection is kept for them
+    #[arg(long, default_value_t = 120)]
+    grace: u64,
+
+    /// Bankroll each player sits down with, in units
+    #[arg(long, default_value_t = STARTING_BANKROLL)]
+    bankroll: f64,
+
+    /// Seed for shuffling, so that the shoe can be repeated exactly
+    #[arg(long)]
+    seed: Option<u64>,
+
+    #[command(flatten)]
+    rules: RuleArgs,
+}
+
+/// How the server runs its table
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    pub seats: usize,
+    pub rules: Rules,
+    pub bankroll: f64,
+    /// How long a player has to act, or to bet once someone else has
+    pub timeout: Duration,
+    /// How long a disconnected player's seat is kept for them
+    pub grace: Duration,
+    /// The pause between each card the dealer draws, and before the next round
+    pub dealer_pause: Duration,
+    pub seed: Option<u64>,
+}
+
+impl From<ServerConfig> for Settings {
+    fn from(cfg: ServerConfig) -> Self {
+        Self {
+            seats: cfg.seats.into(),
+            rules: cfg.rules.into(),
+            bankroll: cfg.bankroll,
+            timeout: Duration::from_secs(cfg.timeout),
+            grace: Duration::from_secs(cfg.grace),
+            dealer_pause: Duration::from_secs(1),
+            seed: cfg.seed,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Listen for players on localhost, at the configured port
+    pub fn bind(self) -> io::Result<Server> {
+        Server::bind(("127.0.0.1", self.port), Settings::from(self))
+    }
+}
+
+/// Host a table on localhost until the process is stopped
+pub fn run_server(cfg: ServerConfig) {
+    let settings = Settings::from(cfg);
+
+    let server = match cfg.bind() {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Unable to listen on port {}: {e}", cfg.port);
+            std::process::exit(1);
+        },
+    };
+
+    let addr = server.local_addr().map(|a| a.to_string()).unwrap_or_else(|_| format!("127.0.0.1:{}", cfg.port));
+
+    println!("Hosting a table with {} seats on {addr}", settings.seats);
+    println!("Connect with e.g. `nc {}` and send `join <name>`", addr.replace(':', " "));
+
+    server.run();
+}
+
+/// Something that happened on a client's connection,
+/// sent to the thread running the table
+enum Incoming {
+    Connected { id: usize, stream: TcpStream },
+    Line { id: usize, line: String },
+    Closed { id: usize },
+}
+
+/// A table served over a line based TCP protocol
+///
+/// Clients send one [Command] per line and are sent
+/// one [ServerMessage] per line, as JSON
+pub struct Server {
+    listener: TcpListener,
+    settings: Settings,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A, settings: Settings) -> io::Result<Self> {
+        Ok(Self { listener: TcpListener::bind(addr)?, settings })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept clients and play the table, blocking the current thread
+    ///
+    /// Each connection is read on its own thread, while a single
+    /// thread owns the table and answers every client in turn
+    pub fn run(self) {
+        let (tx, rx) = mpsc::channel();
+        let listener = self.listener;
+
+        thread::spawn(move || {
+            for (id, stream) in listener.incoming().enumerate() {
+                let Ok(stream) = stream else { continue };
+
+                if let Err(e) = accept(id, stream, &tx) {
+                    eprintln!("Unable to accept a connection: {e}");
+                }
+            }
+        });
+
+        Host::new(self.settings).run(rx);
+    }
+}
+
+/// Pass the connection to the table and forward its lines from a new thread
+fn accept(id: usize, stream: TcpStream, tx: &Sender<Incoming>) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+    let reader = BufReader::new(stream.try_clone()?);
+    let tx = tx.clone();
+
+    tx.send(Incoming::Connected { id, stream }).map_err(|_| io::ErrorKind::BrokenPipe)?;
+
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+
+            if tx.send(Incoming::Line { id, line }).is_err() {
+                return;
+            }
+        }
+
+        let _ = tx.send(Incoming::Closed { id });
+    });
+
+    Ok(())
+}
+
+struct Client {
+    stream: TcpStream,
+    seat: Option<usize>,
+}
+
+/// A seat held for a player, so they can rejoin it
+struct Reservation {
+    token: String,
+    /// The connection playing from the seat, if any
+    client: Option<usize>,
+    /// When the player lost their connection
+    away_since: Option<Instant>,
+    /// The player asked to leave once their hand is settled
+    leaving: bool,
+}
+
+/// The table and the clients playing at it
+struct Host {
+    table: SharedTable,
+    settings: Settings,
+    rng: StdRng,
+    clients: HashMap<usize, Client>,
+    reservations: Vec<Option<Reservation>>,
+    /// The phase the timer was started in, and when it runs out
+    timer: Option<(Phase, Instant)>,
+}
+
+impl Host {
+    fn new(settings: Settings) -> Self {
+        let mut rng = match settings.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut table = SharedTable::new(settings.rules, settings.seats);
+        table.reshuffle_with(&mut rng);
+
+        Self {
+            table,
+            settings,
+            rng,
+            clients: HashMap::new(),
+            reservations: (0..settings.seats).map(|_| None).collect(),
+            timer: None,
+        }
+    }
+
+    fn run(mut self, rx: Receiver<Incoming>) {
+        loop {
+            let wait = match self.timer {
+                Some((_, deadline)) => deadline.saturating_duration_since(Instant::now()).min(POLL),
+                None => POLL,
+            };
+
+            match rx.recv_timeout(wait) {
+                Ok(Incoming::Connected { id, stream }) => self.connect(id, stream),
+                Ok(Incoming::Line { id, line }) => self.command(id, &line),
+                Ok(Incoming::Closed { id }) => self.disconnect(id),
+                Err(RecvTimeoutError::Timeout) => {},
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+
+            while self.step(Instant::now()) {}
+        }
+    }
+
+    fn connect(&mut self, id: usize, stream: TcpStream) {
+        self.clients.insert(id, Client { stream, seat: None });
+
+        self.send(id, &ServerMessage::Welcome { seats: self.settings.seats, rules: self.settings.rules });
+        self.send(id, &ServerMessage::State(self.view()));
+    }
+
+    /// Keep the client's seat for them to rejoin
+    fn disconnect(&mut self, id: usize) {
+        let Some(client) = self.clients.remove(&id) else { return };
+
+        if let Some(reservation) = client.seat.and_then(|seat| self.reservations[seat].as_mut()) {
+            reservation.client = None;
+            reservation.away_since = Some(Instant::now());
+
+            self.broadcast_state();
+        }
+    }
+
+    fn command(&mut self, id: usize, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let seat = self.clients.get(&id).and_then(|c| c.seat);
+
+        let result = match (line.parse::<Command>(), seat) {
+            (Err(e), _) => Err(e),
+            (Ok(Command::State), _) => {
+                self.send(id, &ServerMessage::State(self.view()));
+                Ok(())
+            },
+            (Ok(Command::Join { .. } | Command::Rejoin { .. }), Some(seat)) => {
+                Err(format!("you are already sitting in seat {seat}"))
+            },
+            (Ok(Command::Join { name }), None) => self.join(id, name),
+            (Ok(Command::Rejoin { token }), None) => self.rejoin(id, &token),
+            (Ok(_), None) => Err("join the table first, e.g. `join Alice`".to_string()),
+            (Ok(Command::Leave), Some(seat)) => {
+                self.leave(seat);
+                Ok(())
+            },
+            (Ok(Command::Bet(units)), Some(seat)) => self.play(|t| t.bet(seat, units)),
+            (Ok(Command::Hit), Some(seat)) => self.play(|t| t.hit(seat)),
+            (Ok(Command::Stand), Some(seat)) => self.play(|t| t.stand(seat)),
+        };
+
+        if let Err(message) = result {
+            self.send(id, &ServerMessage::Error { message });
+        }
+    }
+
+    fn join(&mut self, id: usize, name: String) -> Result<(), String> {
+        let seat = self.table.sit(name, self.settings.bankroll).map_err(|e| e.to_string())?;
+        let token = format!("{:016x}", self.rng.gen::<u64>());
+
+        self.reservations[seat] = Some(Reservation {
+            token: token.clone(),
+            client: Some(id),
+            away_since: None,
+            leaving: false,
+        });
+
+        self.seat_client(id, seat, token);
+
+        Ok(())
+    }
+
+    /// Move the client into a seat kept for them, taking it
+    /// from any other connection still playing from it
+    fn rejoin(&mut self, id: usize, token: &str) -> Result<(), String> {
+        let seat = self.reservations.iter()
+            .position(|r| r.as_ref().is_some_and(|r| r.token == token && !r.leaving))
+            .ok_or("there is no seat kept for that token")?;
+
+        let reservation = self.reservations[seat].as_mut().unwrap();
+        let previous = reservation.client.replace(id);
+        reservation.away_since = None;
+
+        if let Some(client) = previous.and_then(|previous| self.clients.get_mut(&previous)) {
+            client.seat = None;
+        }
+
+        self.seat_client(id, seat, token.to_string());
+
+        if let Some((Phase::PlayerTurn { seat: turn }, deadline)) = self.timer {
+            if turn == seat {
+                let seconds = deadline.saturating_duration_since(Instant::now()).as_secs_f64();
+                self.send(id, &ServerMessage::Turn { seat, seconds });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn seat_client(&mut self, id: usize, seat: usize, token: String) {
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.seat = Some(seat);
+        }
+
+        self.send(id, &ServerMessage::Seated { seat, token });
+        self.broadcast_state();
+    }
+
+    /// Free the seat, or stand the player's hand and free
+    /// the seat once it is settled
+    fn leave(&mut self, seat: usize) {
+        for client in self.clients.values_mut().filter(|c| c.seat == Some(seat)) {
+            client.seat = None;
+        }
+
+        match self.table.leave(seat) {
+            Err(SeatError::HandInPlay) => {
+                if let Some(reservation) = self.reservations[seat].as_mut() {
+                    reservation.client = None;
+                    reservation.leaving = true;
+                }
+
+                self.broadcast_state();
+            },
+            _ => self.free_seat(seat),
+        }
+    }
+
+    fn free_seat(&mut self, seat: usize) {
+        self.reservations[seat] = None;
+
+        self.broadcast(&ServerMessage::Left { seat });
+        self.broadcast_state();
+    }
+
+    /// Take a player's action at the table and show everyone the result
+    fn play(&mut self, action: impl FnOnce(&mut SharedTable) -> Result<(), SeatError>) -> Result<(), String> {
+        action(&mut self.table).map_err(|e| e.to_string())?;
+        self.broadcast_state();
+
+        Ok(())
+    }
+
+    /// Returns when the timer for the current phase runs out, starting
+    /// it if the phase has changed, and whether it was just started
+    fn timer(&mut self, now: Instant, duration: Duration) -> (Instant, bool) {
+        match self.timer {
+            Some((phase, deadline)) if phase == self.table.phase() => (deadline, false),
+            _ => {
+                let deadline = now + duration;
+                self.timer = Some((self.table.phase(), deadline));
+
+                (deadline, true)
+            },
+        }
+    }
+
+    /// Move the table on if nobody has to be waited on,
+    /// returning true if anything changed
+    fn step(&mut self, now: Instant) -> bool {
+        if self.free_abandoned_seats(now) {
+            return true;
+        }
+
+        let result = match self.table.phase() {
+            Phase::Betting => {
+                let seated = self.table.seats().iter()
+                    .enumerate()
+                    .filter_map(|(seat, player)| Some((seat, player.as_ref()?)));
+
+                let mut betting = false;
+                let mut waiting = false;
+
+                for (seat, player) in seated {
+                    betting |= player.bet().is_some();
+                    waiting |= player.bet().is_none() && self.is_connected(seat);
+                }
+
+                if !betting {
+                    self.timer = None;
+                    return false;
+                }
+
+                let (deadline, _) = self.timer(now, self.settings.timeout);
+
+                if waiting && now < deadline {
+                    return false;
+                }
+
+                self.table.deal()
+            },
+            Phase::PlayerTurn { seat } => {
+                let (deadline, started) = self.timer(now, self.settings.timeout);
+
+                if started {
+                    self.broadcast(&ServerMessage::Turn { seat, seconds: self.settings.timeout.as_secs_f64() });
+                }
+
+                let leaving = self.reservations[seat].as_ref().is_none_or(|r| r.leaving);
+
+                if !leaving && now < deadline {
+                    return started;
+                }
+
+                if !leaving {
+                    self.broadcast(&ServerMessage::TimedOut { seat });
+                }
+
+                self.table.stand(seat)
+            },
+            Phase::DealerTurn => {
+                let (deadline, _) = self.timer(now, self.settings.dealer_pause);
+
+                if now < deadline {
+                    return false;
+                }
+
+                let drawn = self.table.dealer_hand().cards().len();
+                let result = self.table.dealer_play();
+
+                if let Some(&card) = self.table.dealer_hand().cards().get(drawn) {
+                    self.broadcast(&ServerMessage::DealerDrew { card });
+                }
+
+                // The pause starts again before the dealer's next card
+                self.timer = None;
+                result
+            },
+            Phase::RoundOver => {
+                let (deadline, _) = self.timer(now, self.settings.dealer_pause);
+
+                if now < deadline {
+                    return false;
+                }
+
+                let leaving: Vec<usize> = (0..self.reservations.len())
+                    .filter(|&seat| self.reservations[seat].as_ref().is_some_and(|r| r.leaving))
+                    .collect();
+
+                for seat in leaving {
+                    if self.table.leave(seat).is_ok() {
+                        self.free_seat(seat);
+                    }
+                }
+
+                let result = self.table.next_round();
+
+                if self.table.needs_reshuffle() {
+                    self.table.reshuffle_with(&mut self.rng);
+                }
+
+                result
+            },
+        };
+
+        if let Err(e) = result {
+            // The table cannot carry on, so start the shoe again
+            eprintln!("The round could not be played: {e}");
+            self.broadcast(&ServerMessage::Error { message: format!("The round could not be played: {e}") });
+
+            let mut table = SharedTable::new(self.settings.rules, self.settings.seats);
+            table.reshuffle_with(&mut self.rng);
+            self.table = table;
+            self.reservations.iter_mut().for_each(|r| *r = None);
+
+            for client in self.clients.values_mut() {
+                client.seat = None;
+            }
+        }
+
+        self.broadcast_state();
+
+        true
+    }
+
+    /// Free the seats of players who have been gone for longer than the grace period,
+    /// returning true if any were freed
+    fn free_abandoned_seats(&mut self, now: Instant) -> bool {
+        let abandoned: Vec<usize> = (0..self.reservations.len())
+            .filter(|&seat| {
+                let gone = self.reservations[seat].as_ref()
+                    .and_then(|r| r.away_since)
+                    .is_some_and(|since| now.duration_since(since) >= self.settings.grace);
+
+                gone && self.table.seat(seat).is_some_and(|p| !p.in_round() || self.table.phase() == Phase::RoundOver)
+            })
+            .collect();
+
+        for &seat in &abandoned {
+            if self.table.leave(seat).is_ok() {
+                self.free_seat(seat);
+            }
+        }
+
+        !abandoned.is_empty()
+    }
+
+    fn is_connected(&self, seat: usize) -> bool {
+        self.reservations[seat].as_ref().is_some_and(|r| r.client.is_some())
+    }
+
+    fn view(&self) -> TableView {
+        TableView::new(&self.table, |seat| self.is_connected(seat))
+    }
+
+    fn broadcast_state(&mut self) {
+        self.broadcast(&ServerMessage::State(self.view()));
+    }
+
+    fn broadcast(&mut self, message: &ServerMessage) {
+        let line = serde_json::to_string(message).expect("Server messages can always be serialized");
+
+        // A client that cannot be written to is dropped when its reader sees the connection close
+        for client in self.clients.values_mut() {
+            let _ = writeln!(client.stream, "{line}");
+        }
+    }
+
+    fn send(&mut self, id: usize, message: &ServerMessage) {
+        let line = serde_json::to_string(message).expect("Server messages can always be serialized");
+
+        if let Some(client) = self.clients.get_mut(&id) {
+            let _ = writeln!(client.stream, "{line}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
+    use blackjack::rules::Rules;
+    use blackjack::shared::Phase;
+
+    use super::protocol::{ServerMessage, TableView};
+    use super::{Server, Settings};
+
+    fn serve(timeout: Duration) -> SocketAddr {
+        let settings = Settings {
+            seats: 3,
+            rules: Rules::default(),
+            bankroll: 100.0,
+            timeout,
+            grace: Duration::from_secs(30),
+            dealer_pause: Duration::ZERO,
+            seed: Some(7),
+        };
+
+        let server = Server::bind("127.0.0.1:0", settings).unwrap();
+        let addr = server.local_addr().unwrap();
+
+        thread::spawn(move || server.run());
+
+        addr
+    }
+
+    struct TestClient {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl TestClient {
+        fn connect(addr: SocketAddr) -> Self {
+            let writer = TcpStream::connect(addr).unwrap();
+            writer.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+
+            Self { reader: BufReader::new(writer.try_clone().unwrap()), writer }
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{line}").unwrap();
+        }
+
+        /// Read messages until one matches, returning its value
+        fn until<T>(&mut self, mut matches: impl FnMut(&ServerMessage) -> Option<T>) -> T {
+            loop {
+                let mut line = String::new();
+                self.reader.read_line(&mut line).unwrap();
+
+                let message = serde_json::from_str(&line).unwrap();
+
+                if let Some(value) = matches(&message) {
+                    return value;
+                }
+            }
+        }
+
+        fn join(&mut self, name: &str) -> (usize, String) {
+            self.send(&format!("join {name}"));
+
+            self.until(|m| match m {
+                ServerMessage::Seated { seat, token } => Some((*seat, token.clone())),
+                _ => None,
+            })
+        }
+
+        fn state(&mut self, mut matches: impl FnMut(&TableView) -> bool) -> TableView {
+            self.until(|m| match m {
+                ServerMessage::State(view) if matches(view) => Some(view.clone()),
+                _ => None,
+            })
+        }
+
+        /// Stand when it is the seat's turn, unless the seat's hand is settled first
+        fn stand(&mut self, seat: usize) {
+            let turn = self.until(|m| match m {
+                ServerMessage::Turn { seat: turn, .. } if *turn == seat => Some(true),
+                ServerMessage::State(view) => match view.phase {
+                    Phase::PlayerTurn { seat: turn } if turn > seat => Some(false),
+                    Phase::DealerTurn | Phase::RoundOver => Some(false),
+                    _ => None,
+                },
+                _ => None,
+            });
+
+            if turn {
+                self.send("stand");
+            }
+        }
+    }
+
+    #[test]
+    fn clients_share_a_table() {
+        let addr = serve(Duration::from_secs(10));
+
+        let mut alice = TestClient::connect(addr);
+        let mut bob = TestClient::connect(addr);
+
+        let (a, _) = alice.join("Alice");
+        let (b, _) = bob.join("Bob");
+        assert_ne!(a, b);
+
+        alice.send("bet 1");
+        bob.send("bet 2");
+
+        alice.stand(a);
+        bob.stand(b);
+
+        let over = |view: &TableView| view.phase == Phase::RoundOver;
+        let (seen_by_alice, seen_by_bob) = (alice.state(over), bob.state(over));
+
+        assert_eq!(seen_by_alice, seen_by_bob);
+        assert!(seen_by_alice.dealer.len() >= 2);
+
+        for seat in [a, b] {
+            let player = seen_by_alice.seats[seat].as_ref().unwrap();
+            assert!(player.outcome.is_some());
+        }
+
+        assert_eq!(seen_by_bob.seats[b].as_ref().unwrap().bet, Some(2.0));
+
+        alice.send("hit");
+        alice.until(|m| matches!(m, ServerMessage::Error { .. }).then_some(()));
+    }
+
+    #[test]
+    fn idle_players_are_stood_and_can_rejoin() {
+        let addr = serve(Duration::from_millis(200));
+
+        let mut watcher = TestClient::connect(addr);
+        let mut alice = TestClient::connect(addr);
+
+        let (seat, token) = alice.join("Alice");
+        alice.send("bet 1");
+
+        // Alice goes quiet and then loses her connection during the round
+        alice.state(|view| matches!(view.phase, Phase::PlayerTurn { .. }));
+        drop(alice);
+
+        watcher.until(|m| (*m == ServerMessage::TimedOut { seat }).then_some(()));
+
+        let mut alice = TestClient::connect(addr);
+
+        alice.send("rejoin not-a-token");
+        alice.until(|m| matches!(m, ServerMessage::Error { .. }).then_some(()));
+
+        alice.send(&format!("rejoin {token}"));
+        let rejoined = alice.until(|m| match m {
+            ServerMessage::Seated { seat, .. } => Some(*seat),
+            _ => None,
+        });
+
+        assert_eq!(rejoined, seat);
+
+        let view = alice.state(|view| view.seats[seat].as_ref().is_some_and(|p| p.rounds == 1));
+        assert!(view.seats[seat].as_ref().unwrap().connected);
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use blackjack::cards::Card;
+use blackjack::hand::Hand;
+use blackjack::rules::Rules;
+use blackjack::shared::{Phase, SeatOutcome, SharedTable};
+
+use serde::{Deserialize, Serialize};
+
+/// A line sent by a client to the server
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Take the first empty seat
+    Join { name: String },
+    /// Return to a seat kept for a player who lost their connection
+    Rejoin { token: String },
+    /// Wager units on the next round
+    Bet(f64),
+    Hit,
+    Stand,
+    /// Give up the seat once the current hand is settled
+    Leave,
+    /// Ask for the state of the table to be sent again
+    State,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (word, rest) = match s.trim().split_once(char::is_whitespace) {
+            Some((word, rest)) => (word, rest.trim()),
+            None => (s.trim(), ""),
+        };
+
+        match (word.to_lowercase().as_str(), rest) {
+            ("join", "") => Err("join needs a name, e.g. `join Alice`".to_string()),
+            ("join", name) => Ok(Self::Join { name: name.to_string() }),
+            ("rejoin", "") => Err("rejoin needs the token sent when you joined".to_string()),
+            ("rejoin", token) => Ok(Self::Rejoin { token: token.to_string() }),
+            ("bet", units) => units.parse()
+                .map(Self::Bet)
+                .map_err(|_| format!("`{units}` is not a number of units")),
+            ("hit", "") => Ok(Self::Hit),
+            ("stand", "") => Ok(Self::Stand),
+            ("leave", "") => Ok(Self::Leave),
+            ("state", "") => Ok(Self::State),
+            _ => Err(format!("unknown command `{}`, expected one of: join, rejoin, bet, hit, stand, leave, state", s.trim())),
+        }
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Join { name } => write!(f, "join {name}"),
+            Self::Rejoin { token } => write!(f, "rejoin {token}"),
+            Self::Bet(units) => write!(f, "bet {units}"),
+            Self::Hit => write!(f, "hit"),
+            Self::Stand => write!(f, "stand"),
+            Self::Leave => write!(f, "leave"),
+            Self::State => write!(f, "state"),
+        }
+    }
+}
+
+/// A line of JSON sent by the server to its clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Sent to every client as it connects
+    Welcome { seats: usize, rules: Rules },
+    /// The client is playing from the seat. The token
+    /// can be used to rejoin it from another connection
+    Seated { seat: usize, token: String },
+    /// Everything the players can see at the table,
+    /// sent to every client whenever it changes
+    State(TableView),
+    /// The player in the seat has the number of seconds to act on their hand
+    Turn { seat: usize, seconds: f64 },
+    /// The dealer drew a card during their turn
+    DealerDrew { card: Card },
+    /// The player in the seat did not act in time and was stood
+    TimedOut { seat: usize },
+    /// The seat is free again
+    Left { seat: usize },
+    /// The client's last command could not be carried out
+    Error { message: String },
+}
+
+/// The table as the players see it, without the dealer's hole card
+/// or the order of the shoe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableView {
+    #[serde(flatten)]
+    pub phase: Phase,
+    pub dealer: Vec<Card>,
+    /// The total of the dealer's visible cards
+    pub dealer_total: u8,
+    pub seats: Vec<Option<SeatView>>,
+    pub cards_remaining: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatView {
+    pub name: String,
+    pub bankroll: f64,
+    /// Rounds the player has finished at the table
+    pub rounds: u64,
+    pub bet: Option<f64>,
+    pub cards: Vec<Card>,
+    pub total: u8,
+    pub outcome: Option<SeatOutcome>,
+    /// False while the player's seat is kept for them to rejoin
+    pub connected: bool,
+}
+
+impl TableView {
+    pub fn new(table: &SharedTable, connected: impl Fn(usize) -> bool) -> Self {
+        let dealer = table.visible_dealer_cards().to_vec();
+
+        let seats = table.seats().iter()
+            .enumerate()
+            .map(|(seat, player)| player.as_ref().map(|p| SeatView {
+                name: p.name().to_string(),
+                bankroll: p.session().bankroll,
+                rounds: p.session().stats.rounds,
+                bet: p.bet(),
+                cards: p.hand().cards().clone(),
+                total: p.hand().total_value(),
+                outcome: p.outcome(),
+                connected: connected(seat),
+            }))
+            .collect();
+
+        Self {
+            phase: table.phase(),
+            dealer_total: Hand::new(dealer.clone()).total_value(),
+            dealer,
+            seats,
+            cards_remaining: table.cards_remaining(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Command, ServerMessage, TableView};
+
+    use blackjack::rules::Rules;
+    use blackjack::shared::{SeatError, SharedTable};
+
+    #[test]
+    fn commands_round_trip() {
+        let commands = [
+            Command::Join { name: "Alice Smith".to_string() },
+            Command::Rejoin { token: "0123abcd".to_string() },
+            Command::Bet(2.5),
+            Command::Hit,
+            Command::Stand,
+            Command::Leave,
+            Command::State,
+        ];
+
+        for command in commands {
+            assert_eq!(command.to_string().parse(), Ok(command));
+        }
+
+        assert_eq!("  HIT ".parse(), Ok(Command::Hit));
+        assert!("bet lots".parse::<Command>().is_err());
+        assert!("join".parse::<Command>().is_err());
+        assert!("split".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn infinite_bets_are_refused() {
+        let mut table = SharedTable::new(Rules::default(), 2);
+        let seat = table.sit("Alice", 100.0).unwrap();
+
+        for line in ["bet inf", "bet 1e999"] {
+            let Ok(Command::Bet(units)) = line.parse() else { panic!("`{line}` is a bet") };
+
+            assert_eq!(table.bet(seat, units), Err(SeatError::InvalidBet(f64::INFINITY)));
+        }
+    }
+
+    #[test]
+    fn messages_are_single_lines() {
+        let mut table = SharedTable::new(Rules::default(), 2);
+        table.reshuffle();
+
+        let seat = table.sit("Alice", 100.0).unwrap();
+        table.bet(seat, 1.0).unwrap();
+        table.deal().unwrap();
+
+        let message = ServerMessage::State(TableView::new(&table, |_| true));
+        let line = serde_json::to_string(&message).unwrap();
+
+        assert!(!line.contains('\n'));
+        assert_eq!(serde_json::from_str::<ServerMessage>(&line).unwrap(), message);
+    }
+}
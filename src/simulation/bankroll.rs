@@ -0,0 +1,216 @@
+use clap::Args;
+
+use crate::{DeviationArgs, RuleArgs};
+
+use super::{BetSpread, SimulationStats, Simulator, Strategy};
+
+/// Percentiles of the session results that are reported
+const PERCENTILES: [f64; 9] = [1.0, 5.0, 10.0, 25.0, 50.0, 75.0, 90.0, 95.0, 99.0];
+
+/// Width, in characters, of the longest bar in the histogram
+const HISTOGRAM_WIDTH: usize = 50;
+
+#[derive(Args, Clone)]
+pub struct BankrollConfig {
+
+    /// Bankroll at the start of each session, in units
+    #[arg(short, long, default_value_t = 200.0)]
+    bankroll: f64,
+
+    /// Number of hands played in each session
+    #[arg(long, default_value_t = 10_000)]
+    hands: u64,
+
+    /// Number of sessions to simulate
+    #[arg(long, default_value_t = 1_000)]
+    sessions: u64,
+
+    /// The strategy played by the simulated player
+    #[arg(short, long, value_enum, default_value_t = Strategy::Basic)]
+    strategy: Strategy,
+
+    /// Units bet at each true count, e.g. `1:1,2:2,3:4,4:8`.
+    /// A single number bets that many units flat
+    #[arg(long, default_value = "1")]
+    spread: BetSpread,
+
+    /// Seed for shuffling, so that a simulation can be repeated exactly
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Number of bars in the histogram of session results
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u16).range(1..))]
+    bins: u16,
+
+    #[command(flatten)]
+    deviations: DeviationArgs,
+
+    #[command(flatten)]
+    rules: RuleArgs,
+}
+
+/// The result of a single simulated session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Session {
+    /// Units won or lost over the session
+    pub result: f64,
+    pub ruined: bool,
+    /// The hand on which the bankroll first doubled, if it did
+    pub doubled_after: Option<u64>,
+}
+
+/// Play a session of up to `hands` hands, stopping early if the bankroll runs out
+///
+/// Bets are capped at the remaining bankroll, so the last bet of a
+/// losing session may be smaller than the spread calls for
+pub fn play_session(sim: &mut Simulator, stats: &mut SimulationStats, bankroll: f64, hands: u64) -> Session {
+    let mut current = bankroll;
+    let mut doubled_after = None;
+
+    for hand in 1..=hands {
+        let outcome = sim.play_round();
+        stats.record(&outcome);
+
+        let scale = (current / outcome.bet).min(1.0);
+        current += outcome.net * scale;
+
+        if doubled_after.is_none() && current >= 2.0 * bankroll {
+            doubled_after = Some(hand);
+        }
+
+        if current <= f64::EPSILON {
+            return Session { result: -bankroll, ruined: true, doubled_after };
+        }
+    }
+
+    Session { result: current - bankroll, ruined: false, doubled_after }
+}
+
+/// Returns the value below which `p` percent of the sorted values fall
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = (p / 100.0 * (sorted.len() - 1) as f64).round() as usize;
+
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+/// Text histogram of the values, one line per bin
+pub fn histogram(sorted: &[f64], bins: u16) -> String {
+    let (min, max) = match (sorted.first(), sorted.last()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return String::new(),
+    };
+
+    let bins = usize::from(bins);
+    let width = ((max - min) / bins as f64).max(f64::EPSILON);
+
+    let mut counts = vec![0usize; bins];
+
+    for v in sorted {
+        let bin = ((v - min) / width) as usize;
+        counts[bin.min(bins - 1)] += 1;
+    }
+
+    let tallest = counts.iter().copied().max().unwrap_or(1).max(1);
+
+    counts.iter().enumerate()
+    .map(|(i, count)| {
+        let low = min + i as f64 * width;
+        let bar = "#".repeat(count * HISTOGRAM_WIDTH / tallest);
+
+        format!("{:>10.1} to {:>10.1} | {:<width$} {}", low, low + width, bar, count, width = HISTOGRAM_WIDTH)
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Simulate many sessions and print the risk of ruin,
+/// N0, time to double, percentiles and a histogram of results
+pub fn run_bankroll(cfg: BankrollConfig) {
+    let mut sim = Simulator::new(cfg.rules.into(), cfg.strategy, cfg.spread, cfg.seed)
+        .with_indices(cfg.deviations.index_table());
+    let mut stats = SimulationStats::default();
+
+    let mut sessions: Vec<Session> = (0..cfg.sessions)
+        .map(|_| play_session(&mut sim, &mut stats, cfg.bankroll, cfg.hands))
+        .collect();
+
+    sessions.sort_by(|a, b| a.result.total_cmp(&b.result));
+
+    let results: Vec<f64> = sessions.iter().map(|s| s.result).collect();
+    let n = sessions.len().max(1) as f64;
+
+    let ruined = sessions.iter().filter(|s| s.ruined).count();
+
+    let mut doubled: Vec<f64> = sessions.iter()
+        .filter_map(|s| s.doubled_after.map(|h| h as f64))
+        .collect();
+    doubled.sort_by(|a, b| a.total_cmp(b));
+
+    let mean = stats.mean();
+    let sd = stats.standard_deviation();
+
+    println!("Sessions:               {} of up to {} hands", sessions.len(), cfg.hands);
+    println!("Starting bankroll:      {:.1} units", cfg.bankroll);
+    println!("Win rate:               {:.4} units per hand", mean);
+    println!("Standard deviation:     {:.4} units per hand", sd);
+
+    if mean > 0.0 {
+        println!("N0:                     {:.0} hands", (sd / mean).powi(2));
+    } else {
+        println!("N0:                     never (the expected result is not positive)");
+    }
+
+    println!("Risk of ruin:           {:.2}%", ruined as f64 / n * 100.0);
+    println!("Sessions that doubled:  {:.2}%", doubled.len() as f64 / n * 100.0);
+
+    if !doubled.is_empty() {
+        println!("Median time to double:  {:.0} hands", percentile(&doubled, 50.0));
+    }
+
+    println!();
+    println!("Session results by percentile (units):");
+
+    for p in PERCENTILES {
+        println!("  {:>3.0}%: {:>10.1}", p, percentile(&results, p));
+    }
+
+    println!();
+    println!("{}", histogram(&results, cfg.bins));
+}
+
+#[cfg(test)]
+mod tests {
+    use blackjack::rules::Rules;
+    use crate::simulation::{BetSpread, SimulationStats, Simulator, Strategy};
+
+    use super::{histogram, percentile, play_session};
+
+    #[test]
+    fn percentiles() {
+        let values: Vec<f64> = (0..=100).map(f64::from).collect();
+
+        assert_eq!(percentile(&values, 0.0), 0.0);
+        assert_eq!(percentile(&values, 50.0), 50.0);
+        assert_eq!(percentile(&values, 99.0), 99.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+
+        assert_eq!(histogram(&values, 4).lines().count(), 4);
+    }
+
+    #[test]
+    fn small_bankrolls_are_ruined() {
+        let mut sim = Simulator::new(Rules::default(), Strategy::MimicDealer, BetSpread::flat(), Some(3));
+        let mut stats = SimulationStats::default();
+
+        let ruined = (0..50)
+            .map(|_| play_session(&mut sim, &mut stats, 2.0, 5_000))
+            .filter(|s| s.ruined)
+            .count();
+
+        assert!(ruined > 45);
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use clap::Args;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::RuleArgs;
+use blackjack::cards::Card;
+use blackjack::count::CountingSystem;
+use blackjack::deck::Deck;
+use blackjack::deviations::{IndexPlay, IndexTable, PlayerHand};
+use blackjack::hand::Hand;
+use blackjack::rules::Rules;
+use blackjack::strategy::{basic_strategy, Action, AvailableActions};
+
+/// Hands dealt at a true count further from zero than this are left out
+/// of the fit, as the gain of a play is no longer close to linear there
+const MAX_TRUE_COUNT: f32 = 10.0;
+
+#[derive(Args, Clone)]
+pub struct IndexConfig {
+
+    /// Number of hands simulated for each play
+    #[arg(short, long, default_value_t = 200_000)]
+    trials: u64,
+
+    /// Counting system to find indices for: `hi-lo`, `hi-opt-1`, `hi-opt-2`,
+    /// `omega-2`, `zen`, or ten tags for A, 2, ..., 9, 10 such as `-1,1,1,1,1,1,0,0,0,-1`
+    #[arg(long, default_value = "hi-lo")]
+    system: CountingSystem,
+
+    /// The plays to find indices for, in the same format as `--indices`.
+    /// Their indices are ignored. By default, the Illustrious 18 and Fab 4 are used
+    #[arg(long, value_name = "FILE", value_parser = crate::read_index_table)]
+    plays: Option<IndexTable>,
+
+    /// Seed for shuffling, so that the indices can be generated again exactly
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Write the index table to a file instead of printing it
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Include the average gain of each play at every true count
+    #[arg(short, long, action = clap::ArgAction::SetTrue)]
+    verbose: bool,
+
+    #[command(flatten)]
+    rules: RuleArgs,
+}
+
+/// The gain of taking a play's deviation instead of the
+/// usual action, accumulated over many hands
+#[derive(Debug, Default, Clone)]
+pub struct PlayGain {
+    n: f64,
+    sum_x: f64,
+    sum_y: f64,
+    sum_xx: f64,
+    sum_xy: f64,
+    /// Number of hands and total gain for each (floored) true count
+    buckets: BTreeMap<i32, (u64, f64)>,
+}
+
+impl PlayGain {
+    /// Record the gain, in units, of a hand dealt at the given true count
+    pub fn record(&mut self, true_count: f32, gain: f64) {
+        if true_count.abs() > MAX_TRUE_COUNT {
+            return;
+        }
+
+        let x = f64::from(true_count);
+
+        self.n += 1.0;
+        self.sum_x += x;
+        self.sum_y += gain;
+        self.sum_xx += x * x;
+        self.sum_xy += x * gain;
+
+        let bucket = self.buckets.entry(true_count.floor() as i32).or_default();
+        bucket.0 += 1;
+        bucket.1 += gain;
+    }
+
+    /// Returns the true count at which the deviation starts to gain,
+    /// from a least squares fit of the gain against the true count
+    ///
+    /// Returns [None] if the gain does not rise with the count
+    pub fn index(&self) -> Option<f32> {
+        let denominator = self.n * self.sum_xx - self.sum_x * self.sum_x;
+
+        if denominator <= 0.0 {
+            return None;
+        }
+
+        let slope = (self.n * self.sum_xy - self.sum_x * self.sum_y) / denominator;
+        let intercept = (self.sum_y - slope * self.sum_x) / self.n;
+
+        (slope > 0.0).then(|| (-intercept / slope) as f32)
+    }
+
+    /// Returns the number of hands and average gain at each true count
+    pub fn buckets(&self) -> impl Iterator<Item = (i32, u64, f64)> + '_ {
+        self.buckets.iter().map(|(tc, (n, total))| (*tc, *n, total / *n as f64))
+    }
+}
+
+/// Runs the simulated hands for each play, using seeded shoes
+pub struct IndexGenerator {
+    rules: Rules,
+    system: CountingSystem,
+    shoe: Deck,
+    rng: StdRng,
+}
+
+impl IndexGenerator {
+    pub fn new(rules: Rules, system: CountingSystem, seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Self { rules, system, shoe: Deck::new(rules.decks), rng }
+    }
+
+    /// Simulate `trials` hands of the play, each from a freshly
+    /// shuffled shoe dealt to a random depth
+    pub fn simulate(&mut self, play: &IndexPlay, trials: u64) -> PlayGain {
+        let mut gain = PlayGain::default();
+
+        for _ in 0..trials {
+            if let Some((true_count, g)) = self.trial(play) {
+                gain.record(true_count, g);
+            }
+        }
+
+        gain
+    }
+
+    /// Play a single hand both ways, returning the true count
+    /// and the gain of the deviation
+    ///
+    /// Returns [None] if the hand could not be dealt, or the dealer
+    /// peeked and had a natural so there was no decision to make
+    fn trial(&mut self, play: &IndexPlay) -> Option<(f32, f64)> {
+        let mut deck = self.shoe.clone();
+        deck.shuffle_with(&mut self.rng);
+
+        let total = deck.remaining();
+        let burn = self.rng.gen_range(0..=(total as f64 * self.rules.penetration) as usize);
+        let mut seen: Vec<Card> = (0..burn).filter_map(|_| deck.draw()).collect();
+
+        let values = match play.hand {
+            PlayerHand::Insurance => {
+                let cards = [deck.draw()?, deck.draw()?];
+                seen.extend(cards);
+
+                Vec::new()
+            },
+            hand => self.player_values(hand),
+        };
+
+        let mut cards = Vec::with_capacity(2);
+
+        for value in values {
+            cards.push(deck.take(value)?);
+        }
+
+        let upcard = deck.take(if play.upcard == 11 { 1 } else { play.upcard })?;
+
+        seen.extend(cards.iter().copied());
+        seen.push(upcard);
+
+        let true_count = self.system.count(&seen).true_count(deck.remaining());
+        let hole = deck.draw()?;
+
+        if play.hand == PlayerHand::Insurance {
+            return Some((true_count, if u8::from(hole) == 10 { 2.0 } else { -1.0 }));
+        }
+
+        let dealer_natural = Hand::new(vec![upcard, hole]).is_natural();
+
+        if dealer_natural && self.rules.dealer_peeks {
+            return None;
+        }
+
+        let deviation = play.at_or_above?;
+        let usual = match play.below {
+            Some(action) => action,
+            None => {
+                let available = AvailableActions {
+                    double: deviation != Action::Double,
+                    split: deviation != Action::Split,
+                    surrender: deviation != Action::Surrender,
+                };
+
+                basic_strategy(&Hand::new(cards.clone()), &upcard, available, &self.rules)
+            },
+        };
+
+        let with = play_out(&mut deck.clone(), cards.clone(), upcard, hole, deviation, &self.rules)?;
+        let without = play_out(&mut deck, cards, upcard, hole, usual, &self.rules)?;
+
+        Some((true_count, with - without))
+    }
+
+    /// Returns the values of two cards making up the hand,
+    /// picking at random between the ways of making a hard total
+    fn player_values(&mut self, hand: PlayerHand) -> Vec<u8> {
+        match hand {
+            PlayerHand::Hard(total) => {
+                let ways: Vec<u8> = (2..=10)
+                    .filter(|x| total > *x && (x + 1..=10).contains(&(total - x)))
+                    .collect();
+
+                match ways.len() {
+                    0 => vec![total / 2, total - total / 2],
+                    n => {
+                        let x = ways[self.rng.gen_range(0..n)];
+                        vec![x, total - x]
+                    },
+                }
+            },
+            PlayerHand::Soft(total) => vec![1, total.saturating_sub(11).max(1)],
+            PlayerHand::Pair(value) => vec![value, value],
+            PlayerHand::Insurance => Vec::new(),
+        }
+    }
+}
+
+/// Draw cards into the hand while basic strategy says to hit
+fn play_basic(hand: &mut Hand, upcard: &Card, deck: &mut Deck, rules: &Rules) -> Option<()> {
+    while hand.total_value() < 21 && basic_strategy(hand, upcard, AvailableActions::default(), rules) == Action::Hit {
+        hand.push(deck.draw()?);
+    }
+
+    Some(())
+}
+
+/// Take the action on the player's hand, play the rest of the round
+/// with basic strategy, and return the player's net result in units
+fn play_out(deck: &mut Deck, cards: Vec<Card>, upcard: Card, hole: Card, action: Action, rules: &Rules) -> Option<f64> {
+    let hands: Vec<(Hand, f64)> = match action {
+        Action::Surrender => return Some(-0.5),
+        Action::Stand => vec![(Hand::new(cards), 1.0)],
+        Action::Hit => {
+            let mut hand = Hand::new(cards);
+            hand.push(deck.draw()?);
+            play_basic(&mut hand, &upcard, deck, rules)?;
+
+            vec![(hand, 1.0)]
+        },
+        Action::Double => {
+            let mut hand = Hand::new(cards);
+            hand.push(deck.draw()?);
+
+            vec![(hand, 2.0)]
+        },
+        Action::Split => {
+            let mut hands = Vec::with_capacity(2);
+
+            for card in cards {
+                let mut hand = Hand::new(vec![card, deck.draw()?]);
+
+                // Split aces only receive one card each
+                if u8::from(card) != 1 {
+                    play_basic(&mut hand, &upcard, deck, rules)?;
+                }
+
+                hands.push((hand, 1.0));
+            }
+
+            hands
+        },
+    };
+
+    let staked: f64 = hands.iter().map(|(_, stake)| stake).sum();
+    let mut dealer = Hand::new(vec![upcard, hole]);
+
+    if dealer.is_natural() {
+        return Some(-staked);
+    }
+
+    if hands.iter().all(|(hand, _)| hand.total_value() > 21) {
+        return Some(-staked);
+    }
+
+    loop {
+        let value = dealer.total_value();
+
+        if value > 17 || (value == 17 && !(rules.dealer_hits_soft_17 && dealer.is_soft())) {
+            break;
+        }
+
+        dealer.push(deck.draw()?);
+    }
+
+    let dealer_value = dealer.total_value();
+
+    let net = hands.iter().map(|(hand, stake)| {
+        let value = hand.total_value();
+
+        if value > 21 {
+            -stake
+        } else if dealer_value > 21 || value > dealer_value {
+            *stake
+        } else if value == dealer_value {
+            0.0
+        } else {
+            -stake
+        }
+    }).sum();
+
+    Some(net)
+}
+
+/// Simulate every play and write out an index table
+/// with the true count at which each one becomes profitable
+pub fn run_index_generator(cfg: IndexConfig) {
+    let rules = Rules::from(cfg.rules);
+    let plays = cfg.plays.unwrap_or_else(IndexTable::hi_lo);
+    let mut generator = IndexGenerator::new(rules, cfg.system, cfg.seed);
+
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# Indices for {} deck{}, {}, {}, from {} hands per play",
+        rules.decks,
+        if rules.decks == 1 { "" } else { "s" },
+        if rules.dealer_hits_soft_17 { "H17" } else { "S17" },
+        if rules.dealer_peeks { "dealer peeks" } else { "no peek" },
+        cfg.trials,
+    );
+
+    for play in plays.plays() {
+        let gain = generator.simulate(play, cfg.trials);
+
+        match gain.index() {
+            Some(index) => {
+                let _ = writeln!(out, "# Gain is zero at a true count of {index:+.2}");
+                // Adding zero turns a rounded -0 into 0
+                let _ = writeln!(out, "{}", IndexPlay { index: index.round() + 0.0, ..*play });
+            },
+            None => {
+                let _ = writeln!(out, "# No index found, the gain does not rise with the count");
+                let _ = writeln!(out, "# {play}");
+            },
+        }
+
+        if cfg.verbose {
+            for (true_count, n, average) in gain.buckets() {
+                let _ = writeln!(out, "#   {true_count:+3}: {average:+.4} units over {n} hands");
+            }
+        }
+    }
+
+    match cfg.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, out) {
+                eprintln!("Failed to write {}: {e}", path.display());
+            }
+        },
+        None => print!("{out}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blackjack::count::CountingSystem;
+    use blackjack::deviations::{IndexPlay, PlayerHand};
+    use blackjack::rules::Rules;
+    use blackjack::strategy::Action;
+
+    use super::{IndexGenerator, PlayGain};
+
+    #[test]
+    fn fit_finds_the_crossing() {
+        let mut gain = PlayGain::default();
+
+        for tc in -5..=5 {
+            let tc = tc as f32;
+            gain.record(tc, f64::from(tc - 2.0) * 0.01);
+        }
+
+        assert!((gain.index().unwrap() - 2.0).abs() < 1e-4);
+        assert_eq!(gain.buckets().count(), 11);
+
+        let mut falling = PlayGain::default();
+        falling.record(-1.0, 0.1);
+        falling.record(1.0, -0.1);
+
+        assert_eq!(falling.index(), None);
+    }
+
+    #[test]
+    fn insurance_index_is_near_three() {
+        let play = IndexPlay {
+            hand: PlayerHand::Insurance,
+            upcard: 11,
+            index: 0.0,
+            at_or_above: Some(Action::Stand),
+            below: None,
+        };
+
+        let mut generator = IndexGenerator::new(Rules::default(), CountingSystem::hi_lo(), Some(11));
+        let index = generator.simulate(&play, 40_000).index().unwrap();
+
+        assert!(index > 1.5 && index < 4.5, "insurance index {index}");
+    }
+}
@@ -0,0 +1,495 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{DeviationArgs, RuleArgs};
+use blackjack::count::RunningCount;
+use blackjack::events::{TableEvent, TableObserver};
+use blackjack::deviations::IndexTable;
+use blackjack::hand::Hand;
+use blackjack::cards::Card;
+use blackjack::round::{
+    BlackjackTable,
+    BlackjackRoundError,
+    GameOver,
+    GameResult,
+    GameStartResult,
+    NotStarted,
+    PlayerTurn,
+    PlayerTurnResult,
+    DealerTurnResult
+};
+use blackjack::rules::Rules;
+use blackjack::strategy::{basic_strategy, Action, AvailableActions};
+
+use clap::{Args, ValueEnum};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::Serialize;
+
+mod spread;
+pub mod bankroll;
+pub mod indices;
+
+pub use spread::BetSpread;
+
+/// z-score used for the reported 95% confidence intervals
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// How an automated player decides to hit or stand
+///
+/// The simulated tables cannot double, split or surrender,
+/// so every strategy only hits and stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    /// Basic strategy for the table's rules, hitting or standing
+    /// where it would double, split or surrender
+    Basic,
+    /// Play the same way as the dealer, hitting below 17
+    MimicDealer,
+    /// Never hit a hand that could go bust
+    NeverBust,
+}
+
+impl Strategy {
+    /// Returns true if the player should draw another card
+    pub fn wants_hit(self, hand: &Hand, upcard: &Card, rules: &Rules) -> bool {
+        match self {
+            Strategy::Basic => {
+                basic_strategy(hand, upcard, AvailableActions::default(), rules) == Action::Hit
+            },
+            Strategy::MimicDealer => hand.total_value() < 17,
+            Strategy::NeverBust => hand.total_value() < 12,
+        }
+    }
+
+    /// Describes the strategy in reports
+    pub fn label(self) -> &'static str {
+        match self {
+            Strategy::Basic => "basic strategy, hit/stand only",
+            Strategy::MimicDealer => "mimic the dealer",
+            Strategy::NeverBust => "never bust",
+        }
+    }
+}
+
+#[derive(Args, Clone)]
+pub struct SimulationConfig {
+
+    /// Number of rounds to play
+    #[arg(short, long, default_value_t = 1_000_000)]
+    rounds: u64,
+
+    /// The strategy played by the simulated player
+    #[arg(short, long, value_enum, default_value_t = Strategy::Basic)]
+    strategy: Strategy,
+
+    /// Units bet at each true count, e.g. `1:1,2:2,3:4,4:8`.
+    /// A single number bets that many units flat
+    #[arg(long, default_value = "1")]
+    spread: BetSpread,
+
+    /// Seed for shuffling, so that a simulation can be repeated exactly
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Print the report as JSON instead of text
+    #[arg(long, action = clap::ArgAction::SetTrue)]
+    json: bool,
+
+    #[command(flatten)]
+    deviations: DeviationArgs,
+
+    #[command(flatten)]
+    rules: RuleArgs,
+}
+
+/// The outcome of a single simulated round
+#[derive(Debug, Clone, Copy)]
+pub struct RoundOutcome {
+    /// Units wagered on the round
+    pub bet: f64,
+    /// Units won (or lost, if negative) on the round
+    pub net: f64,
+    pub result: GameResult,
+    pub player_natural: bool,
+}
+
+/// Plays rounds headlessly on a single [BlackjackTable],
+/// keeping the shoe and the running count between rounds
+pub struct Simulator {
+    table: Option<BlackjackTable<NotStarted>>,
+    rules: Rules,
+    strategy: Strategy,
+    indices: Option<IndexTable>,
+    spread: BetSpread,
+    /// Kept up to date by the table as cards are shown
+    count: Arc<Mutex<RunningCount>>,
+    rng: StdRng,
+}
+
+impl Simulator {
+    pub fn new(rules: Rules, strategy: Strategy, spread: BetSpread, seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut sim = Self {
+            table: None,
+            rules,
+            strategy,
+            indices: None,
+            spread,
+            count: Arc::default(),
+            rng,
+        };
+
+        sim.new_shoe();
+
+        sim
+    }
+
+    /// Deviate from basic strategy using the index plays in the table.
+    ///
+    /// Index plays only adjust [Strategy::Basic]
+    pub fn with_indices(mut self, indices: Option<IndexTable>) -> Self {
+        self.indices = indices;
+
+        self
+    }
+
+    /// Returns true if the player should draw another card,
+    /// applying any index plays at the table's current true count
+    fn wants_hit(&self, table: &BlackjackTable<PlayerTurn>, upcard: &Card) -> bool {
+        match (&self.indices, self.strategy) {
+            (Some(indices), Strategy::Basic) => {
+                let true_count = table.running_count().true_count(table.cards_remaining());
+                let action = indices.decide(table.player_hand(), upcard, true_count, AvailableActions::default(), table.rules());
+
+                action == Action::Hit
+            },
+            _ => self.strategy.wants_hit(table.player_hand(), upcard, table.rules()),
+        }
+    }
+
+    /// Replace the table with one using a freshly shuffled shoe
+    fn new_shoe(&mut self) {
+        let mut table = BlackjackTable::with_rules(self.rules);
+        let count = self.count.clone();
+
+        table.subscribe(move |event: &TableEvent| count.lock().unwrap().on_event(event));
+
+        self.table = Some(table.reshuffle_with(&mut self.rng));
+    }
+
+    /// Play a single round, reshuffling first if the shoe
+    /// has been dealt past its penetration
+    ///
+    /// # Panics
+    ///
+    /// If the simulator's [Rules] cannot be played, see [Rules::validate]
+    pub fn play_round(&mut self) -> RoundOutcome {
+        loop {
+            let mut table = match self.table.take() {
+                Some(t) => t,
+                None => {
+                    self.new_shoe();
+                    continue;
+                }
+            };
+
+            if table.needs_reshuffle() {
+                table = table.reshuffle_with(&mut self.rng);
+            }
+
+            let true_count = self.count.lock().unwrap().true_count(table.cards_remaining());
+            let bet = self.spread.bet(true_count);
+
+            match self.play_table(table) {
+                Ok(finished) => {
+                    let outcome = RoundOutcome {
+                        bet,
+                        net: bet * finished.payout(),
+                        result: *finished.result(),
+                        player_natural: finished.player_hand().is_natural(),
+                    };
+
+                    self.table = Some(finished.next_round());
+
+                    return outcome;
+                },
+
+                // The shoe and the discards ran out part way through the round,
+                // so the round is abandoned and replayed with a new shoe
+                Err(BlackjackRoundError::DeckEmpty) => self.new_shoe(),
+                Err(e) => panic!("The simulated round could not be played: {e}"),
+            }
+        }
+    }
+
+    fn play_table(&self, table: BlackjackTable<NotStarted>) -> Result<BlackjackTable<GameOver>, BlackjackRoundError> {
+        let mut players_turn = match table.deal()? {
+            GameStartResult::Natural(t) => return Ok(t),
+            GameStartResult::Normal(t) => t,
+        };
+
+        let mut dealers_turn = loop {
+            let upcard = *players_turn.showing_card().ok_or(BlackjackRoundError::DeckEmpty)?;
+
+            if !self.wants_hit(&players_turn, &upcard) {
+                break players_turn.stand();
+            }
+
+            players_turn = match players_turn.hit()? {
+                PlayerTurnResult::Hit(t) => t,
+                PlayerTurnResult::Bust(t) => return Ok(t),
+            };
+        };
+
+        loop {
+            dealers_turn = match dealers_turn.hit()? {
+                DealerTurnResult::Hit(t) => t,
+                DealerTurnResult::Stand(t) => return Ok(t),
+            };
+        }
+    }
+}
+
+/// A proportion together with its confidence interval
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Estimate {
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Estimate {
+    fn new(value: f64, standard_error: f64) -> Self {
+        Self {
+            value,
+            low: value - CONFIDENCE_Z * standard_error,
+            high: value + CONFIDENCE_Z * standard_error,
+        }
+    }
+
+    /// Estimate of a rate from the number of times something happened
+    fn rate(hits: u64, n: u64) -> Self {
+        let p = hits as f64 / n.max(1) as f64;
+
+        Self::new(p, (p * (1.0 - p) / n.max(1) as f64).sqrt())
+    }
+}
+
+/// Running totals accumulated over simulated rounds
+#[derive(Debug, Default, Clone)]
+pub struct SimulationStats {
+    rounds: u64,
+    wagered: f64,
+    net: f64,
+    net_squared: f64,
+    wins: u64,
+    losses: u64,
+    pushes: u64,
+    blackjacks: u64,
+    player_busts: u64,
+    dealer_busts: u64,
+}
+
+impl SimulationStats {
+    pub fn record(&mut self, outcome: &RoundOutcome) {
+        self.rounds += 1;
+        self.wagered += outcome.bet;
+        self.net += outcome.net;
+        self.net_squared += outcome.net * outcome.net;
+
+        if outcome.player_natural {
+            self.blackjacks += 1;
+        }
+
+        match outcome.result {
+            GameResult::PlayerWin => self.wins += 1,
+            GameResult::DealerBust => {
+                self.wins += 1;
+                self.dealer_busts += 1;
+            },
+            GameResult::DealerWin => self.losses += 1,
+            GameResult::PlayerBust => {
+                self.losses += 1;
+                self.player_busts += 1;
+            },
+            GameResult::StandOff => self.pushes += 1,
+        }
+    }
+
+    /// Mean result of a round, in units
+    pub fn mean(&self) -> f64 {
+        self.net / self.rounds.max(1) as f64
+    }
+
+    /// Standard deviation of the result of a round, in units
+    pub fn standard_deviation(&self) -> f64 {
+        let n = self.rounds.max(1) as f64;
+
+        (self.net_squared / n - self.mean().powi(2)).max(0.0).sqrt()
+    }
+
+    /// Average number of units wagered each round
+    pub fn average_bet(&self) -> f64 {
+        self.wagered / self.rounds.max(1) as f64
+    }
+
+    /// The house edge as a fraction of the total amount wagered
+    pub fn house_edge(&self) -> Estimate {
+        let standard_error = self.standard_deviation()
+            / (self.rounds.max(1) as f64).sqrt()
+            / self.average_bet().max(f64::EPSILON);
+
+        Estimate::new(-self.net / self.wagered.max(f64::EPSILON), standard_error)
+    }
+
+    /// Summarise the rounds, which were played with the supplied strategy
+    pub fn report(&self, strategy: Strategy) -> SimulationReport {
+        SimulationReport {
+            strategy: strategy.label(),
+            rounds: self.rounds,
+            units_wagered: self.wagered,
+            net_units: self.net,
+            house_edge: self.house_edge(),
+            standard_deviation: self.standard_deviation(),
+            average_bet: self.average_bet(),
+            win_rate: Estimate::rate(self.wins, self.rounds),
+            loss_rate: Estimate::rate(self.losses, self.rounds),
+            push_rate: Estimate::rate(self.pushes, self.rounds),
+            blackjack_rate: Estimate::rate(self.blackjacks, self.rounds),
+            player_bust_rate: Estimate::rate(self.player_busts, self.rounds),
+            dealer_bust_rate: Estimate::rate(self.dealer_busts, self.rounds),
+        }
+    }
+}
+
+/// Summary of a simulation, with 95% confidence intervals
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationReport {
+    pub strategy: &'static str,
+    pub rounds: u64,
+    pub units_wagered: f64,
+    pub net_units: f64,
+    pub house_edge: Estimate,
+    pub standard_deviation: f64,
+    pub average_bet: f64,
+    pub win_rate: Estimate,
+    pub loss_rate: Estimate,
+    pub push_rate: Estimate,
+    pub blackjack_rate: Estimate,
+    pub player_bust_rate: Estimate,
+    pub dealer_bust_rate: Estimate,
+}
+
+impl std::fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pct = |e: &Estimate| format!(
+            "{:>8.3}%  (95% CI {:.3}% to {:.3}%)",
+            e.value * 100.0,
+            e.low * 100.0,
+            e.high * 100.0
+        );
+
+        writeln!(f, "Strategy:           {}", self.strategy)?;
+        writeln!(f, "Rounds played:      {}", self.rounds)?;
+        writeln!(f, "Units wagered:      {:.1}", self.units_wagered)?;
+        writeln!(f, "Net result:         {:.1} units", self.net_units)?;
+        writeln!(f, "Average bet:        {:.3} units", self.average_bet)?;
+        writeln!(f, "Standard deviation: {:.3} units per round", self.standard_deviation)?;
+        writeln!(f, "House edge:         {}", pct(&self.house_edge))?;
+        writeln!(f, "Win rate:           {}", pct(&self.win_rate))?;
+        writeln!(f, "Loss rate:          {}", pct(&self.loss_rate))?;
+        writeln!(f, "Push rate:          {}", pct(&self.push_rate))?;
+        writeln!(f, "Blackjack rate:     {}", pct(&self.blackjack_rate))?;
+        writeln!(f, "Player bust rate:   {}", pct(&self.player_bust_rate))?;
+        write!(f, "Dealer bust rate:   {}", pct(&self.dealer_bust_rate))
+    }
+}
+
+/// Run a headless simulation and print the report to stdout
+pub fn run_simulation(sim_cfg: SimulationConfig) {
+    let mut sim = Simulator::new(sim_cfg.rules.into(), sim_cfg.strategy, sim_cfg.spread, sim_cfg.seed)
+        .with_indices(sim_cfg.deviations.index_table());
+    let mut stats = SimulationStats::default();
+
+    for _ in 0..sim_cfg.rounds {
+        stats.record(&sim.play_round());
+    }
+
+    let report = stats.report(sim_cfg.strategy);
+
+    if sim_cfg.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(e) => eprintln!("Failed to serialize the simulation report: {e}"),
+        }
+    } else {
+        println!("{report}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blackjack::deviations::IndexTable;
+    use blackjack::rules::Rules;
+
+    use super::{BetSpread, SimulationStats, Simulator, Strategy};
+
+    #[test]
+    fn seeded_simulations_repeat() {
+        let run = || {
+            let mut sim = Simulator::new(Rules::default(), Strategy::Basic, BetSpread::flat(), Some(7));
+            let mut stats = SimulationStats::default();
+
+            for _ in 0..2_000 {
+                stats.record(&sim.play_round());
+            }
+
+            stats.report(Strategy::Basic)
+        };
+
+        let (a, b) = (run(), run());
+
+        assert_eq!(a.rounds, 2_000);
+        assert_eq!(a.net_units, b.net_units);
+        assert_eq!(a.win_rate.value, b.win_rate.value);
+    }
+
+    #[test]
+    fn basic_strategy_edge_is_plausible() {
+        let mut sim = Simulator::new(Rules::default(), Strategy::Basic, BetSpread::flat(), Some(1));
+        let mut stats = SimulationStats::default();
+
+        for _ in 0..200_000 {
+            stats.record(&sim.play_round());
+        }
+
+        let report = stats.report(Strategy::Basic);
+        let rates = report.win_rate.value + report.loss_rate.value + report.push_rate.value;
+
+        assert!((rates - 1.0).abs() < 1e-9);
+        assert!(report.to_string().starts_with("Strategy:           basic strategy, hit/stand only\n"));
+        assert!(report.house_edge.value > -0.02 && report.house_edge.value < 0.06);
+        assert!(report.blackjack_rate.value > 0.04 && report.blackjack_rate.value < 0.05);
+    }
+
+    #[test]
+    fn index_plays_change_the_result() {
+        let run = |indices: Option<IndexTable>| {
+            let mut sim = Simulator::new(Rules::default(), Strategy::Basic, BetSpread::flat(), Some(5))
+                .with_indices(indices);
+            let mut stats = SimulationStats::default();
+
+            for _ in 0..20_000 {
+                stats.record(&sim.play_round());
+            }
+
+            stats.report(Strategy::Basic).net_units
+        };
+
+        assert_ne!(run(None), run(Some(IndexTable::hi_lo())));
+    }
+}
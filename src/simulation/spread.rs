@@ -0,0 +1,107 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// A betting strategy keyed to the true count
+///
+/// Each step pairs a minimum true count with the number of units to bet
+/// once the true count reaches it. Below the lowest step, the
+/// lowest step's bet is used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BetSpread(Vec<(i32, f64)>);
+
+impl BetSpread {
+    /// A spread that always bets a single unit
+    pub fn flat() -> Self {
+        Self(vec![(i32::MIN, 1.0)])
+    }
+
+    /// Returns the number of units to bet at the supplied true count
+    pub fn bet(&self, true_count: f32) -> f64 {
+        self.0.iter()
+        .rev()
+        .find(|(threshold, _)| *threshold as f32 <= true_count)
+        .or(self.0.first())
+        .map(|(_, units)| *units)
+        .unwrap_or(1.0)
+    }
+}
+
+impl Default for BetSpread {
+    fn default() -> Self {
+        Self::flat()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BetSpreadError(String);
+
+impl Display for BetSpreadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid bet spread step '{}', expected <true count>:<units> with a positive number of units", self.0)
+    }
+}
+
+impl std::error::Error for BetSpreadError {}
+
+impl FromStr for BetSpread {
+    type Err = BetSpreadError;
+
+    /// Parse a spread such as `1:1,2:2,3:4,4:8`.
+    ///
+    /// A single number without a true count bets that many units flat
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(units) = s.trim().parse::<f64>() {
+            if !units.is_finite() || units <= 0.0 {
+                return Err(BetSpreadError(s.to_string()));
+            }
+
+            return Ok(Self(vec![(i32::MIN, units)]));
+        }
+
+        let mut steps = s.split(',')
+        .map(|step| {
+            let err = || BetSpreadError(step.to_string());
+
+            let (count, units) = step.split_once(':').ok_or_else(err)?;
+
+            let count = count.trim().parse::<i32>().map_err(|_| err())?;
+            let units = units.trim().parse::<f64>().map_err(|_| err())?;
+
+            if !units.is_finite() || units <= 0.0 {
+                return Err(err());
+            }
+
+            Ok((count, units))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+        steps.sort_by_key(|(count, _)| *count);
+
+        Ok(Self(steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BetSpread;
+
+    #[test]
+    fn parse_and_bet() {
+        let spread: BetSpread = "3:4,1:1,2:2,4:8".parse().unwrap();
+
+        assert_eq!(spread.bet(-2.0), 1.0);
+        assert_eq!(spread.bet(1.5), 1.0);
+        assert_eq!(spread.bet(2.0), 2.0);
+        assert_eq!(spread.bet(3.9), 4.0);
+        assert_eq!(spread.bet(12.0), 8.0);
+
+        let flat: BetSpread = "5".parse().unwrap();
+
+        assert_eq!(flat.bet(10.0), 5.0);
+        assert!("2:x".parse::<BetSpread>().is_err());
+
+        for invalid in ["2:0", "2:-1", "2:nan", "2:inf", "0", "-3", "NaN", "inf"] {
+            assert!(invalid.parse::<BetSpread>().is_err(), "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use blackjack::lifetime::{LifetimeStats, RoundCounts};
+
+/// Print the statistics of every round played across sessions
+pub fn print_stats() {
+    let Some(path) = crate::files::stats_path() else {
+        eprintln!("No data directory was found to keep statistics in");
+        return;
+    };
+
+    let stats = match LifetimeStats::load(&path) {
+        Ok(stats) => stats,
+        Err(e) => {
+            eprintln!("Unable to read the statistics in {}: {e}", path.display());
+            return;
+        },
+    };
+
+    if stats.hands == 0 {
+        println!("No hands have been played yet");
+        return;
+    }
+
+    let percent = |n: u64| n as f64 / stats.hands as f64 * 100.0;
+    let results = &stats.results;
+
+    println!("Hands played:        {}", stats.hands);
+    println!("Net result:          {:+.1} units ({:+.4} per hand)", stats.net, stats.net / stats.hands as f64);
+    println!("Wins:                {} ({:.2}%)", results.wins(), percent(results.wins()));
+    println!("  Player wins:       {}", results.player_win);
+    println!("  Dealer busts:      {}", results.dealer_bust);
+    println!("Losses:              {} ({:.2}%)", results.losses(), percent(results.losses()));
+    println!("  Dealer wins:       {}", results.dealer_win);
+    println!("  Player busts:      {}", results.player_bust);
+    println!("Pushes:              {} ({:.2}%)", results.pushes(), percent(results.pushes()));
+    println!("Blackjacks:          {} ({:.2}%)", stats.blackjacks, percent(stats.blackjacks));
+    println!("Longest win streak:  {}", stats.longest_win_streak);
+    println!("Longest loss streak: {}", stats.longest_loss_streak);
+
+    println!();
+    print_breakdown("Upcard", &stats.by_upcard(), |upcard| match upcard {
+        1 => "A".to_string(),
+        v => v.to_string(),
+    });
+
+    println!();
+    print_breakdown("Start", &stats.by_starting_total(), |total| total.to_string());
+}
+
+fn print_breakdown(heading: &str, rows: &BTreeMap<u8, RoundCounts>, label: impl Fn(u8) -> String) {
+    println!("{heading:<8}{:>8}{:>8}{:>8}{:>8}{:>10}", "Hands", "Win", "Loss", "Push", "Net/hand");
+
+    // Aces are listed after the tens, as in the usual order of upcards
+    let mut rows: Vec<_> = rows.iter().collect();
+    rows.sort_by_key(|(key, _)| (**key == 1, **key));
+
+    for (key, counts) in rows {
+        let percent = |n: u64| n as f64 / counts.hands as f64 * 100.0;
+
+        println!(
+            "{:<8}{:>8}{:>7.1}%{:>7.1}%{:>7.1}%{:>+10.3}",
+            label(*key),
+            counts.hands,
+            counts.win_rate().unwrap_or_default() * 100.0,
+            percent(counts.losses),
+            percent(counts.pushes),
+            counts.net / counts.hands as f64,
+        );
+    }
+}
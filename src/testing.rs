@@ -0,0 +1,14 @@
+use blackjack::cards::Card;
+use blackjack::deck::Deck;
+use blackjack::round::{BlackjackTable, NotStarted};
+use blackjack::rules::Rules;
+
+/// The named cards, in order
+pub fn cards(names: &[&str]) -> Vec<Card> {
+    names.iter().map(|c| c.parse().unwrap()).collect()
+}
+
+/// A table with the default rules that deals the named cards in order
+pub fn stacked(names: &[&str]) -> BlackjackTable<NotStarted> {
+    BlackjackTable::with_deck(Rules::default(), Deck::stacked(cards(names)))
+}
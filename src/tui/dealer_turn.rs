@@ -1,9 +1,9 @@
-use super::{set_message, game_states};
+use super::{apply_action, set_message, game_states};
 
 use super::update_hands::update_dealer_hand;
 
 use crate::Config;
-use crate::game_rules::round::{DealerTurnResult, DealerTurn, BlackjackTable};
+use blackjack::game::{AnyTable, TableAction};
 
 use std::sync::mpsc;
 use std::time::Duration;
@@ -38,22 +38,22 @@ pub(crate) fn run_dealer_turn(s: &mut Cursive, cfg: Config) {
             let shutdown_tx = tx.clone();
 
             cb_sink.send(Box::new(move |s| {
-                if let Some(table) = s.take_user_data::<BlackjackTable<DealerTurn>>() {
-                    match table.hit() {
-                        Ok(DealerTurnResult::Hit(t)) => {
-                            s.set_user_data(t);
-                            update_dealer_hand(s, cfg.ascii);
-                        },
-                        Ok(DealerTurnResult::Stand(t)) => {
-                            shutdown_tx.send(()).unwrap();
-
-                            s.set_user_data(t);
-                            update_dealer_hand(s, cfg.ascii);
-
-                            game_states::end_game(s, cfg);
-                        }
-                        Err(_) => todo!(),
-                    }
+                // The turn may already have been ended by an earlier callback
+                if !matches!(s.user_data::<AnyTable>(), Some(AnyTable::DealerTurn(_))) {
+                    return;
+                }
+
+                if !apply_action(s, TableAction::DealerPlay) {
+                    shutdown_tx.send(()).unwrap();
+                    return;
+                }
+
+                update_dealer_hand(s, cfg.ascii);
+
+                if matches!(s.user_data::<AnyTable>(), Some(AnyTable::GameOver(_))) {
+                    shutdown_tx.send(()).unwrap();
+
+                    game_states::end_game(s, cfg);
                 }
             })).unwrap();
         }
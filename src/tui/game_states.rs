@@ -1,11 +1,14 @@
 use super::update_hands::{update_dealer_hand, update_player_hand};
-use super::{stand_callback, hit_callback, error_popup, set_message};
+use super::{stand_callback, hit_callback, apply_action, error_popup, set_message};
 
-use crate::game_rules::round::{GameResult, GameOver, GameStartResult, NotStarted};
+use blackjack::game::{AnyTable, TableAction};
+use blackjack::round::GameResult;
+use blackjack::deviations::IndexTable;
+use blackjack::ev::action_values;
+use crate::files;
+use blackjack::strategy::{basic_strategy, AvailableActions};
 
-use cursive::views::Dialog;
-
-use crate::game_rules::round::BlackjackTable;
+use cursive::views::{Dialog, TextView};
 
 use crate::Config;
 
@@ -13,15 +16,39 @@ use cursive::Cursive;
 
 /// Start a new game and prompt the player to deal
 /// a new round
+///
+/// The shoe is kept from the previous round, so that the
+/// count carries over, until it needs to be reshuffled
 pub(crate) fn init_round(s: &mut Cursive, cfg: Config) {
-    let table = BlackjackTable::default();
-
-    s.set_user_data(table);
+    let table = match s.take_user_data::<AnyTable>() {
+        Some(AnyTable::GameOver(finished)) => finished.next_round(),
+        Some(AnyTable::NotStarted(table)) => table,
+        Some(table) => {
+            // Starting a new round would throw away the one being played
+            s.set_user_data(table);
+            error_popup(s, "Invalid game state: the round is not over");
+            return;
+        },
+        None => {
+            error_popup(s, "Invalid game state");
+            return;
+        },
+    };
+
+    let reshuffled = table.needs_reshuffle();
+    let table = if reshuffled { table.reshuffle() } else { table };
+    let bankroll = table.session().bankroll;
+
+    s.set_user_data(AnyTable::from(table));
 
     update_dealer_hand(s, cfg.ascii);
     update_player_hand(s, cfg.ascii);
 
-    set_message(s, "Press q any time to quit");
+    if reshuffled {
+        set_message(s, format!("The shoe has been reshuffled\nBankroll: {bankroll:.1} units\nPress s for statistics, or q any time to quit and save"));
+    } else {
+        set_message(s, format!("Bankroll: {bankroll:.1} units\nPress s for statistics, or q any time to quit and save"));
+    }
 
     s.call_on_name("game_dialog", move |d: &mut Dialog| {
         d.clear_buttons();
@@ -30,26 +57,16 @@ pub(crate) fn init_round(s: &mut Cursive, cfg: Config) {
 
 }
 
-/// Shuffle the deck, deal, and begin the player's turn
+/// Deal, and begin the player's turn
 pub(crate) fn deal_round(s: &mut Cursive, cfg: Config) {
-    if let Some(table) = s.take_user_data::<BlackjackTable<NotStarted>>() {
-
-        match table.shuffle().deal() {
-            Ok(GameStartResult::Natural(t)) => {
-                s.set_user_data(t);
-
-                end_game(s, cfg);
-            },
-            Ok(GameStartResult::Normal(t)) => {
-                s.set_user_data(t);
-
-                start_player_turn(s, cfg);
-            },
-            Err(_) => error_popup(s, "Unable to deal"),
-        }
+    if !apply_action(s, TableAction::Deal) {
+        return;
+    }
 
+    if matches!(s.user_data::<AnyTable>(), Some(AnyTable::GameOver(_))) {
+        end_game(s, cfg);
     } else {
-        error_popup(s, "Invalid game state");
+        start_player_turn(s, cfg);
     }
 }
 
@@ -59,27 +76,86 @@ pub(crate) fn start_player_turn(s: &mut Cursive, cfg: Config) {
     update_dealer_hand(s, cfg.ascii);
     update_player_hand(s, cfg.ascii);
 
-    set_message(s, "It's your turn!");
+    let mut hint = None;
+
+    let values = match s.user_data::<AnyTable>() {
+        Some(AnyTable::PlayerTurn(table)) => table.showing_card().and_then(|upcard| {
+            // The table can only hit or stand, so the hints and
+            // the best play are limited to those two
+            let available = AvailableActions::default();
+
+            if cfg.hints {
+                let true_count = table.running_count().true_count(table.cards_remaining());
+                let play = IndexTable::hi_lo().decide(table.player_hand(), upcard, true_count, available, table.rules());
+                let basic = basic_strategy(table.player_hand(), upcard, available, table.rules());
+
+                hint = Some(if play == basic {
+                    format!("True count {true_count:+.1}: {play:?}")
+                } else {
+                    format!("True count {true_count:+.1}: {play:?} (index play, basic strategy is {basic:?})")
+                });
+            }
+
+            cfg.show_ev.then(|| {
+                let ev = action_values(&table.unseen_cards(), table.player_hand(), upcard, table.rules());
+
+                (ev, ev.best(available))
+            })
+        }),
+        _ => None,
+    };
+
+    let (mut msg, hit_label, stand_label) = match values {
+        Some((ev, (best, best_ev))) => (
+            format!("It's your turn! Best play: {best:?} (EV {best_ev:+.3})"),
+            format!("Hit (EV {:+.3})", ev.hit),
+            format!("Stand (EV {:+.3})", ev.stand),
+        ),
+        None => ("It's your turn!".to_string(), "Hit".to_string(), "Stand".to_string()),
+    };
+
+    if let Some(hint) = hint {
+        msg = format!("{msg}\n{hint}");
+    }
+
+    set_message(s, msg);
 
     s.call_on_name("game_dialog", |d: &mut Dialog| {
         d.clear_buttons();
-        d.add_button("Hit", move |s| hit_callback(s, cfg));
-        d.add_button("Stand", move |s| stand_callback(s, cfg));
+        d.add_button(hit_label, move |s| hit_callback(s, cfg));
+        d.add_button(stand_label, move |s| stand_callback(s, cfg));
     });
 }
 
-/// End the round and display the results
+/// End the round, adding it to the lifetime statistics, and display the results
 pub(crate) fn end_game(s: &mut Cursive, cfg: Config) {
+    let stats_error = match s.user_data::<AnyTable>() {
+        Some(AnyTable::GameOver(table)) => files::record_round(table).err(),
+        _ => None,
+    };
+
+    show_result(s, cfg);
+
+    if let Some(e) = stats_error {
+        s.call_on_name("message_box", |d: &mut Dialog| {
+            if let Some(text) = d.get_content_mut().downcast_mut::<TextView>() {
+                text.append(format!("\nThe lifetime statistics could not be saved: {e}"));
+            }
+        });
+    }
+}
+
+/// Display the results of a finished round
+pub(crate) fn show_result(s: &mut Cursive, cfg: Config) {
     update_dealer_hand(s, cfg.ascii);
     update_player_hand(s, cfg.ascii);
 
-    if let Some(table) = s.user_data::<BlackjackTable<GameOver>>() {
-        let msg = match table.result() {
-            GameResult::DealerWin => "The dealer won",
-            GameResult::PlayerWin => "You win!",
-            GameResult::DealerBust => "The dealer went bust! You win!",
-            GameResult::PlayerBust => "You went bust!",
-            GameResult::StandOff => "You and the dealer are in a stand off!",
+    if let Some(AnyTable::GameOver(table)) = s.user_data::<AnyTable>() {
+        let msg = format!("{} ({:+.1})\nBankroll: {:.1} units", describe_result(*table.result()), table.payout(), table.session().bankroll);
+
+        let msg = match table.history().and_then(|h| h.error()) {
+            Some(e) => format!("{msg}\nThe hand history could not be written: {e}"),
+            None => msg,
         };
 
         set_message(s, msg);
@@ -90,3 +166,14 @@ pub(crate) fn end_game(s: &mut Cursive, cfg: Config) {
         d.add_button("Play Again", move |s| init_round(s, cfg));
     });
 }
+
+/// Describe the result of a round to the player
+pub(crate) fn describe_result(result: GameResult) -> &'static str {
+    match result {
+        GameResult::DealerWin => "The dealer won",
+        GameResult::PlayerWin => "You win!",
+        GameResult::DealerBust => "The dealer went bust! You win!",
+        GameResult::PlayerBust => "You went bust!",
+        GameResult::StandOff => "You and the dealer are in a stand off!",
+    }
+}
@@ -4,74 +4,143 @@ use cursive::view::Nameable;
 use cursive::{Cursive, CursiveExt};
 use cursive::views::{LinearLayout, Dialog, TextView};
 
+use std::path::PathBuf;
+
 use crate::Config;
-use crate::game_rules::round::{
-    BlackjackTable,
-    PlayerTurn,
-    PlayerTurnResult,
-    
-};
+use blackjack::game::{AnyTable, TableAction};
+use blackjack::history::HistoryLog;
+use blackjack::round::{BlackjackRoundError, BlackjackTable};
+use blackjack::session::Session;
 
 mod update_hands;
 mod dealer_turn;
+mod stats_view;
+pub mod trainer;
+pub mod replay;
+pub mod remote;
 
 use dealer_turn::run_dealer_turn;
 
-/// Start a tui game.
+/// Start a tui game, writing the history of each round to the log if one is supplied
+/// 
+/// A resumed table continues from the state it was saved in.
+/// When the tui is exited, the table is saved to `save_path`
 /// 
 /// Returns when tui is exited
-pub fn run_game(cfg: Config) {
+pub fn run_game(cfg: Config, history: Option<HistoryLog>, resumed: Option<AnyTable>, save_path: Option<PathBuf>) {
     let mut tui = Cursive::default();
 
-    tui.add_global_callback('q', |s| s.quit());
+    tui.add_global_callback('q', move |s| stats_view::quit_with_summary(s, cfg));
+    tui.add_global_callback('s', move |s| stats_view::toggle_stats(s, cfg));
 
     tui.update_theme(|t| {
         t.palette[PaletteColor::Background] = Color::Dark(BaseColor::Green);
     });
 
-    let hands = LinearLayout::vertical()
-    .child(Dialog::text("Dealer's hand placeholder").title("Dealer").with_name("dealer_hand"))
-    .child(Dialog::text("Player's hand placeholder").title("Your Hand").with_name("player_hand"))
-    .child(Dialog::text("message placeholder").with_name("message_box"));
-
     tui.add_layer(
-        Dialog::around(hands).with_name("game_dialog")
+        Dialog::around(table_layout()).with_name("game_dialog")
     );
 
-    game_states::init_round(&mut tui, cfg);
+    let table = resumed.unwrap_or_else(|| {
+        AnyTable::from(BlackjackTable::default().with_bankroll(cfg.bankroll).reshuffle())
+    });
+
+    let table = match history {
+        Some(log) => table.with_history(log),
+        None => table,
+    };
+
+    // Pick up the game from whichever state the table was left in
+    let resume: fn(&mut Cursive, Config) = match &table {
+        AnyTable::NotStarted(_) => game_states::init_round,
+        AnyTable::PlayerTurn(_) => game_states::start_player_turn,
+        AnyTable::DealerTurn(_) => |s, cfg| {
+            update_hands::update_player_hand(s, cfg.ascii);
+            update_hands::update_dealer_hand(s, cfg.ascii);
+            run_dealer_turn(s, cfg);
+        },
+        AnyTable::GameOver(_) => game_states::show_result,
+    };
+
+    tui.set_user_data(table);
+    resume(&mut tui, cfg);
 
     tui.run();
 
+    if let (Some(path), Some(table)) = (save_path, tui.take_user_data::<AnyTable>()) {
+        match table.save(&path) {
+            Ok(()) => println!("Session saved, resume it with --resume {}", path.display()),
+            Err(e) => eprintln!("Unable to save the session to {}: {e}", path.display()),
+        }
+    }
+}
+
+/// The dealer's and player's hands above a message box, as every view of a table shows them
+fn table_layout() -> LinearLayout {
+    LinearLayout::vertical()
+    .child(Dialog::text("Dealer's hand placeholder").title("Dealer").with_name("dealer_hand"))
+    .child(Dialog::text("Player's hand placeholder").title("Your Hand").with_name("player_hand"))
+    .child(Dialog::text("message placeholder").with_name("message_box"))
+}
+
+/// Returns the player's session at the table
+fn current_session(s: &mut Cursive) -> Option<Session> {
+    s.user_data::<AnyTable>().map(|t| t.session().clone())
+}
+
+/// Take an action at the table, putting the table back in its new state
+/// 
+/// Shows an error if the action cannot be taken, returning false
+fn apply_action(s: &mut Cursive, action: TableAction) -> bool {
+    let Some(table) = s.take_user_data::<AnyTable>() else {
+        error_popup(s, "Invalid game state");
+        return false;
+    };
+
+    match table.apply(action) {
+        Ok(table) => {
+            s.set_user_data(table);
+            true
+        },
+        Err(e) => {
+            let msg = match e.error {
+                BlackjackRoundError::IllegalAction { .. } => format!("Invalid game state: {e}"),
+                _ => format!("The round could not continue: {e}"),
+            };
+
+            // The table is kept when the action could not be taken at all
+            if let Some(table) = e.table {
+                s.set_user_data(*table);
+            }
+
+            error_popup(s, msg);
+            false
+        },
+    }
 }
 
 /// Callback for a player hitting during their turn.
 /// 
 /// Will error if called outside of a player's turn
 fn hit_callback(s: &mut Cursive, cfg: Config) {
-    if let Some(table) = s.take_user_data::<BlackjackTable<PlayerTurn>>() {
-        match table.hit() {
-            Ok(PlayerTurnResult::Hit(new_table)) => s.set_user_data(new_table),
-            Ok(PlayerTurnResult::Bust(new_table)) => {
-                s.set_user_data(new_table);
-
-                game_states::end_game(s, cfg)
-            },
-            Err(_) => todo!(),
-        }
+    if !apply_action(s, TableAction::Hit) {
+        return;
+    }
 
-        update_hands::update_player_hand(s, cfg.ascii)
+    if matches!(s.user_data::<AnyTable>(), Some(AnyTable::GameOver(_))) {
+        game_states::end_game(s, cfg);
     } else {
-        error_popup(s, "Invalid game state");
-    };
+        game_states::start_player_turn(s, cfg);
+    }
+
+    update_hands::update_player_hand(s, cfg.ascii)
 }
 
 /// Callback for a player standing to end their turn
 /// 
 /// Will error if called outside of a player's turn
 fn stand_callback(s: &mut Cursive, cfg: Config) {
-    if let Some(table) = s.take_user_data::<BlackjackTable<PlayerTurn>>() {
-        s.set_user_data(table.stand());
-
+    if apply_action(s, TableAction::Stand) {
         run_dealer_turn(s, cfg);
     }
 }
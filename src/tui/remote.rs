@@ -0,0 +1,365 @@
+use super::game_states::describe_result;
+use super::update_hands::{display_face_down, display_full_hand};
+use super::{error_popup, set_message, table_layout};
+
+use crate::Config;
+use crate::server::ServerConfig;
+use crate::server::client::Connection;
+use crate::server::protocol::{Command, SeatView, ServerMessage, TableView};
+use blackjack::cards::Card;
+use blackjack::shared::Phase;
+
+use std::thread;
+
+use clap::Args;
+use cursive::theme::{PaletteColor, Color, BaseColor};
+use cursive::traits::Resizable;
+use cursive::view::Nameable;
+use cursive::{Cursive, CursiveExt};
+use cursive::views::{Dialog, EditView};
+
+#[derive(Args, Clone)]
+pub struct ConnectConfig {
+
+    /// Address of the table, e.g. `127.0.0.1:7878`
+    addr: String,
+
+    /// The name to sit at the table with
+    #[arg(long, default_value = "Player")]
+    name: String,
+
+    /// Take back a seat kept after losing the connection,
+    /// with the token sent when you joined
+    #[arg(long, value_name = "TOKEN")]
+    rejoin: Option<String>,
+}
+
+#[derive(Args, Clone)]
+pub struct HostConfig {
+
+    #[command(flatten)]
+    server: ServerConfig,
+
+    /// The name to sit at the table with
+    #[arg(long, default_value = "Host")]
+    name: String,
+}
+
+/// The connection to the table and the last state it sent
+struct RemoteTable {
+    connection: Connection,
+    seat: Option<usize>,
+    token: Option<String>,
+    view: Option<TableView>,
+    /// The address other players can connect to, if this table is hosted here
+    hosting: Option<String>,
+}
+
+/// Host a table on localhost and play at it, while others connect with `connect`
+///
+/// The table is served from a background thread until the tui is exited
+pub fn run_host(cfg: Config, host_cfg: HostConfig) {
+    let server = host_cfg.server.bind();
+
+    let (server, addr) = match server.and_then(|s| s.local_addr().map(|addr| (s, addr))) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Unable to host the table: {e}");
+            return;
+        },
+    };
+
+    thread::spawn(move || server.run());
+
+    let addr = addr.to_string();
+    play_remote(cfg, addr.clone(), Command::Join { name: host_cfg.name }, Some(addr));
+}
+
+/// Play at a table hosted with `serve` or `host`
+pub fn run_remote(cfg: Config, connect_cfg: ConnectConfig) {
+    let first = match connect_cfg.rejoin {
+        Some(token) => Command::Rejoin { token },
+        None => Command::Join { name: connect_cfg.name },
+    };
+
+    play_remote(cfg, connect_cfg.addr, first, None);
+}
+
+/// Connect to the table and show it in the tui, sending `first` to take a seat
+///
+/// Returns when tui is exited
+fn play_remote(cfg: Config, addr: String, first: Command, hosting: Option<String>) {
+    let mut tui = Cursive::default();
+
+    tui.add_global_callback('q', |s| s.quit());
+    tui.add_global_callback('h', |s| send(s, Command::Hit));
+    tui.add_global_callback('s', |s| send(s, Command::Stand));
+    tui.add_global_callback('b', ask_bet);
+
+    tui.update_theme(|t| {
+        t.palette[PaletteColor::Background] = Color::Dark(BaseColor::Green);
+    });
+
+    // The other players are shown between the dealer and your own hand
+    let mut layout = table_layout();
+    layout.insert_child(1, Dialog::text("Nobody else is at the table").title("Other Seats").with_name("other_seats"));
+
+    tui.add_layer(
+        Dialog::around(layout)
+        .title(format!("Table at {addr}"))
+        .with_name("game_dialog")
+    );
+
+    let cb_sink = tui.cb_sink().clone();
+
+    // Messages are handled on the tui's thread, in the order they arrive
+    let connection = Connection::open(addr.as_str(), move |message| {
+        let _ = cb_sink.send(Box::new(move |s| on_message(s, cfg, message)));
+    });
+
+    let connection = match connection {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Unable to connect to {addr}: {e}");
+            return;
+        },
+    };
+
+    tui.set_user_data(RemoteTable { connection, seat: None, token: None, view: None, hosting });
+
+    set_message(&mut tui, "Joining the table...");
+    send(&mut tui, first);
+
+    tui.run();
+
+    // Give up the seat rather than keeping it for a rejoin
+    if let Some(mut table) = tui.take_user_data::<RemoteTable>() {
+        let _ = table.connection.send(&Command::Leave);
+    }
+}
+
+/// Send a command to the table
+fn send(s: &mut Cursive, command: Command) {
+    let result = s.with_user_data(|t: &mut RemoteTable| t.connection.send(&command));
+
+    if let Some(Err(e)) = result {
+        error_popup(s, format!("The connection to the table was lost: {e}"));
+    }
+}
+
+/// Prompt for the number of units to bet on the next round
+fn ask_bet(s: &mut Cursive) {
+    s.add_layer(
+        Dialog::around(
+            EditView::new()
+            .content("1")
+            .on_submit(|s, answer| {
+                s.pop_layer();
+
+                match answer.trim().parse::<f64>() {
+                    Ok(units) => send(s, Command::Bet(units)),
+                    Err(_) => set_message(s, format!("`{}` is not a number of units", answer.trim())),
+                }
+            })
+            .fixed_width(10)
+        )
+        .title("Bet (units)")
+    );
+}
+
+fn on_message(s: &mut Cursive, cfg: Config, message: Option<ServerMessage>) {
+    let Some(message) = message else {
+        error_popup(s, "The connection to the table was closed");
+        return;
+    };
+
+    match message {
+        ServerMessage::Welcome { seats, rules } => {
+            let title = format!(
+                "{seats} seat table ({} deck{}, {})",
+                rules.decks,
+                if rules.decks == 1 { "" } else { "s" },
+                if rules.dealer_hits_soft_17 { "H17" } else { "S17" },
+            );
+
+            s.call_on_name("game_dialog", |d: &mut Dialog| d.set_title(title));
+        },
+        ServerMessage::Seated { seat, token } => {
+            s.with_user_data(|t: &mut RemoteTable| {
+                t.seat = Some(seat);
+                t.token = Some(token);
+            });
+
+            show_table(s, cfg, true);
+        },
+        ServerMessage::State(view) => {
+            let entered = s.with_user_data(|t: &mut RemoteTable| {
+                let previous = t.view.replace(view);
+
+                previous.map(|v| v.phase) != t.view.as_ref().map(|v| v.phase)
+            });
+
+            show_table(s, cfg, entered.unwrap_or(true));
+        },
+        ServerMessage::Turn { seat, seconds } => {
+            let msg = match (my_seat(s), player_name(s, seat)) {
+                (Some(mine), _) if mine == seat => format!("It's your turn! You have {seconds:.0} seconds to act"),
+                (_, Some(name)) => format!("Waiting for {name} to play their hand"),
+                _ => "Waiting for another player".to_string(),
+            };
+
+            set_message(s, msg);
+        },
+        ServerMessage::DealerDrew { card } => {
+            let card = if cfg.ascii { format!("{card:#}") } else { card.to_string() };
+
+            set_message(s, format!("The dealer is playing...\nThe dealer drew {card}"));
+        },
+        ServerMessage::TimedOut { seat } if my_seat(s) == Some(seat) => {
+            set_message(s, "You took too long to act, so your hand was stood");
+        },
+        ServerMessage::TimedOut { .. } | ServerMessage::Left { .. } => {},
+        ServerMessage::Error { message } => set_message(s, message),
+    }
+}
+
+fn my_seat(s: &mut Cursive) -> Option<usize> {
+    s.user_data::<RemoteTable>()?.seat
+}
+
+fn player_name(s: &mut Cursive, seat: usize) -> Option<String> {
+    let view = s.user_data::<RemoteTable>()?.view.as_ref()?;
+
+    Some(view.seats.get(seat)?.as_ref()?.name.clone())
+}
+
+/// Show the last state sent by the table, with the actions that can be taken in it
+///
+/// The message is only replaced if the phase was just `entered`, or it says
+/// what the player should do next, so the table's events stay on screen
+fn show_table(s: &mut Cursive, cfg: Config, entered: bool) {
+    let Some(table) = s.user_data::<RemoteTable>() else { return };
+    let Some(view) = table.view.clone() else { return };
+
+    let seat = table.seat;
+    let me = seat.and_then(|seat| view.seats.get(seat)?.clone());
+    let rejoin = table.token.as_ref().map(|token| format!("\nIf you lose your connection, rejoin with --rejoin {token}"));
+    let hosting = table.hosting.as_ref().map(|addr| format!("\nOthers can join with `blackjack connect {addr}`"));
+
+    let (title, cards) = match view.phase {
+        Phase::Betting => ("Dealer's hand".to_string(), display_face_down(cfg.ascii)),
+        Phase::PlayerTurn { .. } => {
+            let mut cards = display_full_hand(&view.dealer, cfg.ascii);
+            cards.add_child(Dialog::text(Card::flipped_icon(cfg.ascii)));
+
+            ("Dealer's hand".to_string(), cards)
+        },
+        Phase::DealerTurn | Phase::RoundOver => (
+            format!("Dealer's hand (score: {}):", view.dealer_total),
+            display_full_hand(&view.dealer, cfg.ascii),
+        ),
+    };
+
+    s.call_on_name("dealer_hand", |d: &mut Dialog| {
+        d.set_title(title);
+        d.set_content(cards);
+    });
+
+    let (title, cards) = match &me {
+        Some(player) if !player.cards.is_empty() => (
+            format!("Your hand (score: {}):", player.total),
+            display_full_hand(&player.cards, cfg.ascii),
+        ),
+        _ => ("Your hand".to_string(), display_face_down(cfg.ascii)),
+    };
+
+    s.call_on_name("player_hand", |d: &mut Dialog| {
+        d.set_title(title);
+        d.set_content(cards);
+    });
+
+    let others: Vec<String> = view.seats.iter()
+        .enumerate()
+        .filter(|(i, _)| Some(*i) != seat)
+        .filter_map(|(i, player)| Some(describe_seat(i, player.as_ref()?, cfg.ascii)))
+        .collect();
+
+    let others = match others.is_empty() {
+        true => "Nobody else is at the table".to_string(),
+        false => others.join("\n"),
+    };
+
+    s.call_on_name("other_seats", |d: &mut Dialog| d.set_content(cursive::views::TextView::new(others)));
+
+    let msg = match (view.phase, &me) {
+        (_, None) => Some("You are not sitting at the table".to_string()),
+        (Phase::Betting, Some(player)) => Some(match player.bet {
+            Some(units) => format!("You bet {units} units, the cards are dealt once everyone has bet"),
+            None => format!(
+                "Bankroll: {:.1} units\nPress b to bet, or q any time to leave the table{}{}",
+                player.bankroll,
+                rejoin.unwrap_or_default(),
+                hosting.unwrap_or_default(),
+            ),
+        }),
+        (Phase::DealerTurn, _) if entered => Some("The dealer is playing...".to_string()),
+        (Phase::RoundOver, Some(player)) => Some(match player.outcome {
+            Some(outcome) => format!(
+                "{} ({:+.1})\nBankroll: {:.1} units",
+                describe_result(outcome.result),
+                outcome.payout,
+                player.bankroll,
+            ),
+            None => "The round is over".to_string(),
+        }),
+        _ => None,
+    };
+
+    if let Some(msg) = msg {
+        set_message(s, msg);
+    }
+
+    let my_turn = matches!(view.phase, Phase::PlayerTurn { seat: turn } if Some(turn) == seat);
+    let can_bet = view.phase == Phase::Betting && me.is_some();
+
+    s.call_on_name("game_dialog", |d: &mut Dialog| {
+        d.clear_buttons();
+
+        if can_bet {
+            d.add_button("Bet", ask_bet);
+        }
+
+        if my_turn {
+            d.add_button("Hit", |s| send(s, Command::Hit));
+            d.add_button("Stand", |s| send(s, Command::Stand));
+        }
+
+        d.add_button("Leave", |s| s.quit());
+    });
+}
+
+/// A line describing another player's seat
+///
+/// Seats are numbered from 0, as the table numbers them in its messages and errors
+fn describe_seat(seat: usize, player: &SeatView, ascii: bool) -> String {
+    let away = if player.connected { "" } else { " (away)" };
+
+    let hand = match (&player.cards[..], player.bet) {
+        ([], Some(units)) => format!("bet {units} units"),
+        ([], None) => "no bet yet".to_string(),
+        (cards, _) => {
+            let cards: Vec<String> = cards.iter()
+                .map(|c| if ascii { format!("{c:#}") } else { c.to_string() })
+                .collect();
+
+            format!("{} ({})", cards.join(" "), player.total)
+        },
+    };
+
+    let outcome = match player.outcome {
+        Some(outcome) => format!(", {:+.1}", outcome.payout),
+        None => String::new(),
+    };
+
+    format!("Seat {seat}: {}{away}, {hand}{outcome}", player.name)
+}
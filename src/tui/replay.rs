@@ -0,0 +1,358 @@
+use super::update_hands::{update_dealer_hand, update_player_hand};
+use super::{set_message, table_layout};
+
+use crate::Config;
+use blackjack::deck::Deck;
+use blackjack::game::{AnyTable, TableAction};
+use blackjack::history::{read_history, RecordedRound};
+use blackjack::round::{BlackjackTable, GameResult};
+use blackjack::strategy::{basic_strategy, AvailableActions};
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use clap::Args;
+use cursive::theme::{PaletteColor, Color, BaseColor};
+use cursive::traits::Resizable;
+use cursive::view::Nameable;
+use cursive::{Cursive, CursiveExt};
+use cursive::views::{Dialog, EditView};
+
+#[derive(Args, Debug, Clone)]
+pub struct ReplayConfig {
+
+    /// A hand history written with `--history`
+    file: PathBuf,
+}
+
+/// Deal the recorded round from a stacked shoe and play `steps` of its
+/// actions and dealer draws, returning [None] if the round has fewer steps
+fn replay_round(round: &RecordedRound, steps: usize) -> Option<AnyTable> {
+    let deck = Deck::stacked(round.cards_in_draw_order());
+
+    let mut table = AnyTable::from(BlackjackTable::with_deck(round.rules, deck))
+        .apply(TableAction::Deal)
+        .ok()?;
+
+    for _ in 0..steps {
+        let action = match &table {
+            AnyTable::PlayerTurn(t) => {
+                let taken = t.player_hand().cards().len() - 2;
+
+                TableAction::try_from(round.actions.get(taken)?.0).ok()?
+            },
+            AnyTable::DealerTurn(_) => TableAction::DealerPlay,
+            _ => return None,
+        };
+
+        table = table.apply(action).ok()?;
+    }
+
+    Some(table)
+}
+
+/// Returns the number of steps that can be replayed in the round
+fn count_steps(round: &RecordedRound) -> usize {
+    let mut steps = 0;
+
+    while let Some(table) = replay_round(round, steps) {
+        if table.result().is_some() {
+            break;
+        }
+
+        steps += 1;
+    }
+
+    // A truncated round stops at its last step that could be replayed
+    match replay_round(round, steps) {
+        Some(_) => steps,
+        None => steps.saturating_sub(1),
+    }
+}
+
+/// The rounds being replayed and the position within them
+struct Replay {
+    rounds: Vec<RecordedRound>,
+    steps: Vec<usize>,
+    hand: usize,
+    step: usize,
+}
+
+impl Replay {
+    fn new(rounds: Vec<RecordedRound>) -> Self {
+        let steps = rounds.iter().map(count_steps).collect();
+
+        Self { rounds, steps, hand: 0, step: 0 }
+    }
+
+    fn next(&mut self) {
+        if self.step < self.steps[self.hand] {
+            self.step += 1;
+        } else if self.hand + 1 < self.rounds.len() {
+            self.hand += 1;
+            self.step = 0;
+        }
+    }
+
+    fn previous(&mut self) {
+        if self.step > 0 {
+            self.step -= 1;
+        } else if self.hand > 0 {
+            self.hand -= 1;
+            self.step = self.steps[self.hand];
+        }
+    }
+
+    /// Move to the start of a hand, numbered from 1
+    fn jump(&mut self, hand: usize) {
+        self.hand = hand.clamp(1, self.rounds.len()) - 1;
+        self.step = 0;
+    }
+}
+
+/// Step through the rounds of a hand history in the TUI
+pub fn run_replay(cfg: Config, replay_cfg: ReplayConfig) {
+    let rounds = File::open(&replay_cfg.file)
+        .map_err(|e| e.to_string())
+        .and_then(|f| read_history(BufReader::new(f)).map_err(|e| e.to_string()));
+
+    let rounds = match rounds {
+        Ok(rounds) if rounds.is_empty() => {
+            eprintln!("{} does not contain any rounds", replay_cfg.file.display());
+            return;
+        },
+        Ok(rounds) => rounds,
+        Err(e) => {
+            eprintln!("Unable to read {}: {e}", replay_cfg.file.display());
+            return;
+        },
+    };
+
+    let replay = Rc::new(RefCell::new(Replay::new(rounds)));
+
+    let mut tui = Cursive::default();
+
+    tui.add_global_callback('q', |s| s.quit());
+
+    tui.update_theme(|t| {
+        t.palette[PaletteColor::Background] = Color::Dark(BaseColor::Green);
+    });
+
+    let (prev, next, jump) = (replay.clone(), replay.clone(), replay.clone());
+
+    tui.add_layer(
+        Dialog::around(table_layout())
+        .button("< Previous", move |s| step(s, cfg, &prev, Replay::previous))
+        .button("Next >", move |s| step(s, cfg, &next, Replay::next))
+        .button("Jump to hand", move |s| ask_hand(s, cfg, &jump))
+        .button("Quit", |s| s.quit())
+        .with_name("game_dialog")
+    );
+
+    for (key, movement) in [('n', Replay::next as fn(&mut Replay)), ('p', Replay::previous)] {
+        let r = replay.clone();
+
+        tui.add_global_callback(key, move |s| step(s, cfg, &r, movement));
+    }
+
+    let r = replay.clone();
+    tui.add_global_callback('j', move |s| ask_hand(s, cfg, &r));
+
+    show(&mut tui, cfg, &replay.borrow());
+
+    tui.run();
+}
+
+/// Move through the replay and show the new position
+fn step(s: &mut Cursive, cfg: Config, replay: &Rc<RefCell<Replay>>, movement: fn(&mut Replay)) {
+    movement(&mut replay.borrow_mut());
+
+    show(s, cfg, &replay.borrow());
+}
+
+/// Prompt for the number of a hand to jump to
+fn ask_hand(s: &mut Cursive, cfg: Config, replay: &Rc<RefCell<Replay>>) {
+    let replay = replay.clone();
+    let hands = replay.borrow().rounds.len();
+
+    s.add_layer(
+        Dialog::around(
+            EditView::new()
+            .on_submit(move |s, answer| {
+                s.pop_layer();
+
+                if let Ok(hand) = answer.trim().parse::<usize>() {
+                    replay.borrow_mut().jump(hand);
+                }
+
+                show(s, cfg, &replay.borrow());
+            })
+            .fixed_width(10)
+        )
+        .title(format!("Jump to hand (1 to {hands})"))
+    );
+}
+
+/// Display the table at the current position, with the
+/// player's recorded decision and the basic strategy play
+fn show(s: &mut Cursive, cfg: Config, replay: &Replay) {
+    let round = &replay.rounds[replay.hand];
+
+    let msg = match replay_round(round, replay.step) {
+        Some(AnyTable::PlayerTurn(t)) => {
+            let taken = t.player_hand().cards().len() - 2;
+            let upcard = t.showing_card().copied();
+
+            let basic = upcard.map(|up| basic_strategy(t.player_hand(), &up, AvailableActions::default(), t.rules()));
+
+            let msg = match (round.actions.get(taken), basic) {
+                (Some((action, _)), Some(basic)) if *action == basic => {
+                    format!("You chose to {action:?}, as basic strategy would")
+                },
+                (Some((action, _)), Some(basic)) => {
+                    format!("You chose to {action:?}, basic strategy would {basic:?}")
+                },
+                (None, Some(basic)) => {
+                    format!("The history ends here, basic strategy would {basic:?}")
+                },
+                _ => "The history ends here".to_string(),
+            };
+
+            s.set_user_data(AnyTable::from(t));
+            msg
+        },
+        Some(AnyTable::DealerTurn(t)) => {
+            let msg = match t.dealer_hand().cards().len() {
+                2 => "The dealer reveals their hole card".to_string(),
+                _ => format!("The dealer drew {}", t.dealer_hand().cards().last().map(|c| c.to_string()).unwrap_or_default()),
+            };
+
+            s.set_user_data(AnyTable::from(t));
+            msg
+        },
+        Some(AnyTable::GameOver(t)) => {
+            let result = match t.result() {
+                GameResult::DealerWin => "The dealer won",
+                GameResult::PlayerWin => "You won!",
+                GameResult::DealerBust => "The dealer went bust! You won!",
+                GameResult::PlayerBust => "You went bust!",
+                GameResult::StandOff => "You and the dealer were in a stand off!",
+            };
+
+            let msg = match round.result {
+                Some((_, payout)) => format!("{result} (payout {payout:+.1})"),
+                None => result.to_string(),
+            };
+
+            s.set_user_data(AnyTable::from(t));
+            msg
+        },
+        _ => {
+            s.set_user_data(());
+            "This round could not be replayed".to_string()
+        },
+    };
+
+    update_dealer_hand(s, cfg.ascii);
+    update_player_hand(s, cfg.ascii);
+
+    set_message(s, format!("{msg}\n\n[n]ext, [p]revious, [j]ump to hand, [q]uit"));
+
+    let title = format!(
+        "Hand {} of {} (shoe {}, round {}), step {} of {}",
+        replay.hand + 1,
+        replay.rounds.len(),
+        round.shoe,
+        round.round,
+        replay.step + 1,
+        replay.steps[replay.hand] + 1,
+    );
+
+    s.call_on_name("game_dialog", |d: &mut Dialog| {
+        d.set_title(title);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use blackjack::cards::Card;
+    use blackjack::game::AnyTable;
+    use blackjack::round::GameResult;
+    use blackjack::rules::Rules;
+    use blackjack::strategy::Action;
+
+    use super::{count_steps, replay_round, Replay, RecordedRound};
+
+    fn card(name: &str) -> Card {
+        name.parse().unwrap()
+    }
+
+    /// A round where the player hits 16 to 18 and stands, and the dealer stands on 17
+    fn recorded(actions: Vec<(Action, Option<Card>)>) -> RecordedRound {
+        RecordedRound {
+            shoe: "0123456789abcdef".to_string(),
+            round: 1,
+            rules: Rules::default(),
+            player: vec![card("10H"), card("6S")],
+            dealer_upcard: card("9D"),
+            dealer_hole: card("8C"),
+            actions,
+            dealer_draws: Vec::new(),
+            result: Some((GameResult::PlayerWin, 1.0)),
+        }
+    }
+
+    #[test]
+    fn recorded_rounds_are_replayed_step_by_step() {
+        let round = recorded(vec![(Action::Hit, Some(card("2C"))), (Action::Stand, None)]);
+
+        assert!(matches!(replay_round(&round, 0), Some(AnyTable::PlayerTurn(t)) if t.player_hand().total_value() == 16));
+        assert!(matches!(replay_round(&round, 1), Some(AnyTable::PlayerTurn(t)) if t.player_hand().total_value() == 18));
+        assert!(matches!(replay_round(&round, 2), Some(AnyTable::DealerTurn(_))));
+
+        let finished = replay_round(&round, 3).unwrap();
+        assert_eq!(finished.result(), Some(GameResult::PlayerWin));
+
+        assert!(replay_round(&round, 4).is_none());
+        assert_eq!(count_steps(&round), 3);
+
+        // A history cut off part way through a round stops at its last recorded action
+        let truncated = recorded(vec![(Action::Hit, Some(card("2C")))]);
+        assert_eq!(count_steps(&truncated), 1);
+    }
+
+    #[test]
+    fn the_replay_moves_between_hands() {
+        let mut replay = Replay::new(vec![
+            recorded(vec![(Action::Stand, None)]),
+            recorded(vec![(Action::Hit, Some(card("2C"))), (Action::Stand, None)]),
+        ]);
+
+        assert_eq!(replay.steps, [2, 3]);
+
+        replay.previous();
+        assert_eq!((replay.hand, replay.step), (0, 0));
+
+        for _ in 0..3 {
+            replay.next();
+        }
+        assert_eq!((replay.hand, replay.step), (1, 0));
+
+        replay.previous();
+        assert_eq!((replay.hand, replay.step), (0, 2));
+
+        replay.jump(5);
+        assert_eq!((replay.hand, replay.step), (1, 0));
+
+        for _ in 0..10 {
+            replay.next();
+        }
+        assert_eq!((replay.hand, replay.step), (1, 3));
+
+        replay.jump(0);
+        assert_eq!((replay.hand, replay.step), (0, 0));
+    }
+}
@@ -0,0 +1,209 @@
+use super::current_session;
+
+use crate::Config;
+use blackjack::lifetime::LifetimeStats;
+use blackjack::session::Session;
+
+use cursive::Cursive;
+use cursive::view::{Nameable, Scrollable};
+use cursive::views::{Dialog, LinearLayout, TextView};
+
+/// The widest the bankroll sparkline is drawn
+const SPARKLINE_WIDTH: usize = 60;
+
+/// Open the statistics view, or close it if it is already open
+pub(crate) fn toggle_stats(s: &mut Cursive, cfg: Config) {
+    if s.find_name::<Dialog>("stats_view").is_some() {
+        s.pop_layer();
+        return;
+    }
+
+    let session = current_session(s).map(|session| session_summary(&session, cfg.ascii))
+        .unwrap_or_else(|| "No session is in progress".to_string());
+
+    let (lifetime, table) = match crate::files::stats_path().map(LifetimeStats::load) {
+        Some(Ok(stats)) => (lifetime_summary(&stats), win_rate_table(&stats)),
+        Some(Err(e)) => (format!("The lifetime statistics could not be read: {e}"), String::new()),
+        None => ("No data directory was found to keep statistics in".to_string(), String::new()),
+    };
+
+    let content = LinearLayout::vertical()
+        .child(Dialog::text(session).title("This Session"))
+        .child(Dialog::text(lifetime).title("Lifetime"))
+        .child(Dialog::around(TextView::new(table).scrollable()).title("Win Rate by Starting Total and Upcard"));
+
+    s.add_layer(
+        Dialog::around(content)
+        .title("Statistics")
+        .button("Close", |s| { s.pop_layer(); })
+        .with_name("stats_view")
+    );
+}
+
+/// Show a summary of the session before quitting,
+/// or quit if the summary is already showing
+pub(crate) fn quit_with_summary(s: &mut Cursive, cfg: Config) {
+    if s.find_name::<Dialog>("session_summary").is_some() {
+        s.quit();
+        return;
+    }
+
+    let Some(session) = current_session(s) else {
+        s.quit();
+        return;
+    };
+
+    s.add_layer(
+        Dialog::text(session_summary(&session, cfg.ascii))
+        .title("Session Summary")
+        .button("Quit", |s| s.quit())
+        .button("Keep Playing", |s| { s.pop_layer(); })
+        .with_name("session_summary")
+    );
+}
+
+fn session_summary(session: &Session, ascii: bool) -> String {
+    let stats = &session.stats;
+
+    format!(
+        "Rounds played: {}\n\
+        Wins: {}, losses: {}, pushes: {}\n\
+        Blackjacks: {}\n\
+        Busts: {} (dealer {})\n\
+        Net result: {:+.1} units\n\
+        Bankroll: {:.1} units\n\n\
+        {}",
+        stats.rounds,
+        stats.wins,
+        stats.losses,
+        stats.pushes,
+        stats.blackjacks,
+        stats.player_busts,
+        stats.dealer_busts,
+        stats.net,
+        session.bankroll,
+        sparkline(&session.bankroll_history(), ascii),
+    )
+}
+
+fn lifetime_summary(stats: &LifetimeStats) -> String {
+    if stats.hands == 0 {
+        return "No hands have been played yet".to_string();
+    }
+
+    let percent = |n: u64| n as f64 / stats.hands as f64 * 100.0;
+    let results = &stats.results;
+
+    format!(
+        "Hands played: {}\n\
+        Wins: {:.1}%, losses: {:.1}%, pushes: {:.1}%\n\
+        Blackjacks: {}\n\
+        Net result: {:+.1} units ({:+.3} per hand)\n\
+        Longest streaks: {} wins, {} losses",
+        stats.hands,
+        percent(results.wins()),
+        percent(results.losses()),
+        percent(results.pushes()),
+        stats.blackjacks,
+        stats.net,
+        stats.net / stats.hands as f64,
+        stats.longest_win_streak,
+        stats.longest_loss_streak,
+    )
+}
+
+/// Draw the bankroll as a line of bars, scaled between its lowest and highest points
+fn sparkline(points: &[f64], ascii: bool) -> String {
+    let bars: Vec<char> = if ascii {
+        "_.-~=+*#".chars().collect()
+    } else {
+        "▁▂▃▄▅▆▇█".chars().collect()
+    };
+
+    let step = points.len().div_ceil(SPARKLINE_WIDTH).max(1);
+    let points: Vec<f64> = points.iter().step_by(step).copied().collect();
+
+    let low = points.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = points.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    points.iter().map(|p| {
+        let level = if high > low {
+            ((p - low) / (high - low) * (bars.len() - 1) as f64).round() as usize
+        } else {
+            bars.len() / 2
+        };
+
+        bars[level]
+    }).collect()
+}
+
+/// Lay out the fraction of hands won for each starting total against each upcard
+fn win_rate_table(stats: &LifetimeStats) -> String {
+    if stats.by_start.is_empty() {
+        return "No hands have been played yet".to_string();
+    }
+
+    // Upcards are listed in the usual order, from 2 through to the Ace
+    let upcards: Vec<u8> = (2..=10).chain([1]).collect();
+
+    let mut table = format!("{:<7}", "Start");
+
+    for upcard in &upcards {
+        match upcard {
+            1 => table.push_str(&format!("{:>5}", "A")),
+            v => table.push_str(&format!("{v:>5}")),
+        }
+    }
+
+    for (total, by_upcard) in &stats.by_start {
+        table.push_str(&format!("\n{total:<7}"));
+
+        for upcard in &upcards {
+            match by_upcard.get(upcard).and_then(|c| c.win_rate()) {
+                Some(rate) => table.push_str(&format!("{:>4.0}%", rate * 100.0)),
+                None => table.push_str(&format!("{:>5}", "-")),
+            }
+        }
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use blackjack::lifetime::{LifetimeStats, RoundCounts};
+
+    use super::{sparkline, win_rate_table, SPARKLINE_WIDTH};
+
+    #[test]
+    fn sparklines_are_scaled_between_the_lowest_and_highest_points() {
+        assert_eq!(sparkline(&[100.0, 99.0, 101.0, 107.0], false), "▂▁▃█");
+        assert_eq!(sparkline(&[100.0, 99.0, 101.0, 107.0], true), "._-#");
+        assert_eq!(sparkline(&[5.0; 3], false), "▅▅▅");
+        assert_eq!(sparkline(&[], false), "");
+
+        let long: Vec<f64> = (0..1000).map(f64::from).collect();
+        let line = sparkline(&long, false);
+
+        assert!(line.chars().count() <= SPARKLINE_WIDTH);
+        assert!(line.starts_with('▁'));
+    }
+
+    #[test]
+    fn win_rates_are_laid_out_by_start_and_upcard() {
+        let mut stats = LifetimeStats::default();
+        assert_eq!(win_rate_table(&stats), "No hands have been played yet");
+
+        let counts = |hands, wins| RoundCounts { hands, wins, losses: hands - wins, pushes: 0, net: 0.0 };
+        stats.by_start.insert(12, BTreeMap::from([(10, counts(4, 1)), (1, counts(2, 2))]));
+
+        let table = win_rate_table(&stats);
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines[0].split_whitespace().collect::<Vec<_>>(), ["Start", "2", "3", "4", "5", "6", "7", "8", "9", "10", "A"]);
+        assert_eq!(lines[1].split_whitespace().collect::<Vec<_>>(), ["12", "-", "-", "-", "-", "-", "-", "-", "-", "25%", "100%"]);
+        assert_eq!(lines[0].len(), lines[1].len());
+    }
+}
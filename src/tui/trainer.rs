@@ -0,0 +1,539 @@
+use super::error_popup;
+
+use crate::Config;
+use blackjack::cards::Card;
+use blackjack::count::RunningCount;
+use blackjack::deck::Deck;
+use blackjack::hand::Hand;
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use clap::{Args, ValueEnum};
+use cursive::theme::{PaletteColor, Color, BaseColor};
+use cursive::traits::Resizable;
+use cursive::view::Nameable;
+use cursive::{Cursive, CursiveExt};
+use cursive::views::{Dialog, DummyView, EditView, LinearLayout, TextView};
+use rand::Rng;
+
+/// The kind of drill to practice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Drill {
+    /// Single cards flashed one at a time
+    Flash,
+    /// Two cards at a time, so that cancelling pairs can be spotted
+    Pairs,
+    /// Full rounds with several player hands and the dealer
+    Rounds,
+}
+
+#[derive(Args, Debug, Clone, Copy)]
+pub struct TrainerConfig {
+
+    /// The drill to practice
+    #[arg(short, long, value_enum, default_value_t = Drill::Flash)]
+    drill: Drill,
+
+    /// Time each card (or pair of cards) stays on screen, in milliseconds
+    #[arg(short, long, default_value_t = 1000)]
+    speed: u64,
+
+    /// Number of decks in the shoe
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..))]
+    decks: u8,
+
+    /// Number of player hands dealt in the rounds drill
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=7))]
+    hands: u8,
+
+    /// Chance, in percent, of being quizzed after each deal.
+    /// You will always be quizzed at the end of the shoe
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u8).range(0..=100))]
+    quiz_chance: u8,
+}
+
+/// Which seat at the table receives the next card in the rounds drill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Seat {
+    Player(usize),
+    Dealer,
+}
+
+/// A round being dealt card by card during the rounds drill
+struct TrainerRound {
+    hands: Vec<Hand>,
+    dealer: Hand,
+}
+
+impl TrainerRound {
+    fn new(num_hands: u8) -> Self {
+        Self {
+            hands: (0..num_hands).map(|_| Hand::default()).collect(),
+            dealer: Hand::default(),
+        }
+    }
+
+    /// Returns the seat that should receive the next card,
+    /// or [None] once the round has been played out
+    fn next_seat(&self) -> Option<Seat> {
+        let seats = self.hands.len() + 1;
+        let dealt = self.dealer.cards().len()
+            + self.hands.iter().map(|h| h.cards().len()).sum::<usize>();
+
+        if dealt < 2 * seats {
+            return match dealt % seats {
+                i if i == self.hands.len() => Some(Seat::Dealer),
+                i => Some(Seat::Player(i)),
+            };
+        }
+
+        let upcard = self.dealer.cards().first().map(|c| u8::from(*c)).unwrap_or(10);
+
+        // Players stand on 12 or more against a weak upcard and
+        // draw to 17 otherwise, which is close enough to basic strategy
+        // to produce realistic looking rounds
+        let stand_on = if (2..=6).contains(&upcard) { 12 } else { 17 };
+
+        if let Some(i) = self.hands.iter().position(|h| h.total_value() < stand_on) {
+            return Some(Seat::Player(i));
+        }
+
+        let players_left = self.hands.iter().any(|h| h.total_value() <= 21);
+
+        if players_left && self.dealer.total_value() < 17 {
+            Some(Seat::Dealer)
+        } else {
+            None
+        }
+    }
+
+    fn push(&mut self, seat: Seat, card: Card) {
+        match seat {
+            Seat::Player(i) => self.hands[i].push(card),
+            Seat::Dealer => self.dealer.push(card),
+        }
+    }
+
+    /// Compact text summary of every hand at the table
+    fn summary(&self, ascii: bool) -> String {
+        let fmt_hand = |h: &Hand| {
+            h.cards().iter()
+            .map(|c| if ascii { format!("{c:#}") } else { c.to_string() })
+            .collect::<Vec<_>>()
+            .join(" ")
+        };
+
+        let mut lines = vec![format!("Dealer: {}", fmt_hand(&self.dealer))];
+
+        for (i, h) in self.hands.iter().enumerate() {
+            lines.push(format!("Hand {}: {}", i + 1, fmt_hand(h)));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// The count the player is asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quiz {
+    Running,
+    True,
+}
+
+/// All state needed by a training session
+struct TrainerState {
+    shoe: Deck,
+    count: RunningCount,
+    round: Option<TrainerRound>,
+    awaiting_answer: bool,
+    asked: u32,
+    correct: u32,
+}
+
+impl TrainerState {
+    fn new(decks: u8) -> Self {
+        let mut shoe = Deck::new(decks);
+        shoe.shuffle();
+
+        Self {
+            shoe,
+            count: RunningCount::new(),
+            round: None,
+            awaiting_answer: false,
+            asked: 0,
+            correct: 0,
+        }
+    }
+
+    /// Replace the shoe with a freshly shuffled one and reset the count
+    fn reshuffle(&mut self, decks: u8) {
+        self.shoe = Deck::new(decks);
+        self.shoe.shuffle();
+        self.count = RunningCount::new();
+        self.round = None;
+    }
+
+    fn draw(&mut self) -> Option<Card> {
+        let card = self.shoe.draw()?;
+        self.count.observe(&card);
+
+        Some(card)
+    }
+
+    /// Deal the next card or cards of the drill, keeping the count
+    ///
+    /// Returns [None] if the round being dealt is in an invalid state
+    fn deal(&mut self, trainer_cfg: TrainerConfig) -> Option<Step> {
+        match trainer_cfg.drill {
+            Drill::Flash | Drill::Pairs => {
+                let per_step = if trainer_cfg.drill == Drill::Flash { 1 } else { 2 };
+
+                if self.shoe.remaining() < per_step {
+                    return Some(Step::ShoeFinished);
+                }
+
+                Some(Step::Dealt((0..per_step).filter_map(|_| self.draw()).collect()))
+            },
+            Drill::Rounds => {
+                let needs_new_round = self.round.as_ref()
+                    .map(|r| r.next_seat().is_none())
+                    .unwrap_or(true);
+
+                if needs_new_round {
+                    // Leave a cut card's worth of cards behind so a round
+                    // never runs out of cards part way through
+                    if self.shoe.remaining() < 6 * (usize::from(trainer_cfg.hands) + 1) {
+                        return Some(Step::ShoeFinished);
+                    }
+
+                    self.round = Some(TrainerRound::new(trainer_cfg.hands));
+                }
+
+                let seat = self.round.as_ref().and_then(|r| r.next_seat())?;
+
+                let card = match self.draw() {
+                    Some(c) => c,
+                    None => return Some(Step::ShoeFinished),
+                };
+
+                self.round.as_mut()?.push(seat, card);
+
+                Some(Step::Dealt(vec![card]))
+            }
+        }
+    }
+}
+
+/// What happened during a single step of a drill
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// The cards dealt by the step
+    Dealt(Vec<Card>),
+    ShoeFinished,
+}
+
+/// Start a card counting training session.
+///
+/// Returns when the tui is exited
+pub fn run_trainer(cfg: Config, trainer_cfg: TrainerConfig) {
+    let mut tui = Cursive::default();
+
+    tui.add_global_callback('q', |s| s.quit());
+
+    tui.update_theme(|t| {
+        t.palette[PaletteColor::Background] = Color::Dark(BaseColor::Green);
+    });
+
+    let layout = LinearLayout::vertical()
+    .child(TextView::new("").with_name("trainer_table"))
+    .child(Dialog::text("Cards placeholder").title("Cards").with_name("trainer_cards"))
+    .child(Dialog::text("Press q any time to quit").with_name("trainer_message"))
+    .child(TextView::new("").with_name("trainer_score"));
+
+    tui.add_layer(
+        Dialog::around(layout).title("Count Trainer")
+    );
+
+    tui.set_user_data(TrainerState::new(trainer_cfg.decks));
+
+    update_score(&mut tui);
+    start_dealing(&mut tui, cfg, trainer_cfg);
+
+    tui.run();
+}
+
+/// Spawn a thread that deals the next step of the drill
+/// every time the configured delay elapses, until a quiz is asked
+fn start_dealing(s: &mut Cursive, cfg: Config, trainer_cfg: TrainerConfig) {
+    if let Some(state) = s.user_data::<TrainerState>() {
+        state.awaiting_answer = false;
+    }
+
+    let cb_sink = s.cb_sink().clone();
+
+    std::thread::spawn(move || {
+        let pause_time = Duration::from_millis(trainer_cfg.speed);
+
+        let (tx, rx) = mpsc::channel();
+
+        loop {
+            std::thread::sleep(pause_time);
+
+            match rx.try_recv() {
+                Ok(()) | Err(mpsc::TryRecvError::Disconnected) => {
+                    break
+                },
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+
+            let shutdown_tx = tx.clone();
+
+            let sent = cb_sink.send(Box::new(move |s| {
+                let awaiting = s.user_data::<TrainerState>()
+                    .map(|state| state.awaiting_answer)
+                    .unwrap_or(true);
+
+                if awaiting {
+                    let _ = shutdown_tx.send(());
+                    return;
+                }
+
+                let quiz = match deal_step(s, cfg, trainer_cfg) {
+                    Some(Step::ShoeFinished) => Some((Quiz::Running, true)),
+                    Some(Step::Dealt(_)) => {
+                        let mut rng = rand::thread_rng();
+
+                        if rng.gen_range(0..100) < trainer_cfg.quiz_chance {
+                            let quiz = if rng.gen_bool(0.5) { Quiz::Running } else { Quiz::True };
+
+                            Some((quiz, false))
+                        } else {
+                            None
+                        }
+                    },
+                    None => {
+                        let _ = shutdown_tx.send(());
+                        error_popup(s, "Invalid trainer state");
+
+                        None
+                    }
+                };
+
+                if let Some((quiz, shoe_finished)) = quiz {
+                    let _ = shutdown_tx.send(());
+
+                    ask_count(s, cfg, trainer_cfg, quiz, shoe_finished);
+                }
+            }));
+
+            if sent.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Deal the next card or cards of the drill and update the display
+fn deal_step(s: &mut Cursive, cfg: Config, trainer_cfg: TrainerConfig) -> Option<Step> {
+    let state = s.user_data::<TrainerState>()?;
+
+    let dealt = match state.deal(trainer_cfg)? {
+        Step::Dealt(cards) => cards,
+        Step::ShoeFinished => return Some(Step::ShoeFinished),
+    };
+
+    let table = state.round.as_ref()
+        .map(|r| r.summary(cfg.ascii))
+        .unwrap_or_default();
+
+    let mut card_display = LinearLayout::horizontal();
+
+    for c in &dealt {
+        card_display.add_child(Dialog::text(c.icon(cfg.ascii)));
+        card_display.add_child(DummyView);
+    }
+
+    s.call_on_name("trainer_cards", |d: &mut Dialog| d.set_content(card_display));
+    s.call_on_name("trainer_table", |t: &mut TextView| t.set_content(table));
+
+    update_score(s);
+
+    Some(Step::Dealt(dealt))
+}
+
+/// Pause the drill and ask the player for the current count
+fn ask_count(s: &mut Cursive, cfg: Config, trainer_cfg: TrainerConfig, quiz: Quiz, shoe_finished: bool) {
+    let (expected, remaining) = match s.user_data::<TrainerState>() {
+        Some(state) => {
+            state.awaiting_answer = true;
+
+            (state.count, state.shoe.remaining())
+        },
+        None => return error_popup(s, "Invalid trainer state"),
+    };
+
+    let title = match quiz {
+        Quiz::Running => "What is the running count?",
+        Quiz::True => "What is the true count?",
+    };
+
+    s.add_layer(
+        Dialog::around(
+            EditView::new()
+            .on_submit(move |s, answer| {
+                s.pop_layer();
+
+                score_answer(s, answer, quiz, expected, remaining);
+
+                if shoe_finished {
+                    if let Some(state) = s.user_data::<TrainerState>() {
+                        state.reshuffle(trainer_cfg.decks);
+                    }
+                }
+
+                start_dealing(s, cfg, trainer_cfg);
+            })
+            .fixed_width(10)
+        )
+        .title(title)
+    );
+}
+
+/// Check the player's answer against the actual count,
+/// returning whether it was right and the actual count
+///
+/// True counts are accepted if they are within half a point of
+/// the actual true count, so that either rounding or flooring is fine
+fn check_answer(answer: &str, quiz: Quiz, expected: RunningCount, remaining: usize) -> (bool, String) {
+    match quiz {
+        Quiz::Running => {
+            let actual = expected.running();
+            let correct = answer.trim().parse::<i32>().map(|a| a == actual).unwrap_or(false);
+
+            (correct, actual.to_string())
+        },
+        Quiz::True => {
+            let actual = expected.true_count(remaining);
+            let correct = answer.trim().parse::<f32>()
+                .map(|a| (a - actual).abs() <= 0.5)
+                .unwrap_or(false);
+
+            (correct, format!("{actual:.1}"))
+        }
+    }
+}
+
+/// Score the player's answer and show whether it was right
+fn score_answer(s: &mut Cursive, answer: &str, quiz: Quiz, expected: RunningCount, remaining: usize) {
+    let (correct, actual) = check_answer(answer, quiz, expected, remaining);
+
+    if let Some(state) = s.user_data::<TrainerState>() {
+        state.asked += 1;
+
+        if correct {
+            state.correct += 1;
+        }
+    }
+
+    let msg = if correct {
+        "Correct!".to_string()
+    } else {
+        format!("Not quite, the count was {actual}")
+    };
+
+    s.call_on_name("trainer_message", |d: &mut Dialog| {
+        d.set_content(TextView::new(msg));
+    });
+
+    update_score(s);
+}
+
+fn update_score(s: &mut Cursive) {
+    let score = match s.user_data::<TrainerState>() {
+        Some(state) => format!(
+            "Score: {}/{} | Cards left in shoe: {}",
+            state.correct,
+            state.asked,
+            state.shoe.remaining()
+        ),
+        None => return,
+    };
+
+    s.call_on_name("trainer_score", |t: &mut TextView| t.set_content(score));
+}
+
+#[cfg(test)]
+mod tests {
+    use blackjack::count::RunningCount;
+    use blackjack::deck::Deck;
+
+    use crate::testing::cards;
+
+    use super::{check_answer, Drill, Quiz, Step, TrainerConfig, TrainerState};
+
+    fn config(drill: Drill) -> TrainerConfig {
+        TrainerConfig { drill, speed: 0, decks: 1, hands: 1, quiz_chance: 0 }
+    }
+
+    fn stacked(names: &[&str]) -> TrainerState {
+        let mut state = TrainerState::new(1);
+        state.shoe = Deck::stacked(cards(names));
+
+        state
+    }
+
+    #[test]
+    fn answers_are_checked_against_the_count() {
+        let mut count = RunningCount::new();
+
+        for card in cards(&["2H", "5S", "6D", "KC"]) {
+            count.observe(&card);
+        }
+
+        assert_eq!(check_answer("2", Quiz::Running, count, 52), (true, "2".to_string()));
+        assert_eq!(check_answer(" 2 \n", Quiz::Running, count, 52), (true, "2".to_string()));
+        assert_eq!(check_answer("3", Quiz::Running, count, 52), (false, "2".to_string()));
+        assert_eq!(check_answer("two", Quiz::Running, count, 52), (false, "2".to_string()));
+
+        // With half the deck left, a running count of 2 is a true count of 4
+        assert_eq!(check_answer("4", Quiz::True, count, 26), (true, "4.0".to_string()));
+        assert!(check_answer("3.5", Quiz::True, count, 26).0);
+        assert!(!check_answer("3", Quiz::True, count, 26).0);
+    }
+
+    #[test]
+    fn flash_and_pairs_deal_until_the_shoe_runs_out() {
+        let mut state = stacked(&["2H", "KS", "7D"]);
+
+        assert_eq!(state.deal(config(Drill::Pairs)), Some(Step::Dealt(cards(&["2H", "KS"]))));
+        assert_eq!(state.deal(config(Drill::Pairs)), Some(Step::ShoeFinished));
+        assert_eq!(state.deal(config(Drill::Flash)), Some(Step::Dealt(cards(&["7D"]))));
+        assert_eq!(state.deal(config(Drill::Flash)), Some(Step::ShoeFinished));
+
+        assert_eq!(state.count.running(), 0);
+    }
+
+    #[test]
+    fn rounds_are_dealt_seat_by_seat() {
+        // The player stands on 12 against a 6, and the dealer draws to 21,
+        // which leaves too few cards behind the cut card for another round
+        let mut names = vec!["10H", "6S", "2C", "10D", "5C"];
+        names.extend(["2H"; 11]);
+
+        let mut state = stacked(&names);
+
+        for _ in 0..5 {
+            assert!(matches!(state.deal(config(Drill::Rounds)), Some(Step::Dealt(_))));
+        }
+
+        let round = state.round.as_ref().unwrap();
+
+        assert_eq!(round.hands[0].cards(), &cards(&["10H", "2C"]));
+        assert_eq!(round.dealer.cards(), &cards(&["6S", "10D", "5C"]));
+        assert_eq!(round.next_seat(), None);
+        assert_eq!(state.count.running(), 1);
+
+        assert_eq!(state.deal(config(Drill::Rounds)), Some(Step::ShoeFinished));
+    }
+}
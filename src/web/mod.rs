@@ -0,0 +1,155 @@
+use crate::{Config, RuleArgs};
+use crate::http::{bind, serve, Api};
+
+use blackjack::cards::Card;
+use blackjack::rules::Rules;
+
+use clap::Args;
+use serde_json::{json, Map, Value};
+
+#[derive(Args, Clone, Copy)]
+pub struct WebConfig {
+
+    /// Port to serve the page on. Only connections from this machine are accepted
+    #[arg(short, long, default_value_t = 8000)]
+    port: u16,
+
+    /// Seed for shuffling, to deal the same shoes again
+    #[arg(long)]
+    seed: Option<u64>,
+
+    #[command(flatten)]
+    rules: RuleArgs,
+}
+
+/// The page, with its settings left out
+const PAGE: &str = include_str!("page.html");
+
+const FACES: [&str; 13] = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K"];
+const SUITS: [&str; 4] = ["S", "H", "D", "C"];
+
+/// Serve a page on localhost for playing in a browser, until the process is stopped
+///
+/// The page plays through the same API as `http`, at a table opened when it loads
+pub fn run_web(cfg: Config, web_cfg: WebConfig) {
+    let Some(server) = bind(web_cfg.port) else { return };
+
+    println!("Play at http://127.0.0.1:{} in your browser", web_cfg.port);
+
+    serve(server, &mut Api::default(), Some(&page(cfg, web_cfg)));
+}
+
+/// The page with the table to open and the art for every card filled in
+fn page(cfg: Config, web_cfg: WebConfig) -> String {
+    PAGE.replace("/* SETTINGS */", &settings(cfg, web_cfg).to_string())
+}
+
+fn settings(cfg: Config, web_cfg: WebConfig) -> Value {
+    let icons: Map<String, Value> = FACES.iter()
+        .flat_map(|face| SUITS.iter().map(move |suit| format!("{face}{suit}")))
+        .map(|name| {
+            let card: Card = name.parse().expect("Every face and suit make a card");
+            let icon = card.icon(cfg.ascii);
+
+            (name, Value::String(icon))
+        })
+        .collect();
+
+    json!({
+        "table": {
+            "rules": Rules::from(web_cfg.rules),
+            "bankroll": cfg.bankroll,
+            "seed": web_cfg.seed,
+        },
+        "icons": icons,
+        "flipped": Card::flipped_icon(cfg.ascii),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+
+    use blackjack::cards::Card;
+    use tiny_http::Server;
+
+    use crate::{Config, RuleArgs};
+    use crate::http::{serve, Api};
+
+    use super::{page, settings, WebConfig};
+
+    fn configs(ascii: bool) -> (Config, WebConfig) {
+        let cfg = Config {
+            ascii,
+            cli: false,
+            show_ev: false,
+            hints: false,
+            bot_protocol: false,
+            bankroll: 40.0,
+        };
+
+        let rules = RuleArgs {
+            decks: 2,
+            h17: true,
+            no_peek: false,
+            blackjack_payout: 1.5,
+            penetration: 0.75,
+        };
+
+        (cfg, WebConfig { port: 0, seed: Some(3), rules })
+    }
+
+    #[test]
+    fn every_card_is_drawn_as_in_the_terminal() {
+        for ascii in [false, true] {
+            let (cfg, web_cfg) = configs(ascii);
+            let settings = settings(cfg, web_cfg);
+
+            let icons = settings["icons"].as_object().unwrap();
+            assert_eq!(icons.len(), 52);
+
+            for (name, icon) in icons {
+                let card: Card = name.parse().unwrap();
+                assert_eq!(icon, &card.icon(ascii));
+            }
+
+            assert_eq!(settings["flipped"], Card::flipped_icon(ascii));
+            assert_eq!(settings["table"]["bankroll"], 40.0);
+            assert_eq!(settings["table"]["rules"]["decks"], 2);
+        }
+    }
+
+    #[test]
+    fn the_page_is_served_with_the_api() {
+        let (cfg, web_cfg) = configs(false);
+        let page = page(cfg, web_cfg);
+
+        assert!(!page.contains("/* SETTINGS */"));
+
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+
+        let served = page.clone();
+        thread::spawn(move || serve(server, &mut Api::default(), Some(&served)));
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {path} HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n").unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+
+            response
+        };
+
+        let response = get("/");
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("text/html"));
+        assert!(response.ends_with(&page));
+
+        let response = get("/tables");
+        assert!(response.contains(r#"{"tables":[]}"#), "{response}");
+    }
+}